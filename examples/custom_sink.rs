@@ -0,0 +1,77 @@
+//! Send timeline items to a custom destination, here a tab separated table on stdout
+//!
+//! ```sh
+//! cargo run --example custom_sink -- https://nitter.net jack
+//! ```
+
+use std::io::Write;
+
+use futures_util::StreamExt;
+use nitter_scraper::{NitterQuery, NitterScraper, TimelineItem};
+use reqwest::Client;
+
+/// Anything that can consume timeline items, e.g. a database or a message queue
+trait Sink {
+    fn write(&mut self, item: &TimelineItem) -> std::io::Result<()>;
+}
+
+struct TsvSink<W: Write>(W);
+
+impl<W: Write> Sink for TsvSink<W> {
+    fn write(&mut self, item: &TimelineItem) -> std::io::Result<()> {
+        match item {
+            TimelineItem::Tweet(tweet) => writeln!(
+                self.0,
+                "{}\t{}\t{}\t{}",
+                tweet.id,
+                tweet.user.screen_name,
+                tweet.stats.heart,
+                tweet.full_text.replace(['\t', '\n'], " ")
+            ),
+            TimelineItem::Unavailable(unavailable) => {
+                writeln!(self.0, "-\t-\t-\t[{}]", unavailable.reason)
+            }
+            // Skip kinds of items added in later versions
+            _ => Ok(()),
+        }
+    }
+}
+
+#[tokio::main]
+async fn main() {
+    let mut args = std::env::args().skip(1);
+    let (Some(instance), Some(user)) = (args.next(), args.next()) else {
+        eprintln!("usage: custom_sink <INSTANCE> <USER>");
+        std::process::exit(2);
+    };
+
+    let client = Client::new();
+    let scraper = NitterScraper::builder()
+        .client(&client)
+        .instance(instance)
+        .query(NitterQuery::User { user })
+        .limit(Some(50))
+        .try_build()
+        .unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            std::process::exit(2);
+        });
+
+    let mut sink = TsvSink(std::io::stdout().lock());
+    if let Err(e) = drain(&scraper, &mut sink).await {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+}
+
+async fn drain(
+    scraper: &NitterScraper<'_>,
+    sink: &mut impl Sink,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let stream = scraper.search_items().await;
+    futures_util::pin_mut!(stream);
+    while let Some(item) = stream.next().await {
+        sink.write(&item?)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,64 @@
+//! Follow a user, printing new tweets and the recent ones that disappear, e.g. because they
+//! were deleted
+//!
+//! ```sh
+//! cargo run --example deletions -- https://nitter.net jack
+//! ```
+
+use std::time::Duration;
+
+use futures_util::StreamExt;
+use nitter_scraper::{NitterQuery, NitterScraper, RecheckWindow};
+use reqwest::Client;
+
+#[tokio::main]
+async fn main() {
+    let mut args = std::env::args().skip(1);
+    let (Some(instance), Some(user)) = (args.next(), args.next()) else {
+        eprintln!("usage: deletions <INSTANCE> <USER>");
+        std::process::exit(2);
+    };
+
+    let client = Client::new();
+    // Keep re-checking the 50 newest tweets, 5 of them after each poll
+    let mut window = RecheckWindow::new(50);
+    let mut min_id = None;
+    loop {
+        let scraper = NitterScraper::builder()
+            .client(&client)
+            .instance(instance.as_str())
+            .query(NitterQuery::User { user: user.clone() })
+            .reorder_pinned(true)
+            .limit(Some(20))
+            .min_id(min_id)
+            .try_build()
+            .unwrap_or_else(|e| {
+                eprintln!("{}", e);
+                std::process::exit(2);
+            });
+
+        let mut new = vec![];
+        {
+            let stream = scraper.search().await;
+            futures_util::pin_mut!(stream);
+            while let Some(result) = stream.next().await {
+                match result {
+                    Ok(tweet) => new.push(tweet),
+                    Err(e) => eprintln!("{}", e),
+                }
+            }
+        }
+
+        // Tweets are returned newest first, the window is filled oldest first
+        for tweet in new.iter().rev() {
+            println!("new {}: {}", tweet.id, tweet.full_text);
+            min_id = min_id.max(Some(tweet.id + 1));
+            window.push(tweet);
+        }
+
+        for tweet in scraper.recheck(&mut window, 5).await {
+            println!("disappeared {}", tweet.id);
+        }
+        tokio::time::sleep(Duration::from_secs(60)).await;
+    }
+}
@@ -0,0 +1,72 @@
+//! Download the original size images of tweets matching a search
+//!
+//! ```sh
+//! cargo run --example download_media -- https://nitter.net "from:jack filter:images" images/
+//! ```
+
+use std::path::Path;
+
+use futures_util::StreamExt;
+use nitter_scraper::{ImageQuality, NitterQuery, NitterScraper};
+use reqwest::Client;
+
+#[tokio::main]
+async fn main() {
+    let mut args = std::env::args().skip(1);
+    let (Some(instance), Some(query), Some(dir)) = (args.next(), args.next(), args.next()) else {
+        eprintln!("usage: download_media <INSTANCE> <QUERY> <DIRECTORY>");
+        std::process::exit(2);
+    };
+    std::fs::create_dir_all(&dir).unwrap();
+
+    let client = Client::new();
+    let scraper = NitterScraper::builder()
+        .client(&client)
+        .instance(instance)
+        .query(NitterQuery::Search { query })
+        .image_quality(ImageQuality::Orig)
+        .limit(Some(20))
+        .try_build()
+        .unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            std::process::exit(2);
+        });
+
+    let stream = scraper.search().await;
+    futures_util::pin_mut!(stream);
+    while let Some(result) = stream.next().await {
+        let tweet = match result {
+            Ok(tweet) => tweet,
+            Err(e) => {
+                eprintln!("{}", e);
+                break;
+            }
+        };
+
+        for (i, url) in tweet.images.iter().enumerate() {
+            // e.g. https://pbs.twimg.com/media/F1234.jpg?name=orig
+            let extension = url
+                .split('?')
+                .next()
+                .and_then(|path| Path::new(path).extension())
+                .and_then(|ext| ext.to_str())
+                // Keep file names valid on Windows, which reserves characters such as ':' and '?'
+                .filter(|ext| ext.chars().all(|c| c.is_ascii_alphanumeric()))
+                .unwrap_or("jpg");
+            let path = Path::new(&dir).join(format!("{}_{}.{}", tweet.id, i + 1, extension));
+
+            match download(&client, url).await {
+                Ok(bytes) => {
+                    std::fs::write(&path, bytes).unwrap();
+                    println!("{}", path.display());
+                }
+                Err(e) => eprintln!("{}: {}", url, e),
+            }
+        }
+    }
+}
+
+async fn download(client: &Client, url: &str) -> reqwest::Result<Vec<u8>> {
+    let response = client.get(url).send().await?.error_for_status()?;
+    Ok(response.bytes().await?.to_vec())
+}
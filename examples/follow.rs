@@ -0,0 +1,64 @@
+//! Print new tweets from a user, remembering the newest tweet seen between runs
+//!
+//! ```sh
+//! cargo run --example follow -- https://nitter.net jack checkpoint.txt
+//! ```
+
+use std::fs;
+
+use futures_util::StreamExt;
+use nitter_scraper::{NitterQuery, NitterScraper};
+use reqwest::Client;
+
+#[tokio::main]
+async fn main() {
+    let mut args = std::env::args().skip(1);
+    let (Some(instance), Some(user), Some(checkpoint)) = (args.next(), args.next(), args.next())
+    else {
+        eprintln!("usage: follow <INSTANCE> <USER> <CHECKPOINT>");
+        std::process::exit(2);
+    };
+
+    // Only return tweets newer than the last run's newest tweet
+    let min_id = fs::read_to_string(&checkpoint)
+        .ok()
+        .and_then(|s| s.trim().parse::<u128>().ok())
+        .map(|id| id + 1);
+
+    let client = Client::new();
+    let scraper = NitterScraper::builder()
+        .client(&client)
+        .instance(instance)
+        .query(NitterQuery::User { user })
+        .reorder_pinned(true)
+        .min_id(min_id)
+        .try_build()
+        .unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            std::process::exit(2);
+        });
+
+    let mut newest = None;
+    let stream = scraper.search().await;
+    futures_util::pin_mut!(stream);
+    while let Some(result) = stream.next().await {
+        let tweet = match result {
+            Ok(tweet) => tweet,
+            Err(e) => {
+                eprintln!("{}", e);
+                break;
+            }
+        };
+        println!("{}: {}", tweet.id, tweet.full_text);
+        newest = newest.max(Some(tweet.id));
+    }
+
+    // Save the checkpoint even after an error, tweets are returned newest first
+    if let Some(id) = newest {
+        // Write to a temporary file and rename it, a crash mid-write would otherwise leave an
+        // empty checkpoint and the next run would start over
+        let tmp = format!("{}.tmp", checkpoint);
+        fs::write(&tmp, id.to_string()).unwrap();
+        fs::rename(&tmp, &checkpoint).unwrap();
+    }
+}
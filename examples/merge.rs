@@ -0,0 +1,41 @@
+//! Merge the timelines of several accounts into one stream, with each tweet printed once
+//!
+//! ```sh
+//! cargo run --example merge -- https://nitter.net jack elonmusk
+//! ```
+
+use std::collections::HashSet;
+
+use futures_util::StreamExt;
+use nitter_scraper::MultiScraper;
+use reqwest::Client;
+
+#[tokio::main]
+async fn main() {
+    let mut args = std::env::args().skip(1);
+    let Some(instance) = args.next() else {
+        eprintln!("usage: merge <INSTANCE> <USER>...");
+        std::process::exit(2);
+    };
+
+    let client = Client::new();
+    let scraper = MultiScraper::users(&client, &instance, args, Some(100)).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        std::process::exit(2);
+    });
+
+    // Retweets can show up in more than one timeline
+    let mut seen = HashSet::new();
+    let stream = scraper.search().await;
+    futures_util::pin_mut!(stream);
+    while let Some((query, result)) = stream.next().await {
+        match result {
+            Ok(tweet) if seen.insert(tweet.id) => {
+                println!("{}", serde_json::to_string(&tweet).unwrap())
+            }
+            Ok(_) => {}
+            // The other accounts are still scraped
+            Err(e) => eprintln!("{}: {}", query, e),
+        }
+    }
+}
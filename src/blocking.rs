@@ -0,0 +1,129 @@
+//! Synchronous API for callers without an async runtime, such as simple tools and build scripts
+//!
+//! Every call blocks the current thread until it's done, so it mustn't be used from within an
+//! async runtime.
+
+use std::pin::Pin;
+
+use futures_util::{Stream, StreamExt};
+use tokio::runtime::{Builder, Runtime};
+
+use crate::coverage::FieldCoverage;
+use crate::error::NitterError;
+use crate::nitter_scraper::{FinishReason, NitterScraper, ResumeToken};
+use crate::stats::ScrapeStats;
+use crate::tweet::{Profile, TimelineItem, Tweet};
+
+/// Wraps a [`NitterScraper`], driving its requests on a runtime of its own
+pub struct BlockingNitterScraper<'a> {
+    scraper: NitterScraper<'a>,
+    runtime: Runtime,
+}
+
+impl<'a> BlockingNitterScraper<'a> {
+    pub fn new(scraper: NitterScraper<'a>) -> std::io::Result<Self> {
+        let runtime = Builder::new_current_thread().enable_all().build()?;
+        Ok(Self { scraper, runtime })
+    }
+
+    /// See [`NitterScraper::search`], each tweet is fetched as the iterator is advanced
+    pub fn search(&self) -> Iter<'_, Tweet> {
+        let stream = self.runtime.block_on(self.scraper.search());
+        Iter {
+            runtime: &self.runtime,
+            stream: Box::pin(stream),
+        }
+    }
+
+    /// See [`NitterScraper::search_items`]
+    pub fn search_items(&self) -> Iter<'_, TimelineItem> {
+        let stream = self.runtime.block_on(self.scraper.search_items());
+        Iter {
+            runtime: &self.runtime,
+            stream: Box::pin(stream),
+        }
+    }
+
+    /// See [`NitterScraper::fetch_all`]
+    pub fn fetch_all(&self) -> Result<Vec<Tweet>, NitterError> {
+        self.runtime.block_on(self.scraper.fetch_all())
+    }
+
+    /// See [`NitterScraper::fetch_page`]
+    pub fn fetch_page(&self) -> Result<Vec<Tweet>, NitterError> {
+        self.runtime.block_on(self.scraper.fetch_page())
+    }
+
+    /// See [`NitterScraper::profile`]
+    pub fn profile(&self, user: &str) -> Result<Profile, NitterError> {
+        self.runtime.block_on(self.scraper.profile(user))
+    }
+
+    /// See [`NitterScraper::instance_version`]
+    pub fn instance_version(&self) -> Result<Option<String>, NitterError> {
+        self.runtime.block_on(self.scraper.instance_version())
+    }
+
+    pub fn finish_reason(&self) -> Option<FinishReason> {
+        self.scraper.finish_reason()
+    }
+
+    pub fn resume_token(&self) -> Option<ResumeToken> {
+        self.scraper.resume_token()
+    }
+
+    pub fn stats(&self) -> ScrapeStats {
+        self.scraper.stats()
+    }
+
+    pub fn field_coverage(&self) -> FieldCoverage {
+        self.scraper.field_coverage()
+    }
+
+    pub fn into_inner(self) -> NitterScraper<'a> {
+        self.scraper
+    }
+}
+
+/// Results of a search, fetched as they're iterated
+pub struct Iter<'s, T> {
+    runtime: &'s Runtime,
+    stream: Pin<Box<dyn Stream<Item = Result<T, NitterError>> + 's>>,
+}
+
+impl<T> Iterator for Iter<'_, T> {
+    type Item = Result<T, NitterError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.runtime.block_on(self.stream.next())
+    }
+}
+
+#[cfg(all(test, feature = "reqwest"))]
+mod test {
+    use reqwest::Client;
+
+    use super::*;
+    use crate::nitter_scraper::NitterQuery;
+
+    #[test]
+    fn test_blocking_search() {
+        let client = Client::new();
+        let scraper = NitterScraper::builder()
+            .client(&client)
+            // Nothing listens on port 1, the connection is refused
+            .instance("http://127.0.0.1:1")
+            .query(NitterQuery::User {
+                user: "jack".into(),
+            })
+            .try_build()
+            .unwrap();
+        let scraper = BlockingNitterScraper::new(scraper).unwrap();
+
+        let mut tweets = scraper.search();
+        assert!(matches!(tweets.next(), Some(Err(NitterError::Connect(_)))));
+        assert!(tweets.next().is_none());
+        drop(tweets);
+        assert_eq!(scraper.finish_reason(), Some(FinishReason::Error));
+    }
+}
@@ -0,0 +1,299 @@
+use std::collections::HashMap;
+#[cfg(feature = "fs")]
+use std::fs;
+#[cfg(feature = "fs")]
+use std::io;
+#[cfg(feature = "fs")]
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+#[cfg(feature = "fs")]
+use std::time::{Duration, SystemTime};
+
+use http::header::{
+    HeaderMap, HeaderName, HeaderValue, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED,
+};
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "fs")]
+use sha2::{Digest, Sha256};
+use tokio_util::task::TaskTracker;
+
+#[cfg(feature = "fs")]
+use crate::atomic::write_atomic;
+
+/// Headers stored with cached responses, the validators and the RSS feed's cursor
+const KEPT_HEADERS: [&str; 3] = ["etag", "last-modified", "min-id"];
+
+/// Cache of instance responses, revalidated with conditional requests so unchanged pages aren't
+/// downloaded again, e.g. when polling a profile
+///
+/// Only responses with an `ETag` or `Last-Modified` header are cached, along with their
+/// validators and the headers the scraper reads, not cookies or other headers. The in-memory cache keeps
+/// every cached URL until it's dropped, the directory cache until it's [pruned](Self::prune).
+#[derive(Debug)]
+pub struct ResponseCache {
+    store: Store,
+    /// Cache files being written
+    writes: TaskTracker,
+}
+
+#[derive(Debug)]
+enum Store {
+    Memory(Mutex<HashMap<String, CachedResponse>>),
+    #[cfg(feature = "fs")]
+    Directory(PathBuf),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct CachedResponse {
+    url: String,
+    headers: Vec<(String, String)>,
+    body: String,
+}
+
+impl ResponseCache {
+    pub fn in_memory() -> Self {
+        Self {
+            store: Store::Memory(Mutex::new(HashMap::new())),
+            writes: TaskTracker::new(),
+        }
+    }
+
+    /// Cache responses as files in a directory, which is created when needed, so they persist
+    /// across runs
+    ///
+    /// Reading or writing the cache files is best effort, failures cause pages to be downloaded.
+    #[cfg(feature = "fs")]
+    pub fn directory(path: impl Into<PathBuf>) -> Self {
+        Self {
+            store: Store::Directory(path.into()),
+            writes: TaskTracker::new(),
+        }
+    }
+
+    pub(crate) async fn get(&self, url: &str) -> Option<CachedResponse> {
+        match &self.store {
+            Store::Memory(responses) => responses.lock().unwrap().get(url).cloned(),
+            #[cfg(feature = "fs")]
+            Store::Directory(dir) => {
+                let path = dir.join(file_name(url));
+                let json = tokio::task::spawn_blocking(move || fs::read(path))
+                    .await
+                    .ok()?
+                    .ok()?;
+                serde_json::from_slice::<CachedResponse>(&json)
+                    .ok()
+                    // Guard against hash collisions
+                    .filter(|cached| cached.url == url)
+            }
+        }
+    }
+
+    pub(crate) async fn put(&self, url: &str, headers: &HeaderMap, body: &str) {
+        if !headers.contains_key(ETAG) && !headers.contains_key(LAST_MODIFIED) {
+            return;
+        }
+        let cached = CachedResponse {
+            url: url.to_owned(),
+            headers: headers
+                .iter()
+                .filter(|(name, _)| KEPT_HEADERS.contains(&name.as_str()))
+                .filter_map(|(name, value)| {
+                    Some((name.to_string(), value.to_str().ok()?.to_owned()))
+                })
+                .collect(),
+            body: body.to_owned(),
+        };
+        match &self.store {
+            Store::Memory(responses) => {
+                responses.lock().unwrap().insert(url.to_owned(), cached);
+            }
+            #[cfg(feature = "fs")]
+            Store::Directory(dir) => {
+                let dir = dir.clone();
+                let path = dir.join(file_name(url));
+                let json = serde_json::to_vec(&cached).unwrap();
+                let _ = self
+                    .writes
+                    .spawn_blocking(move || {
+                        fs::create_dir_all(dir).and_then(|_| write_atomic(&path, &json))
+                    })
+                    .await;
+            }
+        }
+    }
+
+    /// Remove the cache files last written more than `max_age` ago, then the oldest ones until
+    /// the rest take at most `max_size` bytes, returning the number of files removed
+    ///
+    /// Does nothing for the in-memory cache.
+    #[cfg(feature = "fs")]
+    pub async fn prune(
+        &self,
+        max_age: Option<Duration>,
+        max_size: Option<u64>,
+    ) -> io::Result<usize> {
+        match &self.store {
+            Store::Memory(_) => Ok(0),
+            Store::Directory(dir) => {
+                let dir = dir.clone();
+                tokio::task::spawn_blocking(move || prune_dir(&dir, max_age, max_size))
+                    .await
+                    .map_err(io::Error::other)?
+            }
+        }
+    }
+
+    /// Wait for the cache files being written, e.g. by a search that was dropped
+    pub(crate) async fn wait_for_writes(&self) {
+        self.writes.close();
+        self.writes.wait().await;
+        self.writes.reopen();
+    }
+}
+
+impl CachedResponse {
+    /// Add the headers that make a request conditional on the response having changed
+    pub(crate) fn add_validators(&self, headers: &mut HeaderMap) {
+        let cached = self.headers();
+        if let Some(etag) = cached.get(ETAG) {
+            headers.insert(IF_NONE_MATCH, etag.clone());
+        }
+        if let Some(last_modified) = cached.get(LAST_MODIFIED) {
+            headers.insert(IF_MODIFIED_SINCE, last_modified.clone());
+        }
+    }
+
+    pub(crate) fn headers(&self) -> HeaderMap {
+        self.headers
+            .iter()
+            .filter_map(|(name, value)| {
+                Some((
+                    HeaderName::from_bytes(name.as_bytes()).ok()?,
+                    HeaderValue::from_str(value).ok()?,
+                ))
+            })
+            .collect()
+    }
+
+    pub(crate) fn into_body(self) -> String {
+        self.body
+    }
+}
+
+#[cfg(feature = "fs")]
+fn prune_dir(dir: &Path, max_age: Option<Duration>, max_size: Option<u64>) -> io::Result<usize> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(0),
+        Err(e) => return Err(e),
+    };
+    let mut files = vec![];
+    for entry in entries {
+        let entry = entry?;
+        let metadata = entry.metadata()?;
+        let path = entry.path();
+        if metadata.is_file() && path.extension().is_some_and(|ext| ext == "json") {
+            files.push((metadata.modified()?, metadata.len(), path));
+        }
+    }
+    // Newest first, so the oldest files are the ones over the size limit
+    files.sort_by_key(|(modified, _, _)| std::cmp::Reverse(*modified));
+
+    let now = SystemTime::now();
+    let mut size = 0;
+    let mut removed = 0;
+    for (modified, len, path) in files {
+        let expired = max_age
+            .is_some_and(|max_age| now.duration_since(modified).is_ok_and(|age| age > max_age));
+        size += len;
+        if expired || max_size.is_some_and(|max_size| size > max_size) {
+            fs::remove_file(path)?;
+            size -= len;
+            removed += 1;
+        }
+    }
+    Ok(removed)
+}
+
+#[cfg(feature = "fs")]
+fn file_name(url: &str) -> String {
+    let hash: String = Sha256::digest(url.as_bytes())
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect();
+    format!("{}.json", hash)
+}
+
+#[cfg(all(test, feature = "fs"))]
+mod test {
+    use http::header::SET_COOKIE;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_response_cache() {
+        let dir = std::env::temp_dir().join(format!("cache-test-{}", std::process::id()));
+        let url = "https://nitter.net/jack/rss";
+        let mut headers = HeaderMap::new();
+        headers.insert("min-id", HeaderValue::from_static("123"));
+        headers.insert(SET_COOKIE, HeaderValue::from_static("session=1"));
+
+        for cache in [ResponseCache::in_memory(), ResponseCache::directory(&dir)] {
+            // Responses that can't be revalidated aren't cached
+            cache.put(url, &headers, "old").await;
+            assert!(cache.get(url).await.is_none());
+
+            headers.insert(ETAG, HeaderValue::from_static("\"abc\""));
+            cache.put(url, &headers, "body").await;
+            let cached = cache.get(url).await.unwrap();
+            assert_eq!(cached.headers()["min-id"], "123");
+            // Cookies aren't written to the cache
+            assert!(!cached.headers().contains_key(SET_COOKIE));
+            assert!(cache.get("https://nitter.net/jack").await.is_none());
+
+            let mut request = HeaderMap::new();
+            cached.add_validators(&mut request);
+            assert_eq!(request[IF_NONE_MATCH], "\"abc\"");
+            assert!(!request.contains_key(IF_MODIFIED_SINCE));
+            assert_eq!(cached.into_body(), "body");
+            headers.remove(ETAG);
+        }
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_prune() {
+        let dir = std::env::temp_dir().join(format!("cache-prune-test-{}", std::process::id()));
+        let cache = ResponseCache::directory(&dir);
+        assert_eq!(cache.prune(None, Some(0)).await.unwrap(), 0);
+
+        let mut headers = HeaderMap::new();
+        headers.insert(ETAG, HeaderValue::from_static("\"abc\""));
+        let urls = ["https://nitter.net/a", "https://nitter.net/b"];
+        for (url, age) in urls.iter().zip([10, 1000]) {
+            cache.put(url, &headers, "body").await;
+            let modified = SystemTime::now() - Duration::from_secs(age);
+            let file = fs::File::options()
+                .write(true)
+                .open(dir.join(file_name(url)))
+                .unwrap();
+            file.set_modified(modified).unwrap();
+        }
+        let size = fs::metadata(dir.join(file_name(urls[0]))).unwrap().len();
+
+        // Within both limits nothing is removed
+        let prune = cache.prune(Some(Duration::from_secs(2000)), Some(2 * size));
+        assert_eq!(prune.await.unwrap(), 0);
+        // The older file is removed first to fit the size
+        assert_eq!(cache.prune(None, Some(size)).await.unwrap(), 1);
+        assert!(cache.get(urls[0]).await.is_some());
+        assert!(cache.get(urls[1]).await.is_none());
+        // Then by age
+        let prune = cache.prune(Some(Duration::from_secs(5)), None);
+        assert_eq!(prune.await.unwrap(), 1);
+        assert!(cache.get(urls[0]).await.is_none());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,14 @@
+pub mod archive;
+pub mod compare;
+pub mod completeness;
+pub mod date;
+pub mod format;
+pub mod headers;
+pub mod health;
+pub mod manifest;
+pub mod redact;
+pub mod resume;
+pub mod select;
+pub mod stats;
+pub mod status;
+pub mod template;
@@ -0,0 +1,36 @@
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+use serde_json::Value;
+
+/// Read every tweet of a JSONL archive, or stdin if the path is "-"
+///
+/// Tweets are read as JSON values rather than `Tweet`s so that archives written by older versions
+/// with fewer fields can still be read.
+pub fn read_archive(path: &Path) -> Result<Vec<Value>, String> {
+    let reader: Box<dyn BufRead> = if path == Path::new("-") {
+        Box::new(std::io::stdin().lock())
+    } else {
+        let file =
+            File::open(path).map_err(|e| format!("unable to open {}: {}", path.display(), e))?;
+        Box::new(BufReader::new(file))
+    };
+
+    let mut tweets = vec![];
+    for (i, line) in reader.lines().enumerate() {
+        let line = line.map_err(|e| format!("unable to read {}: {}", path.display(), e))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let tweet: Value = serde_json::from_str(&line)
+            .map_err(|e| format!("{}:{}: invalid tweet: {}", path.display(), i + 1, e))?;
+
+        // Skip unavailable tweet placeholders and user search results, only they have a type
+        if tweet.get("type").is_some() {
+            continue;
+        }
+        tweets.push(tweet);
+    }
+    Ok(tweets)
+}
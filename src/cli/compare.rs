@@ -0,0 +1,132 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+use std::path::Path;
+use std::process::ExitCode;
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde_json::Value;
+
+use super::archive::read_archive;
+
+const MAX_EXAMPLES: usize = 10;
+
+pub fn print_comparison(a: &Path, b: &Path) -> ExitCode {
+    let (tweets_a, tweets_b) = match (read_archive(a), read_archive(b)) {
+        (Ok(tweets_a), Ok(tweets_b)) => (tweets_a, tweets_b),
+        (Err(e), _) | (_, Err(e)) => {
+            eprintln!("{}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let ids_a: HashMap<_, _> = tweets_a.iter().filter_map(|t| Some((id(t)?, t))).collect();
+    let ids_b: HashMap<_, _> = tweets_b.iter().filter_map(|t| Some((id(t)?, t))).collect();
+    println!("{}: {} tweets", a.display(), ids_a.len());
+    println!("{}: {} tweets", b.display(), ids_b.len());
+
+    // Overlapping tweet IDs, split by whether either side saw it as a retweet
+    let mut overlap = vec![];
+    let mut retweets = vec![];
+    for (id, tweet_a) in &ids_a {
+        if let Some(tweet_b) = ids_b.get(id) {
+            if is_retweet(tweet_a) != is_retweet(tweet_b) {
+                retweets.push((id, *tweet_a, *tweet_b));
+            } else {
+                overlap.push(id);
+            }
+        }
+    }
+    overlap.sort_unstable();
+    retweets.sort_unstable_by_key(|r| r.0);
+
+    println!();
+    println!("Overlapping tweet IDs: {}", overlap.len() + retweets.len());
+    for id in overlap.iter().take(MAX_EXAMPLES) {
+        println!("  {}", id);
+    }
+
+    println!();
+    println!("Retweet relationships: {}", retweets.len());
+    for (id, tweet_a, tweet_b) in retweets.iter().take(MAX_EXAMPLES) {
+        let (original, retweeted) = if is_retweet(tweet_a) {
+            (tweet_b, tweet_a)
+        } else {
+            (tweet_a, tweet_b)
+        };
+        println!(
+            "  {} by @{} retweeted by {}",
+            id,
+            screen_name(original).unwrap_or_default(),
+            retweeted
+                .get("retweeted_by")
+                .and_then(|r| r.as_str())
+                .map_or("unknown".to_owned(), |r| format!("@{}", r))
+        );
+    }
+
+    // Different tweets with the same normalized text
+    let mut texts_a: HashMap<u64, Vec<u128>> = HashMap::new();
+    for (id, tweet) in &ids_a {
+        if let Some(hash) = text_hash(tweet) {
+            texts_a.entry(hash).or_default().push(*id);
+        }
+    }
+    let mut duplicates: HashSet<(u128, u128)> = HashSet::new();
+    for (id_b, tweet) in &ids_b {
+        let Some(same_text) = text_hash(tweet).and_then(|hash| texts_a.get(&hash)) else {
+            continue;
+        };
+        for id_a in same_text {
+            if id_a != id_b {
+                duplicates.insert((*id_a, *id_b));
+            }
+        }
+    }
+    let mut duplicates: Vec<_> = duplicates.into_iter().collect();
+    duplicates.sort_unstable();
+
+    println!();
+    println!("Near-duplicate texts: {}", duplicates.len());
+    for (id_a, id_b) in duplicates.iter().take(MAX_EXAMPLES) {
+        println!("  {} ~ {}", id_a, id_b);
+    }
+
+    ExitCode::SUCCESS
+}
+
+fn id(tweet: &Value) -> Option<u128> {
+    tweet.get("id_str")?.as_str()?.parse().ok()
+}
+
+fn is_retweet(tweet: &Value) -> bool {
+    tweet
+        .get("retweet")
+        .and_then(|r| r.as_bool())
+        .unwrap_or(false)
+}
+
+fn screen_name(tweet: &Value) -> Option<&str> {
+    tweet.get("user")?.get("screen_name")?.as_str()
+}
+
+/// Hash of the tweet text ignoring case, links, punctuation, and whitespace
+fn text_hash(tweet: &Value) -> Option<u64> {
+    static LINK_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"https?://\S+").unwrap());
+
+    let text = tweet.get("full_text")?.as_str()?;
+    let text = LINK_RE.replace_all(text, "");
+    let normalized: String = text
+        .chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(|c| c.to_lowercase())
+        .collect();
+    if normalized.is_empty() {
+        return None;
+    }
+
+    let mut hasher = DefaultHasher::new();
+    normalized.hash(&mut hasher);
+    Some(hasher.finish())
+}
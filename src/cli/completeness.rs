@@ -0,0 +1,111 @@
+use nitter_scraper::NitterQuery;
+
+use crate::Args;
+
+/// Number of most recent tweets Twitter serves on a timeline, older ones are only found by search
+const TIMELINE_CAP: u64 = 3200;
+
+/// Retrieved tweets compared with the tweet count stated on the account's profile
+#[derive(Debug)]
+pub struct Completeness {
+    retrieved: u64,
+    stated: u64,
+    gaps: Vec<&'static str>,
+}
+
+/// Screen name of the account whose whole timeline `query` scrapes
+pub fn timeline_user(query: &NitterQuery) -> Option<&str> {
+    match query {
+        NitterQuery::User { user } | NitterQuery::UserWithReplies { user } => Some(user),
+        _ => None,
+    }
+}
+
+/// Estimate how much of a timeline was retrieved, and why the rest may be missing
+pub fn estimate(
+    args: &Args,
+    query: &NitterQuery,
+    min_id: Option<u128>,
+    max_id: Option<u128>,
+    retrieved: u64,
+    stated: u64,
+) -> Completeness {
+    let mut gaps = Vec::new();
+    if retrieved < stated {
+        if args.limit.is_some_and(|limit| retrieved >= limit as u64) {
+            gaps.push("the scrape stopped at --limit");
+        }
+        if args.max_pages.is_some() {
+            gaps.push("the scrape may have stopped at --max-pages");
+        }
+        if min_id.is_some() {
+            gaps.push("tweets older than --min-id or the resumed output weren't requested");
+        }
+        if max_id.is_some() {
+            gaps.push("tweets newer than --max-id or the resumed output weren't requested");
+        }
+        if args.since.is_some() || args.until.is_some() {
+            gaps.push("tweets outside --since and --until weren't requested");
+        }
+        if retrieved + TIMELINE_CAP / 10 >= TIMELINE_CAP {
+            gaps.push("timelines only go back about 3200 tweets, search for older ones");
+        }
+        if matches!(query, NitterQuery::User { .. }) {
+            gaps.push("replies are only listed on the with_replies timeline");
+        }
+        if args.skip_retweets {
+            gaps.push("retweets were skipped");
+        }
+    }
+    Completeness {
+        retrieved,
+        stated,
+        gaps,
+    }
+}
+
+impl std::fmt::Display for Completeness {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "retrieved {} of {} tweets", self.retrieved, self.stated)?;
+        if self.stated > 0 {
+            // Deleted tweets can linger in the stated count, and pinned tweets may be repeated
+            let percent = (self.retrieved as f64 / self.stated as f64 * 100.0).min(100.0);
+            write!(f, " ({:.1}%)", percent)?;
+        }
+        if !self.gaps.is_empty() {
+            write!(f, ", likely gaps: {}", self.gaps.join("; "))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use clap::Parser;
+
+    use super::*;
+
+    #[test]
+    fn test_estimate() {
+        let args = Args::parse_from(["nitter-scraper", "https://nitter.net", "user", "user"]);
+        let query = NitterQuery::User {
+            user: "user".into(),
+        };
+
+        let completeness = estimate(&args, &query, None, None, 3150, 10000);
+        assert_eq!(completeness.gaps.len(), 2);
+        assert_eq!(
+            completeness.to_string(),
+            "retrieved 3150 of 10000 tweets (31.5%), likely gaps: timelines only go back about \
+             3200 tweets, search for older ones; replies are only listed on the with_replies \
+             timeline"
+        );
+
+        let completeness = estimate(&args, &query, None, None, 120, 120);
+        assert!(completeness.gaps.is_empty());
+        assert_eq!(
+            completeness.to_string(),
+            "retrieved 120 of 120 tweets (100.0%)"
+        );
+    }
+}
@@ -0,0 +1,38 @@
+use time::format_description::well_known::Rfc3339;
+use time::macros::format_description;
+use time::{Date, OffsetDateTime};
+
+/// Parse an RFC 3339 date-time, or a date which is taken as midnight UTC
+pub fn parse_date_time(s: &str) -> Result<OffsetDateTime, String> {
+    if let Ok(time) = OffsetDateTime::parse(s, &Rfc3339) {
+        return Ok(time);
+    }
+    Date::parse(s, format_description!("[year]-[month]-[day]"))
+        .map(|date| date.midnight().assume_utc())
+        .map_err(|_| {
+            format!(
+                "invalid date {:?}, expected e.g. 2023-01-31 or 2023-01-31T12:00:00Z",
+                s
+            )
+        })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_date_time() {
+        assert_eq!(
+            parse_date_time("2023-01-31").unwrap().unix_timestamp(),
+            1675123200
+        );
+        assert_eq!(
+            parse_date_time("2023-01-31T01:00:00+01:00")
+                .unwrap()
+                .unix_timestamp(),
+            1675123200
+        );
+        assert!(parse_date_time("31/01/2023").is_err());
+    }
+}
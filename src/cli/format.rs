@@ -0,0 +1,372 @@
+use std::io::{self, IsTerminal, Write};
+
+use clap::ValueEnum;
+use serde_json::Value;
+use time::macros::format_description;
+use time::OffsetDateTime;
+use unicode_width::UnicodeWidthChar;
+
+/// Format of the tweets written to the output
+#[derive(ValueEnum, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// One JSON object per line
+    #[default]
+    Jsonl,
+    /// Comma separated values with a header row
+    Csv,
+    /// Tab separated values with a header row, tabs and newlines in values are escaped
+    Tsv,
+    /// Aligned columns of the time, author, start of the text, and stats of tweets, for reading
+    Table,
+}
+
+/// When to color table output
+#[derive(ValueEnum, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ColorChoice {
+    /// Only when writing to a terminal, and NO_COLOR isn't set
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorChoice {
+    pub fn enabled(self) -> bool {
+        match self {
+            Self::Auto => {
+                io::stdout().is_terminal()
+                    && std::env::var_os("NO_COLOR").is_none_or(|value| value.is_empty())
+            }
+            Self::Always => true,
+            Self::Never => false,
+        }
+    }
+}
+
+/// Widths of the time, author, and text columns of tables, the stats column comes last
+const TABLE_WIDTHS: [usize; 3] = [16, 16, 60];
+
+const BOLD: &str = "\x1b[1m";
+const DIM: &str = "\x1b[2m";
+const CYAN: &str = "\x1b[36m";
+const RESET: &str = "\x1b[0m";
+
+/// Writes serialized tweets in an output format
+///
+/// Tweets are flattened into columns for CSV and TSV, nested fields get dot separated columns
+/// such as "user.screen_name". The columns are the fields of the first tweet, or only of the
+/// selected fields in the order they're given. Lists of plain values are joined with the list
+/// delimiter, other values that don't fit a column, such as attachments, are written as JSON.
+///
+/// Tables have fixed columns instead, with text cut to fit and optionally colored.
+pub struct TweetWriter {
+    format: OutputFormat,
+    list_delimiter: String,
+    /// Paths of the fields to write, the whole tweet is the empty path
+    selected: Vec<Vec<String>>,
+    /// Paths of the fields in each column, known once the header row is written
+    columns: Vec<Vec<String>>,
+    header_written: bool,
+    color: bool,
+}
+
+impl TweetWriter {
+    pub fn new(
+        format: OutputFormat,
+        list_delimiter: &str,
+        selected: Option<Vec<Vec<String>>>,
+        color: bool,
+    ) -> Self {
+        Self {
+            format,
+            list_delimiter: list_delimiter.to_owned(),
+            selected: selected.unwrap_or_else(|| vec![vec![]]),
+            columns: vec![],
+            header_written: false,
+            color,
+        }
+    }
+
+    /// Write a tweet, preceded by the header row if it's the first one
+    pub fn write(&mut self, output: &mut dyn Write, tweet: &Value) -> io::Result<()> {
+        match self.format {
+            OutputFormat::Jsonl => return writeln!(output, "{}", tweet),
+            OutputFormat::Table => return self.write_table_row(output, tweet),
+            OutputFormat::Csv | OutputFormat::Tsv => {}
+        }
+
+        if !self.header_written {
+            for path in &self.selected {
+                let value = lookup(tweet, path).unwrap_or(&Value::Null);
+                flatten(value, &mut path.clone(), &mut self.columns);
+            }
+            let header: Vec<_> = self.columns.iter().map(|path| path.join(".")).collect();
+            self.write_row(output, &header)?;
+            self.header_written = true;
+        }
+        let row: Vec<_> = self
+            .columns
+            .iter()
+            .map(|path| self.cell(lookup(tweet, path)))
+            .collect();
+        self.write_row(output, &row)
+    }
+
+    fn write_table_row(&mut self, output: &mut dyn Write, tweet: &Value) -> io::Result<()> {
+        if !self.header_written {
+            let header = ["TIME", "AUTHOR", "TEXT"]
+                .iter()
+                .zip(TABLE_WIDTHS)
+                .map(|(title, width)| fit(title, width))
+                .chain(["STATS".to_owned()]);
+            let header: Vec<_> = header.map(|title| self.paint(BOLD, &title)).collect();
+            writeln!(output, "{}", header.join("  "))?;
+            self.header_written = true;
+        }
+
+        let time = tweet
+            .get("created_at_ts")
+            .and_then(Value::as_i64)
+            .and_then(|ts| OffsetDateTime::from_unix_timestamp(ts).ok())
+            .and_then(|time| {
+                time.format(format_description!("[year]-[month]-[day] [hour]:[minute]"))
+                    .ok()
+            })
+            .unwrap_or_default();
+        let author = lookup_str(tweet, &["user", "screen_name"])
+            .or_else(|| lookup_str(tweet, &["screen_name"]))
+            .map(|screen_name| format!("@{}", screen_name))
+            .unwrap_or_default();
+        // Users have a bio and unavailable tweets a reason in place of text
+        let text = ["full_text", "bio", "reason"]
+            .iter()
+            .find_map(|field| lookup_str(tweet, &[field]))
+            .unwrap_or_default();
+        let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+        let stats = tweet
+            .get("stats")
+            .map(|stats| {
+                let count = |field| stats.get(field).and_then(Value::as_u64).unwrap_or(0);
+                format!(
+                    "↩ {}  ⟲ {}  ❝ {}  ♥ {}",
+                    count("comment"),
+                    count("retweet"),
+                    count("quote"),
+                    count("heart")
+                )
+            })
+            .unwrap_or_default();
+
+        let [time_width, author_width, text_width] = TABLE_WIDTHS;
+        writeln!(
+            output,
+            "{}  {}  {}  {}",
+            self.paint(DIM, &fit(&time, time_width)),
+            self.paint(CYAN, &fit(&author, author_width)),
+            fit(&text, text_width),
+            self.paint(DIM, &stats)
+        )
+    }
+
+    /// Wrap text in an ANSI style if the table is colored
+    fn paint(&self, style: &str, text: &str) -> String {
+        if self.color {
+            format!("{}{}{}", style, text, RESET)
+        } else {
+            text.to_owned()
+        }
+    }
+
+    /// Text of a value in a column
+    fn cell(&self, value: Option<&Value>) -> String {
+        match value {
+            Some(Value::Array(items)) if items.iter().all(is_plain) => items
+                .iter()
+                .map(plain_text)
+                .collect::<Vec<_>>()
+                .join(&self.list_delimiter),
+            Some(value) if is_plain(value) => plain_text(value),
+            Some(value) => value.to_string(),
+            None => String::new(),
+        }
+    }
+
+    fn write_row(&self, output: &mut dyn Write, cells: &[String]) -> io::Result<()> {
+        let (separator, escape): (_, fn(&str) -> String) = match self.format {
+            OutputFormat::Tsv => ("\t", escape_tsv),
+            _ => (",", quote_csv),
+        };
+        let row: Vec<_> = cells.iter().map(|cell| escape(cell)).collect();
+        writeln!(output, "{}", row.join(separator))
+    }
+}
+
+fn lookup<'v>(value: &'v Value, path: &[String]) -> Option<&'v Value> {
+    path.iter().try_fold(value, |v, f| v.get(f))
+}
+
+fn lookup_str<'v>(value: &'v Value, path: &[&str]) -> Option<&'v str> {
+    path.iter().try_fold(value, |v, f| v.get(f))?.as_str()
+}
+
+/// Pad text with spaces to a display width, cutting it short with an ellipsis if it's wider
+fn fit(text: &str, width: usize) -> String {
+    let mut fitted = String::new();
+    let mut fitted_width = 0;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        let c_width = c.width().unwrap_or(0);
+        let reserved = if chars.peek().is_some() { 1 } else { 0 };
+        if fitted_width + c_width + reserved > width {
+            fitted.push('…');
+            fitted_width += 1;
+            break;
+        }
+        fitted.push(c);
+        fitted_width += c_width;
+    }
+    fitted.push_str(&" ".repeat(width.saturating_sub(fitted_width)));
+    fitted
+}
+
+/// Collect the paths of the fields in a value, nested objects are flattened
+fn flatten(value: &Value, path: &mut Vec<String>, columns: &mut Vec<Vec<String>>) {
+    match value {
+        Value::Object(object) if !object.is_empty() => {
+            for (field, value) in object {
+                path.push(field.clone());
+                flatten(value, path, columns);
+                path.pop();
+            }
+        }
+        _ => columns.push(path.clone()),
+    }
+}
+
+fn is_plain(value: &Value) -> bool {
+    !matches!(value, Value::Array(_) | Value::Object(_))
+}
+
+fn plain_text(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        value => value.to_string(),
+    }
+}
+
+/// Quote a CSV field if it needs to be, as in RFC 4180
+fn quote_csv(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_owned()
+    }
+}
+
+/// Escape the characters that can't appear in a TSV field
+fn escape_tsv(field: &str) -> String {
+    field
+        .replace('\\', "\\\\")
+        .replace('\t', "\\t")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use super::*;
+
+    fn write(mut writer: TweetWriter, tweets: &[Value]) -> String {
+        let mut output = vec![];
+        for tweet in tweets {
+            writer.write(&mut output, tweet).unwrap();
+        }
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn test_csv() {
+        let tweets = [
+            json!({
+                "id": 1,
+                "full_text": "hello, \"world\"",
+                "images": ["a.jpg", "b.jpg"],
+                "user": {"screen_name": "jack", "verified_type": null},
+                "video": null,
+            }),
+            json!({
+                "id": 2,
+                "full_text": "line\nbreak",
+                "images": [],
+                "user": {"screen_name": "jill", "verified_type": "blue"},
+                "video": {"url": "v.mp4"},
+            }),
+        ];
+        assert_eq!(
+            write(
+                TweetWriter::new(OutputFormat::Csv, "|", None, false),
+                &tweets
+            ),
+            "full_text,id,images,user.screen_name,user.verified_type,video\n\
+             \"hello, \"\"world\"\"\",1,a.jpg|b.jpg,jack,,\n\
+             \"line\nbreak\",2,,jill,blue,\"{\"\"url\"\":\"\"v.mp4\"\"}\"\n"
+        );
+
+        let selected = vec![
+            vec!["user".to_owned(), "screen_name".to_owned()],
+            vec!["user".to_owned()],
+        ];
+        assert_eq!(
+            write(
+                TweetWriter::new(OutputFormat::Tsv, ";", Some(selected), false),
+                &tweets
+            ),
+            "user.screen_name\tuser.screen_name\tuser.verified_type\n\
+             jack\tjack\t\n\
+             jill\tjill\tblue\n"
+        );
+        assert_eq!(
+            write(
+                TweetWriter::new(OutputFormat::Tsv, ";", None, false),
+                &tweets[1..]
+            ),
+            "full_text\tid\timages\tuser.screen_name\tuser.verified_type\tvideo.url\n\
+             line\\nbreak\t2\t\tjill\tblue\tv.mp4\n"
+        );
+    }
+
+    #[test]
+    fn test_table() {
+        let tweets = [
+            json!({
+                "created_at_ts": 1672588283,
+                "user": {"screen_name": "jack"},
+                "full_text": "hello\n\nworld",
+                "stats": {"comment": 1, "retweet": 2, "quote": 3, "heart": 4},
+            }),
+            json!({"type": "unavailable", "reason": "deleted"}),
+        ];
+        let table = write(
+            TweetWriter::new(OutputFormat::Table, "|", None, false),
+            &tweets,
+        );
+        let lines: Vec<_> = table.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with("TIME              AUTHOR            TEXT  "));
+        assert_eq!(
+            lines[1],
+            format!(
+                "2023-01-01 15:51  @jack             {:60}  ↩ 1  ⟲ 2  ❝ 3  ♥ 4",
+                "hello world"
+            )
+        );
+        assert_eq!(lines[2].trim_end(), format!("{:36}deleted", ""));
+
+        assert_eq!(fit("abcdef", 4), "abc…");
+        assert_eq!(fit("abcd", 4), "abcd");
+        assert_eq!(fit("日本語", 4), "日… ");
+    }
+}
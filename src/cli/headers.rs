@@ -0,0 +1,46 @@
+use reqwest::header::{HeaderName, HeaderValue};
+
+/// Parse a header in the form "Name: value"
+pub fn parse_header(s: &str) -> Result<(HeaderName, HeaderValue), String> {
+    let (name, value) = s
+        .split_once(':')
+        .ok_or_else(|| format!("invalid header {:?}, expected \"Name: value\"", s))?;
+    let name = HeaderName::from_bytes(name.trim().as_bytes())
+        .map_err(|_| format!("invalid header name {:?}", name))?;
+    let value = HeaderValue::from_str(value.trim())
+        .map_err(|_| format!("invalid header value {:?}", value))?;
+    Ok((name, value))
+}
+
+/// Parse a cookie in the form "name=value"
+pub fn parse_cookie(s: &str) -> Result<(String, String), String> {
+    let (name, value) = s
+        .split_once('=')
+        .ok_or_else(|| format!("invalid cookie {:?}, expected \"name=value\"", s))?;
+    let valid = |s: &str| s.bytes().all(|b| b.is_ascii_graphic() && b != b';');
+    if name.is_empty() || !valid(name) || !valid(value) {
+        return Err(format!("invalid cookie {:?}", s));
+    }
+    Ok((name.to_owned(), value.to_owned()))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_header() {
+        let (name, value) = parse_header("Authorization: Basic dXNlcjpwYXNz").unwrap();
+        assert_eq!(name, "authorization");
+        assert_eq!(value, "Basic dXNlcjpwYXNz");
+        assert!(parse_header("Authorization").is_err());
+        assert!(parse_header("Bad Name: value").is_err());
+
+        assert_eq!(
+            parse_cookie("auth=a=b").unwrap(),
+            ("auth".to_owned(), "a=b".to_owned())
+        );
+        assert!(parse_cookie("auth=a; other=b").is_err());
+        assert!(parse_cookie("=value").is_err());
+    }
+}
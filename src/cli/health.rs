@@ -0,0 +1,103 @@
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use nitter_scraper::{write_atomic, NitterError, TimelineItem};
+use serde_json::json;
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+
+/// Number of failed polls in a row after which the scraper is reported unhealthy
+const UNHEALTHY_FAILURES: u32 = 3;
+
+/// Heartbeat file rewritten after each poll of the instance, so a scraper that stopped making
+/// progress or keeps failing can be restarted
+///
+/// It's a JSON object with whether the recent polls succeeded, when the last poll and the last
+/// successful one were, and the error of the last poll if it failed.
+pub struct HealthFile {
+    path: PathBuf,
+    state: Mutex<HealthState>,
+}
+
+#[derive(Default)]
+struct HealthState {
+    failures: u32,
+    last_success: Option<OffsetDateTime>,
+    /// Page of the last item passed to `record_item`
+    page: Option<u32>,
+}
+
+impl HealthFile {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            state: Mutex::default(),
+        }
+    }
+
+    /// Record a poll when an item of a search arrives from a new page, or the search failed
+    pub fn record_item(&self, item: &Result<TimelineItem, NitterError>) {
+        let page = match item {
+            Ok(TimelineItem::Tweet(tweet)) => tweet.page,
+            Ok(TimelineItem::Unavailable(unavailable)) => unavailable.page,
+            Ok(_) => return,
+            Err(e) => return self.record(Err(e)),
+        };
+        let last_page = std::mem::replace(&mut self.state.lock().unwrap().page, page);
+        if page != last_page {
+            self.record(Ok(()));
+        }
+    }
+
+    /// Record the result of a poll
+    pub fn record(&self, poll: Result<(), &NitterError>) {
+        let mut state = self.state.lock().unwrap();
+        let now = OffsetDateTime::now_utc();
+        match poll {
+            Ok(()) => {
+                state.failures = 0;
+                state.last_success = Some(now);
+            }
+            Err(_) => state.failures += 1,
+        }
+        let heartbeat = json!({
+            "healthy": state.failures < UNHEALTHY_FAILURES,
+            "checked_at": now.format(&Rfc3339).unwrap(),
+            "last_success_at": state.last_success.map(|time| time.format(&Rfc3339).unwrap()),
+            "consecutive_failures": state.failures,
+            "error": poll.err().map(|e| e.to_string()),
+        });
+        if let Err(e) = write_atomic(&self.path, heartbeat.to_string().as_bytes()) {
+            eprintln!("unable to write {}: {}", self.path.display(), e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::Value;
+
+    use super::*;
+
+    #[test]
+    fn test_health_file() {
+        let path = std::env::temp_dir().join(format!("health-test-{}.json", std::process::id()));
+        let read = || serde_json::from_slice::<Value>(&std::fs::read(&path).unwrap()).unwrap();
+        let health = HealthFile::new(&path);
+
+        health.record(Ok(()));
+        assert_eq!(read()["healthy"], true);
+        assert!(read()["error"].is_null());
+
+        for _ in 0..UNHEALTHY_FAILURES {
+            health.record(Err(&NitterError::NotFound));
+        }
+        let heartbeat = read();
+        assert_eq!(heartbeat["healthy"], false);
+        assert_eq!(heartbeat["consecutive_failures"], UNHEALTHY_FAILURES);
+        assert!(heartbeat["last_success_at"].is_string());
+        assert!(heartbeat["error"].is_string());
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
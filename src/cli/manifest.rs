@@ -0,0 +1,87 @@
+use std::path::Path;
+
+use clap::ValueEnum;
+use nitter_scraper::{write_atomic, NitterQuery};
+use serde_json::json;
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+
+use crate::Args;
+
+/// Write the version, configuration, and starting point of a run so its output can be reproduced
+pub fn write_manifest(
+    path: &Path,
+    args: &Args,
+    instance: &str,
+    query: &NitterQuery,
+    min_id: Option<u128>,
+    max_id: Option<u128>,
+) -> std::io::Result<()> {
+    let instances: Vec<_> = std::iter::once(instance)
+        .chain(args.race_instance.as_deref())
+        .collect();
+    // The redaction salt is left out, it would allow reversing hashed fields, and so are headers
+    // and cookies, which may hold credentials
+    let manifest = json!({
+        "version": env!("CARGO_PKG_VERSION"),
+        "started_at": OffsetDateTime::now_utc().format(&Rfc3339).unwrap(),
+        "query": query.to_string(),
+        "instances": instances,
+        "output": args.output,
+        "min_id": min_id.map(|id| id.to_string()),
+        "max_id": max_id.map(|id| id.to_string()),
+        "since": args.since.map(|time| time.format(&Rfc3339).unwrap()),
+        "until": args.until.map(|time| time.format(&Rfc3339).unwrap()),
+        "config": {
+            "limit": args.limit,
+            "max_pages": args.max_pages,
+            "rate_limit": args.rate_limit,
+            "rate_limit_retries": args.rate_limit_retries,
+            "prefetch": args.prefetch,
+            "max_buffered_items": args.max_buffered_items,
+            "page_delay": args.page_delay,
+            "jitter": args.jitter,
+            "page_timeout": args.page_timeout,
+            "deadline": args.deadline,
+            "watch": args.watch,
+            "recheck_window": args.recheck_window,
+            "probe_budget": args.probe_budget,
+            "reorder_pinned": args.reorder_pinned,
+            "skip_retweets": args.skip_retweets,
+            "include_profile": args.include_profile,
+            "include_raw_html": args.include_raw_html,
+            "include_unavailable": args.include_unavailable,
+            "prefer_rss": args.prefer_rss,
+            "image_quality": value_name(args.image_quality),
+            "media_urls": value_name(args.media_urls),
+            "expand_links": args.expand_links,
+            "resolve_quotes": args.resolve_quotes,
+            "lenient_parse": args.lenient_parse,
+            "redact": args.redact.iter().map(|r| r.to_string()).collect::<Vec<_>>(),
+            "seed": args.seed,
+            "format": value_name(args.format),
+            "list_delimiter": args.list_delimiter,
+            "connect_timeout": args.connect_timeout,
+            "timeout": args.timeout,
+            "read_timeout": args.read_timeout,
+            "body_timeout": args.body_timeout,
+            "cache_dir": args.cache_dir,
+            "cache_max_age": args.cache_max_age,
+            "cache_max_size": args.cache_max_size,
+            "cookie_jar": args.cookie_jar,
+            "no_compression": args.no_compression,
+            "max_response_size": args.max_response_size,
+            "user_agents": args.user_agents,
+            "browser_user_agents": args.browser_user_agents,
+        },
+    });
+
+    let mut contents = serde_json::to_string_pretty(&manifest).unwrap();
+    contents.push('\n');
+    write_atomic(path, contents.as_bytes())
+}
+
+/// Name of a value as passed on the command line
+fn value_name(value: impl ValueEnum) -> Option<String> {
+    Some(value.to_possible_value()?.get_name().to_owned())
+}
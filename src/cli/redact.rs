@@ -0,0 +1,146 @@
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+#[derive(Debug, Clone)]
+pub struct Redaction {
+    /// Dot separated path to the field, e.g. "user.screen_name"
+    path: Vec<String>,
+    action: RedactAction,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RedactAction {
+    /// Replace the value with a salted hash, so equal values stay linkable
+    Hash,
+    /// Remove the field entirely
+    Drop,
+}
+
+pub fn parse_redaction(s: &str) -> Result<Redaction, String> {
+    let (path, action) = s.split_once('=').unwrap_or((s, "drop"));
+    let action = match action {
+        "hash" => RedactAction::Hash,
+        "drop" => RedactAction::Drop,
+        _ => {
+            return Err(format!(
+                "unknown redaction {:?}, expected hash or drop",
+                action
+            ))
+        }
+    };
+    let path: Vec<_> = path.split('.').map(|p| p.to_owned()).collect();
+    if path.iter().any(|p| p.is_empty()) {
+        return Err(format!("invalid field path {:?}", s));
+    }
+    Ok(Redaction { path, action })
+}
+
+impl std::fmt::Display for Redaction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let action = match self.action {
+            RedactAction::Hash => "hash",
+            RedactAction::Drop => "drop",
+        };
+        write!(f, "{}={}", self.path.join("."), action)
+    }
+}
+
+/// Output transform that hashes or drops selected fields of serialized tweets
+pub struct Redactor {
+    redactions: Vec<Redaction>,
+    salt: String,
+}
+
+impl Redactor {
+    pub fn new(redactions: Vec<Redaction>, salt: &str) -> Self {
+        Self {
+            redactions,
+            salt: salt.to_owned(),
+        }
+    }
+
+    pub fn apply(&self, tweet: &mut Value) {
+        for redaction in &self.redactions {
+            let (field, parents) = redaction.path.split_last().unwrap();
+            let Some(object) = parents
+                .iter()
+                .try_fold(&mut *tweet, |v, p| v.get_mut(p))
+                .and_then(|v| v.as_object_mut())
+            else {
+                continue;
+            };
+            match redaction.action {
+                RedactAction::Drop => {
+                    object.remove(field);
+                }
+                RedactAction::Hash => {
+                    if let Some(value) = object.get_mut(field) {
+                        if !value.is_null() {
+                            *value = Value::String(self.hash(value));
+                        }
+                    }
+                }
+            }
+        }
+
+        // Resolved quoted tweets have the same fields
+        if let Some(quoted) = tweet.get_mut("quoted_tweet").filter(|q| q.is_object()) {
+            self.apply(quoted);
+        }
+    }
+
+    fn hash(&self, value: &Value) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(self.salt.as_bytes());
+        match value {
+            Value::String(s) => hasher.update(s.as_bytes()),
+            v => hasher.update(v.to_string().as_bytes()),
+        }
+        hasher
+            .finalize()
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_redact() {
+        let redactions = [
+            "user.screen_name=hash",
+            "user.full_name",
+            "missing.field=hash",
+        ]
+        .into_iter()
+        .map(|r| parse_redaction(r).unwrap())
+        .collect();
+        let redactor = Redactor::new(redactions, "salt");
+        let mut tweet: Value =
+            serde_json::from_str(r#"{"id":1,"user":{"screen_name":"jack","full_name":"Jack"}}"#)
+                .unwrap();
+        redactor.apply(&mut tweet);
+
+        let user = tweet["user"].as_object().unwrap();
+        assert!(!user.contains_key("full_name"));
+        let hashed = user["screen_name"].as_str().unwrap();
+        assert_eq!(hashed.len(), 64);
+        assert_ne!(hashed, "jack");
+        assert_eq!(tweet["id"], 1);
+
+        let mut tweet: Value = serde_json::from_str(
+            r#"{"user":{"full_name":"Jack"},"quoted_tweet":{"user":{"full_name":"Ev"},"quoted_tweet":null}}"#,
+        )
+        .unwrap();
+        redactor.apply(&mut tweet);
+        assert!(!tweet["quoted_tweet"]["user"]
+            .as_object()
+            .unwrap()
+            .contains_key("full_name"));
+        assert!(parse_redaction("user.=hash").is_err());
+        assert!(parse_redaction("user=encrypt").is_err());
+    }
+}
@@ -0,0 +1,374 @@
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Seek, SeekFrom, Stdout, Write};
+use std::path::{Path, PathBuf};
+
+use nitter_scraper::write_atomic;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// Where a JSONL output file was left off, kept next to it as "<output>.resume"
+///
+/// The checksum covers the output up to the last checkpoint, so a resumed run notices if the file
+/// was changed, and drops the lines written after it, which are scraped again.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct ResumeToken {
+    len: u64,
+    sha256: String,
+    /// Newest tweet ID of the runs that completed, older tweets are all in the output
+    complete_through: Option<u128>,
+    /// Run that stopped before the end of the timeline, or its minimum ID
+    interrupted: Option<Run>,
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+struct Run {
+    min_id: Option<u128>,
+    newest_id: Option<u128>,
+    oldest_id: Option<u128>,
+}
+
+impl Run {
+    fn record(&mut self, id: u128) {
+        self.newest_id = Some(self.newest_id.map_or(id, |newest| newest.max(id)));
+        self.oldest_id = Some(self.oldest_id.map_or(id, |oldest| oldest.min(id)));
+    }
+}
+
+/// Output of a scrape, either stdout or a resumable JSONL file
+pub enum Output {
+    Stdout(Stdout),
+    File(Box<ResumableFile>),
+}
+
+pub struct ResumableFile {
+    writer: BufWriter<File>,
+    token_path: PathBuf,
+    token: ResumeToken,
+    /// Checksum and length of everything written so far
+    hasher: Sha256,
+    len: u64,
+    run: Run,
+    /// Whether the run resumes from the token, rather than from IDs given on the command line
+    tracked: bool,
+}
+
+impl Output {
+    /// Open an output file to append to, and return the minimum and maximum IDs to scrape
+    ///
+    /// Unless `min_id` or `max_id` is given, an interrupted run is resumed before the oldest
+    /// tweet it wrote, otherwise the scrape continues after the newest tweet in the file.
+    pub fn resume(
+        path: &Path,
+        min_id: Option<u128>,
+        max_id: Option<u128>,
+    ) -> Result<(Self, Option<u128>, Option<u128>), String> {
+        let mut token_path = path.as_os_str().to_owned();
+        token_path.push(".resume");
+        let token_path = PathBuf::from(token_path);
+        let tracked = min_id.is_none() && max_id.is_none();
+
+        let token = match std::fs::read(&token_path) {
+            Ok(json) => Some(
+                serde_json::from_slice::<ResumeToken>(&json)
+                    .map_err(|e| format!("invalid {}: {}", token_path.display(), e))?,
+            ),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+            Err(e) => return Err(format!("unable to read {}: {}", token_path.display(), e)),
+        };
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)
+            .map_err(|e| format!("unable to open {}: {}", path.display(), e))?;
+        let error = |e: io::Error| format!("unable to resume {}: {}", path.display(), e);
+
+        let verified = match token {
+            Some(token) => match verify(&file, path, &token) {
+                Ok(hasher) => Some((token, hasher)),
+                // Given IDs append without resuming, so the token isn't needed
+                Err(e) if !tracked => {
+                    eprintln!("Warning: {}", e);
+                    None
+                }
+                Err(e) => return Err(e),
+            },
+            None => None,
+        };
+        let (token, hasher) = match verified {
+            Some(verified) => verified,
+            None => {
+                let ids = repair_output(&file, path, tracked).map_err(error)?;
+                let len = file.metadata().map_err(error)?.len();
+                // Files written without a token may be missing older tweets too
+                let token = ResumeToken {
+                    len,
+                    sha256: String::new(),
+                    complete_through: None,
+                    interrupted: (len > 0).then_some(ids),
+                };
+                let hasher = hash_prefix(&file, len).map_err(error)?;
+                (token, hasher)
+            }
+        };
+
+        let (run, min_id, max_id) = if !tracked {
+            (Run::default(), min_id, max_id)
+        } else if let Some(run) = &token.interrupted {
+            let max_id = run.oldest_id.map(|id| id.saturating_sub(1));
+            if let Some(id) = max_id {
+                eprintln!("Resuming {} before tweet {}", path.display(), id + 1);
+            }
+            (run.clone(), run.min_id, max_id)
+        } else {
+            let min_id = token.complete_through.map(|id| id + 1);
+            let run = Run {
+                min_id,
+                ..Run::default()
+            };
+            (run, min_id, None)
+        };
+
+        let writer = BufWriter::new(
+            OpenOptions::new()
+                .append(true)
+                .open(path)
+                .map_err(|e| format!("unable to open {}: {}", path.display(), e))?,
+        );
+        let file = ResumableFile {
+            writer,
+            token_path,
+            len: token.len,
+            token,
+            hasher,
+            run,
+            tracked,
+        };
+        Ok((Self::File(Box::new(file)), min_id, max_id))
+    }
+
+    /// Record a tweet ID written to the output
+    pub fn record(&mut self, id: u128) {
+        if let Self::File(file) = self {
+            file.run.record(id);
+        }
+    }
+
+    /// Sync everything written so far and update the resume token, or just flush stdout
+    pub fn checkpoint(&mut self) -> io::Result<()> {
+        match self {
+            Self::Stdout(stdout) => stdout.flush(),
+            Self::File(file) => {
+                if file.tracked {
+                    file.token.interrupted = Some(file.run.clone());
+                }
+                file.write_token()
+            }
+        }
+    }
+
+    /// Checkpoint at the end of a run, which covers the rest of the timeline if it's `complete`
+    pub fn finish(&mut self, complete: bool) -> io::Result<()> {
+        match self {
+            Self::File(file) if file.tracked && complete => {
+                file.token.complete_through = file.token.complete_through.max(file.run.newest_id);
+                file.token.interrupted = None;
+                file.write_token()
+            }
+            _ => self.checkpoint(),
+        }
+    }
+}
+
+impl ResumableFile {
+    fn write_token(&mut self) -> io::Result<()> {
+        self.writer.flush()?;
+        self.writer.get_ref().sync_data()?;
+        self.token.len = self.len;
+        self.token.sha256 = hex(&self.hasher.clone().finalize());
+        write_atomic(&self.token_path, &serde_json::to_vec(&self.token).unwrap())
+    }
+}
+
+impl Write for Output {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Self::Stdout(stdout) => stdout.write(buf),
+            Self::File(file) => {
+                let n = file.writer.write(buf)?;
+                file.hasher.update(&buf[..n]);
+                file.len += n as u64;
+                Ok(n)
+            }
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Self::Stdout(stdout) => stdout.flush(),
+            Self::File(file) => file.writer.flush(),
+        }
+    }
+}
+
+/// Check the output up to the token's checkpoint, and drop what was written after it
+fn verify(file: &File, path: &Path, token: &ResumeToken) -> Result<Sha256, String> {
+    let error = |e: io::Error| format!("unable to resume {}: {}", path.display(), e);
+    let len = file.metadata().map_err(error)?.len();
+    let hasher = hash_prefix(file, token.len).map_err(error)?;
+    if len < token.len || hex(&hasher.clone().finalize()) != token.sha256 {
+        return Err(format!(
+            "{} was changed since it was written, pass --min-id or --max-id to append to it \
+             anyway",
+            path.display()
+        ));
+    }
+    if len > token.len {
+        eprintln!(
+            "Removing {} bytes written to {} after its last checkpoint",
+            len - token.len,
+            path.display()
+        );
+        file.set_len(token.len).map_err(error)?;
+        file.sync_all().map_err(error)?;
+    }
+    Ok(hasher)
+}
+
+fn hash_prefix(mut file: &File, len: u64) -> io::Result<Sha256> {
+    let mut hasher = Sha256::new();
+    file.seek(SeekFrom::Start(0))?;
+    io::copy(&mut file.take(len), &mut hasher)?;
+    Ok(hasher)
+}
+
+/// Truncate an incomplete trailing line left by a crash and return the range of tweet IDs in
+/// the file
+///
+/// If `require_ids` is set, every record must have an ID, they don't if `--select` or `--redact`
+/// removed it.
+fn repair_output(file: &File, path: &Path, require_ids: bool) -> io::Result<Run> {
+    let mut reader = BufReader::new(file);
+    let mut valid_len = 0;
+    let mut ids = Run::default();
+    let mut line = Vec::new();
+    for line_number in 1.. {
+        line.clear();
+        let n = reader.read_until(b'\n', &mut line)?;
+        if n == 0 {
+            break;
+        }
+
+        let value = line
+            .ends_with(b"\n")
+            .then(|| serde_json::from_slice::<serde_json::Value>(&line).ok())
+            .flatten();
+        match value {
+            Some(value) => {
+                let id = value
+                    .get("id_str")
+                    .and_then(|id| id.as_str()?.parse::<u128>().ok());
+                match id {
+                    Some(id) => ids.record(id),
+                    // Unavailable tweet placeholders and users don't have an ID
+                    None if value.get("type").is_some() || !require_ids => {}
+                    None => {
+                        return Err(io::Error::new(
+                            io::ErrorKind::InvalidData,
+                            format!(
+                                "line {} has no id_str to resume from, pass --min-id or \
+                                 --max-id to append to it anyway",
+                                line_number
+                            ),
+                        ))
+                    }
+                }
+                valid_len += n as u64;
+            }
+            None if reader.fill_buf()?.is_empty() => {
+                // Only the last line can be the result of an interrupted write
+                eprintln!(
+                    "Removing incomplete line {} from {}",
+                    line_number,
+                    path.display()
+                );
+                file.set_len(valid_len)?;
+                file.sync_all()?;
+                break;
+            }
+            None => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("invalid tweet on line {}", line_number),
+                ))
+            }
+        }
+    }
+
+    Ok(ids)
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn write_tweets(output: &mut Output, ids: &[u128]) {
+        for &id in ids {
+            writeln!(output, "{{\"id_str\":\"{}\"}}", id).unwrap();
+            output.record(id);
+        }
+    }
+
+    #[test]
+    fn test_resume() {
+        let dir = std::env::temp_dir().join(format!("resume-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("jack.jsonl");
+
+        // A backfill interrupted after a checkpoint and a crash resumes before its oldest tweet
+        let (mut output, min_id, max_id) = Output::resume(&path, None, None).unwrap();
+        assert_eq!((min_id, max_id), (None, None));
+        write_tweets(&mut output, &[50, 40]);
+        output.checkpoint().unwrap();
+        write_tweets(&mut output, &[30]);
+        output.flush().unwrap();
+        drop(output);
+        let (mut output, min_id, max_id) = Output::resume(&path, None, None).unwrap();
+        assert_eq!((min_id, max_id), (None, Some(39)));
+        assert_eq!(std::fs::read_to_string(&path).unwrap().lines().count(), 2);
+
+        // Once it completes, the next run continues after the newest tweet
+        write_tweets(&mut output, &[30, 20]);
+        output.finish(true).unwrap();
+        drop(output);
+        let (output, min_id, max_id) = Output::resume(&path, None, None).unwrap();
+        assert_eq!((min_id, max_id), (Some(51), None));
+        drop(output);
+
+        // Changes to the output are noticed
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(b"{}\n").unwrap();
+        let mut contents = std::fs::read(&path).unwrap();
+        contents[2] = b'X';
+        std::fs::write(&path, contents).unwrap();
+        assert!(Output::resume(&path, None, None).is_err());
+        assert!(Output::resume(&path, Some(51), None).is_ok());
+
+        // Files without a token need IDs to resume from
+        std::fs::remove_file(dir.join("jack.jsonl.resume")).unwrap();
+        std::fs::write(&path, "{\"id_str\":\"5\"}\n{\"id_str\":\"3\"}\n{\"full_te").unwrap();
+        let (output, min_id, max_id) = Output::resume(&path, None, None).unwrap();
+        assert_eq!((min_id, max_id), (None, Some(2)));
+        drop(output);
+        std::fs::write(&path, "{\"full_text\":\"hi\"}\n").unwrap();
+        assert!(Output::resume(&path, None, None).is_err());
+        assert!(Output::resume(&path, Some(1), None).is_ok());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
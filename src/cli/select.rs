@@ -0,0 +1,89 @@
+use serde_json::{Map, Value};
+
+/// Output transform that keeps only selected fields of serialized tweets
+#[derive(Debug, Clone)]
+pub struct Selection {
+    /// Dot separated paths, e.g. ".user.screen_name"
+    paths: Vec<Vec<String>>,
+}
+
+pub fn parse_selection(s: &str) -> Result<Selection, String> {
+    let paths = s
+        .split(',')
+        .map(|path| {
+            let fields: Vec<_> = path
+                .trim()
+                .strip_prefix('.')
+                .ok_or_else(|| format!("field path {:?} must start with '.'", path))?
+                .split('.')
+                .map(|f| f.to_owned())
+                .collect();
+            if fields.iter().any(|f| f.is_empty()) {
+                return Err(format!("invalid field path {:?}", path));
+            }
+            Ok(fields)
+        })
+        .collect::<Result<_, _>>()?;
+    Ok(Selection { paths })
+}
+
+impl Selection {
+    /// Paths of the selected fields, in the order they were given
+    pub fn paths(&self) -> &[Vec<String>] {
+        &self.paths
+    }
+
+    /// Project a tweet onto the selected fields, keeping their nesting. Missing fields are null.
+    pub fn apply(&self, tweet: Value) -> Value {
+        let mut projected = Value::Object(Map::new());
+        for path in &self.paths {
+            let value = path
+                .iter()
+                .try_fold(&tweet, |v, f| v.get(f))
+                .cloned()
+                .unwrap_or(Value::Null);
+
+            let (field, parents) = path.split_last().unwrap();
+            let mut object = &mut projected;
+            for parent in parents {
+                object = object
+                    .as_object_mut()
+                    .unwrap()
+                    .entry(parent.as_str())
+                    .or_insert_with(|| Value::Object(Map::new()));
+                if !object.is_object() {
+                    // A parent was also selected as a whole, it already contains this field
+                    break;
+                }
+            }
+            if let Some(object) = object.as_object_mut() {
+                object.insert(field.clone(), value);
+            }
+        }
+        projected
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_select() {
+        let tweet = json!({
+            "id": 1,
+            "user": {"screen_name": "jack", "full_name": "Jack"},
+            "stats": {"heart": 5, "retweet": 2},
+        });
+        let selection = parse_selection(".user.screen_name, .stats.heart,.missing").unwrap();
+        assert_eq!(
+            selection.apply(tweet),
+            json!({"user": {"screen_name": "jack"}, "stats": {"heart": 5}, "missing": null})
+        );
+
+        assert!(parse_selection("user").is_err());
+        assert!(parse_selection(".user.").is_err());
+    }
+}
@@ -0,0 +1,123 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use std::process::ExitCode;
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde_json::Value;
+use time::macros::format_description;
+use time::OffsetDateTime;
+
+use super::archive::read_archive;
+
+const TOP_N: usize = 10;
+
+pub fn print_stats(path: &Path) -> ExitCode {
+    let tweets = match read_archive(path) {
+        Ok(t) => t,
+        Err(e) => {
+            eprintln!("{}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    println!("Tweets: {}", tweets.len());
+    if tweets.is_empty() {
+        return ExitCode::SUCCESS;
+    }
+
+    // Media ratio
+    let with_media = tweets.iter().filter(|t| has_media(t)).count();
+    println!(
+        "With media: {} ({:.1}%)",
+        with_media,
+        100.0 * with_media as f64 / tweets.len() as f64
+    );
+
+    // Tweets per day
+    println!();
+    println!("Tweets per day:");
+    let mut per_day = BTreeMap::new();
+    for tweet in &tweets {
+        if let Some(day) = day(tweet) {
+            *per_day.entry(day).or_insert(0) += 1;
+        }
+    }
+    for (day, count) in per_day {
+        println!("  {}  {}", day, count);
+    }
+
+    // Engagement distribution
+    println!();
+    println!(
+        "{:<10} {:>10} {:>10} {:>10} {:>10} {:>10}",
+        "Engagement", "min", "median", "p90", "max", "mean"
+    );
+    for stat in ["comment", "retweet", "quote", "heart", "views"] {
+        let mut values: Vec<u64> = tweets
+            .iter()
+            .filter_map(|t| t.get("stats")?.get(stat)?.as_u64())
+            .collect();
+        if values.is_empty() {
+            continue;
+        }
+        values.sort_unstable();
+        let mean = values.iter().sum::<u64>() as f64 / values.len() as f64;
+        println!(
+            "{:<10} {:>10} {:>10} {:>10} {:>10} {:>10.1}",
+            stat,
+            values[0],
+            percentile(&values, 50),
+            percentile(&values, 90),
+            values[values.len() - 1],
+            mean
+        );
+    }
+
+    // Top hashtags and mentions
+    static HASHTAG_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"#\w+").unwrap());
+    static MENTION_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"@\w+").unwrap());
+    for (title, re) in [("hashtags", &HASHTAG_RE), ("mentions", &MENTION_RE)] {
+        println!();
+        println!("Top {}:", title);
+        for (entity, count) in top_entities(&tweets, re) {
+            println!("  {:>6}  {}", count, entity);
+        }
+    }
+
+    ExitCode::SUCCESS
+}
+
+fn has_media(tweet: &Value) -> bool {
+    let has_images = tweet
+        .get("images")
+        .and_then(|i| i.as_array())
+        .map(|i| !i.is_empty())
+        .unwrap_or(false);
+    let has_video = tweet.get("video").map(|v| !v.is_null()).unwrap_or(false);
+    has_images || has_video
+}
+
+fn day(tweet: &Value) -> Option<String> {
+    let ts = tweet.get("created_at_ts")?.as_i64()?;
+    let time = OffsetDateTime::from_unix_timestamp(ts).ok()?;
+    time.format(format_description!("[year]-[month]-[day]"))
+        .ok()
+}
+
+fn percentile(sorted: &[u64], p: usize) -> u64 {
+    sorted[(sorted.len() - 1) * p / 100]
+}
+
+fn top_entities(tweets: &[Value], re: &Regex) -> Vec<(String, usize)> {
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for text in tweets.iter().filter_map(|t| t.get("full_text")?.as_str()) {
+        for m in re.find_iter(text) {
+            *counts.entry(m.as_str().to_lowercase()).or_insert(0) += 1;
+        }
+    }
+    let mut counts: Vec<_> = counts.into_iter().collect();
+    counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    counts.truncate(TOP_N);
+    counts
+}
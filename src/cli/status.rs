@@ -0,0 +1,76 @@
+use std::time::Instant;
+
+/// Progress of a scrape, printed on request while it runs
+pub struct Progress {
+    started: Instant,
+    written: u64,
+    last_id: Option<u128>,
+    last_page: Option<u32>,
+}
+
+impl Progress {
+    pub fn new() -> Self {
+        Self {
+            started: Instant::now(),
+            written: 0,
+            last_id: None,
+            last_page: None,
+        }
+    }
+
+    pub fn record(&mut self, id: Option<u128>, page: Option<u32>) {
+        self.written += 1;
+        self.last_id = id.or(self.last_id);
+        self.last_page = page.or(self.last_page);
+    }
+
+    /// Page of the last item written
+    pub fn page(&self) -> Option<u32> {
+        self.last_page
+    }
+}
+
+impl std::fmt::Display for Progress {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} items written in {}s",
+            self.written,
+            self.started.elapsed().as_secs()
+        )?;
+        if let Some(page) = self.last_page {
+            write!(f, ", page {}", page)?;
+        }
+        if let Some(id) = self.last_id {
+            write!(f, ", last tweet {}", id)?;
+        }
+        Ok(())
+    }
+}
+
+/// Requests for a status dump, sent with SIGUSR1 on Unix
+pub struct StatusSignal {
+    #[cfg(unix)]
+    signal: Option<tokio::signal::unix::Signal>,
+}
+
+impl StatusSignal {
+    pub fn new() -> Self {
+        Self {
+            #[cfg(unix)]
+            signal: tokio::signal::unix::signal(tokio::signal::unix::SignalKind::user_defined1())
+                .ok(),
+        }
+    }
+
+    /// Wait for the next request, which never comes on other platforms
+    pub async fn recv(&mut self) {
+        #[cfg(unix)]
+        if let Some(signal) = &mut self.signal {
+            if signal.recv().await.is_some() {
+                return;
+            }
+        }
+        std::future::pending().await
+    }
+}
@@ -0,0 +1,88 @@
+use std::path::PathBuf;
+
+use nitter_scraper::NitterQuery;
+use time::macros::format_description;
+use time::OffsetDateTime;
+
+/// Expand variables in an output path, e.g. "out/{user}/{date}.jsonl"
+///
+/// Supported variables are `{query}`, `{user}` for queries of a single user, and `{date}`, the
+/// current UTC date.
+pub fn expand_output_path(template: &str, query: &NitterQuery) -> Result<PathBuf, String> {
+    let date = OffsetDateTime::now_utc()
+        .format(format_description!("[year]-[month]-[day]"))
+        .unwrap();
+    expand(template, query, &date)
+}
+
+fn expand(template: &str, query: &NitterQuery, date: &str) -> Result<PathBuf, String> {
+    let mut path = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        path.push_str(&rest[..start]);
+        let end = rest[start..]
+            .find('}')
+            .ok_or_else(|| format!("unclosed variable in {:?}", template))?;
+        let value = match &rest[start + 1..start + end] {
+            "query" => query.to_string(),
+            "user" => query_user(query)
+                .ok_or_else(|| format!("{{user}} is not available for {}", query))?
+                .to_owned(),
+            "date" => date.to_owned(),
+            variable => return Err(format!("unknown variable {{{}}}", variable)),
+        };
+        path.push_str(&sanitize(&value));
+        rest = &rest[start + end + 1..];
+    }
+    path.push_str(rest);
+    Ok(PathBuf::from(path))
+}
+
+fn query_user(query: &NitterQuery) -> Option<&str> {
+    match query {
+        NitterQuery::User { user }
+        | NitterQuery::UserWithReplies { user }
+        | NitterQuery::UserMedia { user }
+        | NitterQuery::UserSearch { user, .. } => Some(user),
+        _ => None,
+    }
+}
+
+/// Make a value safe to use as a single path component on any platform
+fn sanitize(value: &str) -> String {
+    value
+        .chars()
+        .map(|c| match c {
+            c if c.is_alphanumeric() || matches!(c, '-' | '_' | '.' | '@' | '#') => c,
+            _ => '_',
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_expand() {
+        let query = NitterQuery::UserSearch {
+            user: "jack".into(),
+            query: "from:jack lang:en".into(),
+        };
+        assert_eq!(
+            expand("out/{user}/{query}_{date}.jsonl", &query, "2023-01-05").unwrap(),
+            PathBuf::from("out/jack/user-search_jack_from_jack_lang_en_2023-01-05.jsonl")
+        );
+        assert_eq!(
+            expand("plain.jsonl", &query, "").unwrap(),
+            PathBuf::from("plain.jsonl")
+        );
+
+        let search = NitterQuery::Search {
+            query: "rust".into(),
+        };
+        assert!(expand("{user}.jsonl", &search, "").is_err());
+        assert!(expand("{nope}.jsonl", &search, "").is_err());
+        assert!(expand("{query.jsonl", &search, "").is_err());
+    }
+}
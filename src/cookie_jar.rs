@@ -0,0 +1,283 @@
+use std::collections::BTreeMap;
+#[cfg(feature = "fs")]
+use std::fs;
+#[cfg(feature = "fs")]
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use http::header::{HeaderMap, SET_COOKIE};
+use time::format_description::well_known::Rfc2822;
+use time::OffsetDateTime;
+use tokio_util::task::TaskTracker;
+use url::Url;
+use web_time::{SystemTime, UNIX_EPOCH};
+
+#[cfg(feature = "fs")]
+use crate::atomic::write_private;
+
+/// Cookies set by instances, sent back with later requests to the same host, e.g. ones proving a
+/// challenge was passed
+///
+/// Cookies are kept until an instance removes them with `Max-Age=0` or an `Expires` date in the
+/// past, other attributes such as their path are ignored.
+#[derive(Debug)]
+pub struct CookieJar {
+    #[cfg(feature = "fs")]
+    path: Option<PathBuf>,
+    /// Cookies by host, then by name
+    cookies: Mutex<BTreeMap<String, BTreeMap<String, String>>>,
+    /// Held while writing the file, so writes land in the order the cookies changed
+    #[cfg(feature = "fs")]
+    file_lock: tokio::sync::Mutex<()>,
+    /// Writes of the file
+    writes: TaskTracker,
+}
+
+impl CookieJar {
+    pub fn in_memory() -> Self {
+        Self {
+            #[cfg(feature = "fs")]
+            path: None,
+            cookies: Mutex::default(),
+            #[cfg(feature = "fs")]
+            file_lock: tokio::sync::Mutex::default(),
+            writes: TaskTracker::new(),
+        }
+    }
+
+    /// Keep the cookies in a file so they persist across runs, loading the ones it has now
+    ///
+    /// The file is only readable by its owner, as the cookies may prove a session.
+    ///
+    /// Reading or writing the file is best effort, failures start from or keep an empty jar.
+    #[cfg(feature = "fs")]
+    pub fn file(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        let cookies = fs::read(&path)
+            .ok()
+            .and_then(|json| serde_json::from_slice(&json).ok())
+            .unwrap_or_default();
+        Self {
+            path: Some(path),
+            cookies: Mutex::new(cookies),
+            file_lock: tokio::sync::Mutex::default(),
+            writes: TaskTracker::new(),
+        }
+    }
+
+    /// Value of the `Cookie` header for a request to `url`, if the host set any cookies
+    pub(crate) fn header(&self, url: &str) -> Option<String> {
+        let host = host(url)?;
+        let cookies = self.cookies.lock().unwrap();
+        let header = cookies
+            .get(&host)?
+            .iter()
+            .map(|(name, value)| format!("{}={}", name, value))
+            .collect::<Vec<_>>()
+            .join("; ");
+        Some(header).filter(|header| !header.is_empty())
+    }
+
+    /// Store the cookies set by a response from `url`
+    pub(crate) async fn store(&self, url: &str, headers: &HeaderMap) {
+        let Some(host) = host(url) else {
+            return;
+        };
+        let mut changed = false;
+        {
+            let mut cookies = self.cookies.lock().unwrap();
+            for set_cookie in headers.get_all(SET_COOKIE) {
+                let Some((name, value, removed)) =
+                    set_cookie.to_str().ok().and_then(parse_set_cookie)
+                else {
+                    continue;
+                };
+                let host_cookies = cookies.entry(host.clone()).or_default();
+                changed |= if removed {
+                    host_cookies.remove(name).is_some()
+                } else {
+                    host_cookies
+                        .insert(name.to_owned(), value.to_owned())
+                        .as_deref()
+                        != Some(value)
+                };
+            }
+            cookies.retain(|_, host_cookies| !host_cookies.is_empty());
+        }
+
+        if changed {
+            self.save().await;
+        }
+    }
+
+    /// Write the cookies to the file, if the jar has one
+    async fn save(&self) {
+        #[cfg(feature = "fs")]
+        if let Some(path) = &self.path {
+            let _file_lock = self.file_lock.lock().await;
+            // Serialized after taking the lock, so a later change isn't overwritten by this one
+            let json = serde_json::to_vec(&*self.cookies.lock().unwrap()).unwrap();
+            let path = path.clone();
+            let _ = self
+                .writes
+                .spawn_blocking(move || write_private(&path, &json))
+                .await;
+        }
+    }
+
+    /// Wait for the writes of the file, e.g. by a search that was dropped
+    pub(crate) async fn wait_for_writes(&self) {
+        self.writes.close();
+        self.writes.wait().await;
+        self.writes.reopen();
+    }
+}
+
+fn host(url: &str) -> Option<String> {
+    let url = Url::parse(url).ok()?;
+    let host = url.host_str()?;
+    Some(match url.port() {
+        Some(port) => format!("{}:{}", host, port),
+        None => host.to_owned(),
+    })
+}
+
+/// Name and value of a `Set-Cookie` header, and whether it removes the cookie, with a `Max-Age`
+/// of zero or less, or without one, an `Expires` date in the past
+fn parse_set_cookie(set_cookie: &str) -> Option<(&str, &str, bool)> {
+    let mut parts = set_cookie.split(';');
+    let (name, value) = parts.next()?.split_once('=')?;
+    let name = name.trim();
+    if name.is_empty() {
+        return None;
+    }
+    let mut max_age = None;
+    let mut expires = None;
+    for (key, value) in parts.filter_map(|attribute| attribute.split_once('=')) {
+        let key = key.trim();
+        if key.eq_ignore_ascii_case("max-age") {
+            max_age = value.trim().parse::<i64>().ok();
+        } else if key.eq_ignore_ascii_case("expires") {
+            expires = parse_expires(value.trim());
+        }
+    }
+    let removed = match (max_age, expires) {
+        (Some(max_age), _) => max_age <= 0,
+        (None, Some(expires)) => expires.unix_timestamp() <= now_timestamp(),
+        (None, None) => false,
+    };
+    Some((name, value.trim(), removed))
+}
+
+/// Date of an `Expires` attribute, an IMF-fixdate such as `Wed, 21 Oct 2015 07:28:00 GMT`, or
+/// the older `Wed, 21-Oct-2015 07:28:00 GMT`
+fn parse_expires(expires: &str) -> Option<OffsetDateTime> {
+    OffsetDateTime::parse(expires, &Rfc2822)
+        .or_else(|_| OffsetDateTime::parse(&expires.replace('-', " "), &Rfc2822))
+        .ok()
+}
+
+/// Seconds since the Unix epoch, from a clock that also works in browsers
+fn now_timestamp() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs() as i64)
+}
+
+#[cfg(test)]
+mod test {
+    use http::header::HeaderValue;
+
+    use super::*;
+
+    #[cfg(feature = "fs")]
+    #[tokio::test]
+    async fn test_cookie_jar() {
+        let path = std::env::temp_dir().join(format!("cookies-test-{}.json", std::process::id()));
+        let url = "https://nitter.net/jack";
+        let mut headers = HeaderMap::new();
+        headers.append(
+            SET_COOKIE,
+            HeaderValue::from_static("cf_clearance=abc; Path=/"),
+        );
+        headers.append(SET_COOKIE, HeaderValue::from_static("session=1; HttpOnly"));
+
+        let jar = CookieJar::file(&path);
+        assert!(jar.header(url).is_none());
+        jar.store(url, &headers).await;
+        assert_eq!(
+            jar.header("https://nitter.net/search").as_deref(),
+            Some("cf_clearance=abc; session=1")
+        );
+        // Cookies are only sent back to the host that set them
+        assert!(jar.header("https://t.co/abc").is_none());
+        assert!(jar.header("https://nitter.net:8443/jack").is_none());
+
+        // Cookies are loaded from the file by the next run
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+        let jar = CookieJar::file(&path);
+        assert_eq!(
+            jar.header(url).as_deref(),
+            Some("cf_clearance=abc; session=1")
+        );
+        let mut headers = HeaderMap::new();
+        headers.append(SET_COOKIE, HeaderValue::from_static("session=; Max-Age=0"));
+        jar.store(url, &headers).await;
+        assert_eq!(jar.header(url).as_deref(), Some("cf_clearance=abc"));
+        assert_eq!(
+            CookieJar::file(&path).header(url).as_deref(),
+            Some("cf_clearance=abc")
+        );
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_parse_set_cookie() {
+        assert_eq!(
+            parse_set_cookie("session=1; Expires=Wed, 21 Oct 2099 07:28:00 GMT"),
+            Some(("session", "1", false))
+        );
+        assert_eq!(
+            parse_set_cookie("session=; Expires=Thu, 01 Jan 1970 00:00:00 GMT; Path=/"),
+            Some(("session", "", true))
+        );
+        assert_eq!(
+            parse_set_cookie("session=; expires=Wed, 21-Oct-2015 07:28:00 GMT"),
+            Some(("session", "", true))
+        );
+        // Max-Age takes precedence over Expires
+        assert_eq!(
+            parse_set_cookie("session=1; Expires=Wed, 21 Oct 2015 07:28:00 GMT; Max-Age=60"),
+            Some(("session", "1", false))
+        );
+        // Dates that can't be parsed are ignored
+        assert_eq!(
+            parse_set_cookie("session=1; Expires=yesterday"),
+            Some(("session", "1", false))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_expired_cookie() {
+        let url = "https://nitter.net/jack";
+        let jar = CookieJar::in_memory();
+        let mut headers = HeaderMap::new();
+        headers.append(SET_COOKIE, HeaderValue::from_static("session=1"));
+        jar.store(url, &headers).await;
+        assert_eq!(jar.header(url).as_deref(), Some("session=1"));
+
+        let mut headers = HeaderMap::new();
+        headers.append(
+            SET_COOKIE,
+            HeaderValue::from_static("session=; Expires=Thu, 01 Jan 1970 00:00:00 GMT"),
+        );
+        jar.store(url, &headers).await;
+        assert!(jar.header(url).is_none());
+    }
+}
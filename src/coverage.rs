@@ -0,0 +1,40 @@
+use std::collections::BTreeMap;
+
+/// Per-field extraction success counts, to detect markup changes that silently empty a field
+#[derive(Debug, Default, Clone)]
+pub struct FieldCoverage {
+    /// Field name to (extracted, expected) counts
+    counts: BTreeMap<&'static str, (u64, u64)>,
+}
+
+impl FieldCoverage {
+    /// Record that a field's markup was present, and whether the field was extracted from it
+    pub(crate) fn record(&mut self, field: &'static str, extracted: bool) {
+        let (e, total) = self.counts.entry(field).or_default();
+        *e += extracted as u64;
+        *total += 1;
+    }
+
+    /// Iterate over (field, extracted, expected) counts
+    pub fn iter(&self) -> impl Iterator<Item = (&'static str, u64, u64)> + '_ {
+        self.counts.iter().map(|(f, (e, total))| (*f, *e, *total))
+    }
+
+    /// Whether any field failed to be extracted from its markup
+    pub fn has_missing(&self) -> bool {
+        self.iter()
+            .any(|(_, extracted, expected)| extracted < expected)
+    }
+}
+
+impl std::fmt::Display for FieldCoverage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, (field, extracted, expected)) in self.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}: {}/{} extracted", field, extracted, expected)?;
+        }
+        Ok(())
+    }
+}
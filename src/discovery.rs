@@ -0,0 +1,168 @@
+use std::time::Duration;
+
+use http::header::HeaderMap;
+use serde::Deserialize;
+use typed_builder::TypedBuilder;
+
+use crate::error::NitterError;
+use crate::health::check_instance;
+use crate::http_client::{HttpClient, HttpMethod};
+
+/// Community maintained status page API listing public instances and their uptime
+pub const DEFAULT_INSTANCE_LIST: &str = "https://status.d420.de/api/v1/instances";
+
+/// Finds working public instances, so they don't have to be hardcoded
+#[derive(TypedBuilder)]
+pub struct InstanceDiscovery<'a> {
+    #[builder(setter(into))]
+    client: HttpClient<'a>,
+
+    /// URL of an instance list in the format of [`DEFAULT_INSTANCE_LIST`]
+    #[builder(default = DEFAULT_INSTANCE_LIST.into(), setter(into))]
+    list_url: String,
+
+    /// Minimum percentage of the list's health checks an instance must have passed
+    #[builder(default = 90.0)]
+    min_uptime: f64,
+
+    /// How many of the best listed instances to check ourselves, 0 to trust the list
+    #[builder(default = 5)]
+    check: usize,
+}
+
+/// A public instance from the instance list
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct PublicInstance {
+    pub url: String,
+    /// Percentage of the list's health checks the instance passed
+    pub uptime: Option<f64>,
+    /// Average response time measured by the list
+    pub ping: Option<Duration>,
+    pub rss: bool,
+    /// Response time of our own health check, if it was checked
+    pub latency: Option<Duration>,
+}
+
+#[derive(Deserialize)]
+struct InstanceList {
+    hosts: Vec<ListedInstance>,
+}
+
+#[derive(Deserialize)]
+struct ListedInstance {
+    url: String,
+    #[serde(default)]
+    healthy: bool,
+    #[serde(default)]
+    is_bad_host: bool,
+    #[serde(default)]
+    rss: bool,
+    ping_avg: Option<f64>,
+    healthy_percentage_overall: Option<f64>,
+}
+
+impl InstanceDiscovery<'_> {
+    /// Fetch the instance list and return the healthy instances, best first
+    ///
+    /// Checked instances are ordered by their response time, and come before unchecked ones.
+    pub async fn instances(&self) -> Result<Vec<PublicInstance>, NitterError> {
+        let response = self
+            .client
+            .send(HttpMethod::Get, &self.list_url, HeaderMap::new())
+            .await?;
+        if !response.status.is_success() {
+            return Err(NitterError::Network(format!(
+                "received status code {}",
+                response.status.as_u16()
+            )));
+        }
+        let mut instances = rank_instances(&response.body, self.min_uptime)?;
+
+        let checked = instances.len().min(self.check);
+        let checks = futures_util::future::join_all(
+            instances[..checked]
+                .iter()
+                .map(|instance| check_instance(self.client, &instance.url)),
+        )
+        .await;
+        let mut healthy: Vec<_> = instances
+            .drain(..checked)
+            .zip(checks)
+            .filter(|(_, check)| check.is_healthy())
+            .map(|(instance, check)| PublicInstance {
+                latency: Some(check.latency),
+                ..instance
+            })
+            .collect();
+        healthy.sort_by_key(|instance| instance.latency);
+        healthy.extend(instances);
+        Ok(healthy)
+    }
+}
+
+/// Parse an instance list, keeping healthy instances ordered by uptime and then response time
+fn rank_instances(json: &str, min_uptime: f64) -> Result<Vec<PublicInstance>, NitterError> {
+    let list: InstanceList = serde_json::from_str(json)
+        .map_err(|e| NitterError::Parse(format!("invalid instance list: {}", e)))?;
+
+    let mut instances: Vec<_> = list
+        .hosts
+        .into_iter()
+        .filter(|host| host.healthy && !host.is_bad_host)
+        .filter(|host| {
+            host.healthy_percentage_overall
+                .is_none_or(|uptime| uptime >= min_uptime)
+        })
+        .map(|host| PublicInstance {
+            url: host.url.trim_end_matches('/').to_owned(),
+            uptime: host.healthy_percentage_overall,
+            ping: host
+                .ping_avg
+                .filter(|ping| *ping >= 0.0)
+                .map(|ping| Duration::from_secs_f64(ping / 1000.0)),
+            rss: host.rss,
+            latency: None,
+        })
+        .collect();
+    instances.sort_by(|a, b| {
+        let uptime = b.uptime.unwrap_or(0.0).total_cmp(&a.uptime.unwrap_or(0.0));
+        uptime.then_with(|| {
+            a.ping
+                .unwrap_or(Duration::MAX)
+                .cmp(&b.ping.unwrap_or(Duration::MAX))
+        })
+    });
+    Ok(instances)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_rank_instances() {
+        let json = r#"{"hosts": [
+            {"url": "https://slow.example", "healthy": true, "rss": true, "ping_avg": 900.0, "healthy_percentage_overall": 99},
+            {"url": "https://down.example", "healthy": false, "ping_avg": 100.0, "healthy_percentage_overall": 99},
+            {"url": "https://fast.example/", "healthy": true, "ping_avg": 150.5, "healthy_percentage_overall": 99},
+            {"url": "https://flaky.example", "healthy": true, "ping_avg": 50.0, "healthy_percentage_overall": 60},
+            {"url": "https://bad.example", "healthy": true, "is_bad_host": true, "healthy_percentage_overall": 100},
+            {"url": "https://best.example", "healthy": true, "healthy_percentage_overall": 100}
+        ], "last_update": "2024-01-01T00:00:00"}"#;
+        let instances = rank_instances(json, 90.0).unwrap();
+        let urls: Vec<_> = instances.iter().map(|i| i.url.as_str()).collect();
+        assert_eq!(
+            urls,
+            [
+                "https://best.example",
+                "https://fast.example",
+                "https://slow.example"
+            ]
+        );
+        assert_eq!(instances[1].ping, Some(Duration::from_micros(150_500)));
+        assert!(instances[2].rss);
+
+        assert!(rank_instances("<html></html>", 90.0).is_err());
+    }
+}
@@ -1,12 +1,42 @@
 use std::process::ExitCode;
+use std::time::Duration;
 
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum NitterError {
     Parse(String),
     Network(String),
+    Connect(String),
+    ConnectTimeout,
+    Tls(String),
+    /// The request took longer than the client's timeout
+    Timeout,
+    /// The instance didn't start responding, or stopped sending the body, for longer than the
+    /// read timeout
+    ReadTimeout,
+    /// The instance kept sending the body, but not all of it within the body timeout
+    BodyTimeout,
     ProtectedAccount,
     SuspendedAccount,
     NotFound,
+    /// The instance is rate limited, `status` is the status code it responded with, which is
+    /// 200 for a rate limit error page, and `retry_after` is how long it asked to wait if it said
+    RateLimited {
+        status: u16,
+        retry_after: Option<Duration>,
+    },
+    InvalidQuery(String),
+    InvalidSelector(String),
+    InvalidInstance(String),
+    InvalidCookie(String),
+    InvalidProxy(String),
+    /// The instance responded with an anti-bot challenge page, e.g. from Cloudflare, instead of
+    /// the requested page
+    Challenge(String),
+    /// The response body was larger than the limit of this many bytes
+    ResponseTooLarge(usize),
+    /// The scrape's deadline passed before it was done
+    DeadlineExceeded,
 }
 
 impl std::fmt::Display for NitterError {
@@ -14,14 +44,72 @@ impl std::fmt::Display for NitterError {
         match self {
             Self::Parse(s) => write!(f, "unable to parse nitter: {}", s),
             Self::Network(s) => write!(f, "unable to send request: {}", s),
+            Self::Connect(s) => write!(f, "unable to connect to instance: {}", s),
+            Self::ConnectTimeout => write!(f, "timed out connecting to instance"),
+            Self::Tls(s) => write!(f, "TLS handshake with instance failed: {}", s),
+            Self::Timeout => write!(f, "timed out waiting for instance response"),
+            Self::ReadTimeout => write!(f, "timed out waiting for instance to send data"),
+            Self::BodyTimeout => write!(f, "instance sent the response too slowly"),
             Self::ProtectedAccount => write!(f, "account is protected"),
             Self::SuspendedAccount => write!(f, "account is suspended"),
             Self::NotFound => write!(f, "account not found"),
+            Self::RateLimited {
+                retry_after: Some(retry_after),
+                ..
+            } => write!(
+                f,
+                "instance is rate limited, retry after {} seconds",
+                retry_after.as_secs()
+            ),
+            Self::RateLimited {
+                retry_after: None, ..
+            } => write!(f, "instance is rate limited"),
+            Self::InvalidQuery(s) => write!(f, "invalid query: {}", s),
+            Self::InvalidSelector(s) => write!(f, "invalid selector: {}", s),
+            Self::InvalidInstance(s) => write!(f, "invalid instance URL: {}", s),
+            Self::InvalidCookie(s) => write!(f, "invalid cookie: {}", s),
+            Self::InvalidProxy(s) => write!(f, "invalid proxy URL: {}", s),
+            Self::Challenge(s) => write!(f, "instance responded with a {} challenge page", s),
+            Self::ResponseTooLarge(limit) => {
+                write!(f, "response is larger than the limit of {} bytes", limit)
+            }
+            Self::DeadlineExceeded => write!(f, "scrape didn't finish before its deadline"),
         }
     }
 }
 
 impl NitterError {
+    /// Classify a request error by the phase it failed in
+    #[cfg(feature = "reqwest")]
+    pub(crate) fn from_reqwest(e: reqwest::Error) -> Self {
+        if e.is_connect() {
+            if e.is_timeout() {
+                return Self::ConnectTimeout;
+            }
+            // rustls reports handshake failures as InvalidData IO errors
+            let mut source = std::error::Error::source(&e);
+            while let Some(err) = source {
+                if let Some(io) = err.downcast_ref::<std::io::Error>() {
+                    if io.kind() == std::io::ErrorKind::InvalidData {
+                        return Self::Tls(io.to_string());
+                    }
+                }
+                source = err.source();
+            }
+            Self::Connect(e.to_string())
+        } else if e.is_timeout() {
+            Self::Timeout
+        } else {
+            Self::Network(e.to_string())
+        }
+    }
+
+    /// Whether the instance could not be reached at all, as opposed to being slow or returning
+    /// an error
+    pub fn is_unreachable(&self) -> bool {
+        matches!(self, Self::Connect(_) | Self::ConnectTimeout | Self::Tls(_))
+    }
+
     pub fn exit_code(&self) -> ExitCode {
         match self {
             Self::ProtectedAccount | Self::SuspendedAccount | Self::NotFound => ExitCode::from(10),
@@ -0,0 +1,133 @@
+use std::time::Duration;
+
+use http::header::{HeaderMap, HeaderValue, COOKIE};
+use web_time::Instant;
+
+use crate::coverage::FieldCoverage;
+use crate::error::NitterError;
+use crate::http_client::{HttpClient, HttpMethod};
+use crate::nitter_scraper::{challenge_provider, PREFERENCES_COOKIE};
+use crate::parse::{parse_nitter_html, ParseOptions};
+use crate::tweet::TimelineItem;
+
+/// Account whose timeline is fetched to check instances, it's unlikely to go anywhere
+const PROBE_USER: &str = "jack";
+
+/// Result of [`NitterScraper::check_instance`](crate::NitterScraper::check_instance)
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct InstanceCheck {
+    pub health: InstanceHealth,
+    /// Time until the probe page was received, or until the request failed
+    pub latency: Duration,
+}
+
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum InstanceHealth {
+    /// The probe timeline loaded and its tweets parsed
+    Healthy,
+    /// The instance responded, but without tweets that could be parsed, e.g. because its markup
+    /// changed or it can't reach Twitter
+    Degraded(String),
+    /// The instance couldn't be reached or responded with an error
+    Down(NitterError),
+}
+
+impl InstanceCheck {
+    pub fn is_healthy(&self) -> bool {
+        matches!(self.health, InstanceHealth::Healthy)
+    }
+}
+
+impl std::fmt::Display for InstanceHealth {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Healthy => write!(f, "healthy"),
+            Self::Degraded(reason) => write!(f, "degraded ({})", reason),
+            Self::Down(e) => write!(f, "down ({})", e),
+        }
+    }
+}
+
+pub(crate) async fn check_instance(client: HttpClient<'_>, instance: &str) -> InstanceCheck {
+    let url = format!("{}/{}", instance.trim_end_matches('/'), PROBE_USER);
+    let mut headers = HeaderMap::new();
+    headers.insert(COOKIE, HeaderValue::from_static(PREFERENCES_COOKIE));
+
+    let started = Instant::now();
+    let text = async {
+        let response = client.send(HttpMethod::Get, &url, headers).await?;
+        if let Some(provider) = challenge_provider(&response) {
+            return Err(NitterError::Challenge(provider.to_owned()));
+        }
+        if !response.status.is_success() {
+            return Err(NitterError::Network(format!(
+                "received status code {}",
+                response.status.as_u16()
+            )));
+        }
+        Ok(response.body)
+    }
+    .await;
+    let latency = started.elapsed();
+
+    InstanceCheck {
+        health: probe_health(text),
+        latency,
+    }
+}
+
+fn probe_health(text: Result<String, NitterError>) -> InstanceHealth {
+    let text = match text {
+        Ok(text) => text,
+        Err(e) => return InstanceHealth::Down(e),
+    };
+    match parse_nitter_html(
+        text,
+        &ParseOptions::default(),
+        &mut FieldCoverage::default(),
+    ) {
+        Ok((items, _)) if items.iter().any(|i| matches!(i, TimelineItem::Tweet(_))) => {
+            InstanceHealth::Healthy
+        }
+        Ok(_) => InstanceHealth::Degraded(format!("no tweets on /{}", PROBE_USER)),
+        Err(e) => InstanceHealth::Degraded(e.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_probe_health() {
+        // Synthetic pages, with only the markup the parser reads
+        let timeline = r#"<html><body><div class="timeline"><div class="timeline-item">
+            <a class="fullname" href="/jack" title="jack">jack</a>
+            <span class="tweet-date"><a href="/jack/status/20#m" title="Mar 21, 2006 · 8:50 PM UTC">21 Mar 2006</a></span>
+            <div class="tweet-content media-body">just setting up my twttr</div>
+        </div></div></body></html>"#;
+        assert!(matches!(
+            probe_health(Ok(timeline.into())),
+            InstanceHealth::Healthy
+        ));
+
+        let empty = r#"<html><body><div class="timeline"></div></body></html>"#;
+        assert!(matches!(
+            probe_health(Ok(empty.into())),
+            InstanceHealth::Degraded(_)
+        ));
+
+        let not_found = r#"<html><body><div class="error-panel"><span>User "jack" not found</span></div></body></html>"#;
+        assert!(matches!(
+            probe_health(Ok(not_found.into())),
+            InstanceHealth::Degraded(_)
+        ));
+
+        assert!(matches!(
+            probe_health(Err(NitterError::Timeout)),
+            InstanceHealth::Down(NitterError::Timeout)
+        ));
+    }
+}
@@ -0,0 +1,505 @@
+#[cfg(feature = "reqwest")]
+use std::future::Future;
+use std::time::Duration;
+
+use futures_util::future::BoxFuture;
+use http::header::{HeaderMap, HeaderName, HeaderValue};
+use http::StatusCode;
+#[cfg(feature = "reqwest")]
+use reqwest::{Client, Method, Response};
+
+use crate::error::NitterError;
+#[cfg(feature = "reqwest")]
+use crate::timer::{self, Instant};
+
+/// SOCKS port of a local Tor daemon
+#[cfg(feature = "socks")]
+pub const TOR_PROXY: &str = "socks5h://127.0.0.1:9050";
+
+/// HTTP client to send requests with, a reqwest `Client` with the default `reqwest` feature, with
+/// the `reqwest-middleware` feature a client with a middleware stack, or any other [`HttpFetch`]
+/// implementation
+#[derive(Clone, Copy)]
+#[non_exhaustive]
+pub enum HttpClient<'a> {
+    #[cfg(feature = "reqwest")]
+    Reqwest(&'a Client),
+    #[cfg(feature = "reqwest-middleware")]
+    Middleware(&'a reqwest_middleware::ClientWithMiddleware),
+    Custom(&'a dyn HttpFetch),
+}
+
+impl std::fmt::Debug for HttpClient<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            #[cfg(feature = "reqwest")]
+            Self::Reqwest(client) => f.debug_tuple("Reqwest").field(client).finish(),
+            #[cfg(feature = "reqwest-middleware")]
+            Self::Middleware(client) => f.debug_tuple("Middleware").field(client).finish(),
+            Self::Custom(_) => f.write_str("Custom"),
+        }
+    }
+}
+
+impl<'a> HttpClient<'a> {
+    pub(crate) async fn send(
+        &self,
+        method: HttpMethod,
+        url: &str,
+        headers: HeaderMap,
+    ) -> Result<HttpResponse, NitterError> {
+        self.send_limited(method, url, headers, ResponseLimits::default())
+            .await
+    }
+
+    /// Send a request, failing if the response is larger or slower than the limits
+    pub(crate) async fn send_limited(
+        &self,
+        method: HttpMethod,
+        url: &str,
+        headers: HeaderMap,
+        limits: ResponseLimits,
+    ) -> Result<HttpResponse, NitterError> {
+        let fetch: &dyn HttpFetch = match self {
+            #[cfg(feature = "reqwest")]
+            Self::Reqwest(client) => *client,
+            #[cfg(feature = "reqwest-middleware")]
+            Self::Middleware(client) => *client,
+            Self::Custom(fetch) => *fetch,
+        };
+        let request = HttpRequest {
+            method,
+            url: url.to_owned(),
+            headers: headers
+                .iter()
+                .map(|(name, value)| {
+                    let value = String::from_utf8_lossy(value.as_bytes()).into_owned();
+                    (name.to_string(), value)
+                })
+                .collect(),
+            read_timeout: limits.read_timeout,
+            body_timeout: limits.body_timeout,
+        };
+        match limits.max_size {
+            Some(max_size) => fetch.fetch_limited(request, max_size).await,
+            None => fetch.fetch(request).await,
+        }
+    }
+}
+
+/// Sends the scraper's requests, to use an HTTP library other than reqwest, or to answer them
+/// without a network, e.g. in tests
+///
+/// This is meant to be implemented outside of the crate, so it only uses the crate's own types
+/// rather than reqwest's, and new methods will have default implementations. Redirects shouldn't
+/// be followed for `HEAD` requests, their `Location` is what links are expanded to. Requests
+/// should fail with [`NitterError::ReadTimeout`] and [`NitterError::BodyTimeout`] once their
+/// timeouts pass.
+///
+/// On wasm32, built without default features, an implementation sending requests with the
+/// browser's `fetch` is how the scraper reaches instances.
+pub trait HttpFetch: Send + Sync {
+    fn fetch(&self, request: HttpRequest) -> BoxFuture<'_, Result<HttpResponse, NitterError>>;
+
+    /// Like [`fetch`](Self::fetch), but fails with [`NitterError::ResponseTooLarge`] if the body
+    /// is larger than `max_size` bytes
+    ///
+    /// The provided implementation checks the body once it was read in full, implementations
+    /// should stop reading as soon as the limit is exceeded instead.
+    fn fetch_limited(
+        &self,
+        request: HttpRequest,
+        max_size: usize,
+    ) -> BoxFuture<'_, Result<HttpResponse, NitterError>> {
+        Box::pin(async move {
+            let response = self.fetch(request).await?;
+            if response.body.len() > max_size {
+                return Err(NitterError::ResponseTooLarge(max_size));
+            }
+            Ok(response)
+        })
+    }
+}
+
+/// Method of an [`HttpRequest`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum HttpMethod {
+    Get,
+    Head,
+}
+
+impl HttpMethod {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Get => "GET",
+            Self::Head => "HEAD",
+        }
+    }
+
+    #[cfg(feature = "reqwest")]
+    fn to_reqwest(self) -> Method {
+        match self {
+            Self::Get => Method::GET,
+            Self::Head => Method::HEAD,
+        }
+    }
+}
+
+/// A request for an [`HttpFetch`] implementation to send
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct HttpRequest {
+    pub method: HttpMethod,
+    pub url: String,
+    /// Names and values of the headers, names may repeat
+    pub headers: Vec<(String, String)>,
+    /// Longest time to wait for the response to start, or for more of its body
+    pub read_timeout: Option<Duration>,
+    /// Longest time to read the whole body in, once the response started
+    pub body_timeout: Option<Duration>,
+}
+
+#[cfg(feature = "reqwest")]
+impl HttpRequest {
+    fn reqwest_headers(&self) -> HeaderMap {
+        header_map(&self.headers)
+    }
+}
+
+/// Limits on the responses to a scraper's requests
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct ResponseLimits {
+    pub(crate) max_size: Option<usize>,
+    pub(crate) read_timeout: Option<Duration>,
+    pub(crate) body_timeout: Option<Duration>,
+}
+
+/// Headers that are valid, others are left out
+fn header_map(headers: &[(String, String)]) -> HeaderMap {
+    headers
+        .iter()
+        .filter_map(|(name, value)| {
+            Some((
+                HeaderName::from_bytes(name.as_bytes()).ok()?,
+                HeaderValue::from_str(value).ok()?,
+            ))
+        })
+        .collect()
+}
+
+/// A response read in full
+#[derive(Debug, Clone)]
+pub struct HttpResponse {
+    pub(crate) status: StatusCode,
+    pub(crate) headers: HeaderMap,
+    /// URL of the response, after any redirects that were followed
+    pub(crate) url: String,
+    pub(crate) body: String,
+}
+
+impl HttpResponse {
+    /// A response with a status code, header names and values, the URL it was received from
+    /// after following any redirects, and the body
+    ///
+    /// Fails if the status code isn't between 100 and 999, headers that aren't valid are left out.
+    pub fn new(
+        status: u16,
+        headers: Vec<(String, String)>,
+        url: String,
+        body: String,
+    ) -> Result<Self, NitterError> {
+        let status = StatusCode::from_u16(status)
+            .map_err(|_| NitterError::Network(format!("invalid status code {}", status)))?;
+        Ok(Self::from_parts(status, header_map(&headers), url, body))
+    }
+
+    pub(crate) fn from_parts(
+        status: StatusCode,
+        headers: HeaderMap,
+        url: String,
+        body: String,
+    ) -> Self {
+        Self {
+            status,
+            headers,
+            url,
+            body,
+        }
+    }
+
+    pub fn status(&self) -> u16 {
+        self.status.as_u16()
+    }
+
+    /// Value of the first header named `name`, if it's valid UTF-8
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get(name)?.to_str().ok()
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    pub fn body(&self) -> &str {
+        &self.body
+    }
+
+    /// Read a response, up to `max_size` bytes of its decompressed body, within the request's
+    /// timeouts
+    #[cfg(feature = "reqwest")]
+    async fn read(
+        mut response: Response,
+        request: &HttpRequest,
+        max_size: Option<usize>,
+    ) -> Result<Self, NitterError> {
+        let status = response.status();
+        let headers = response.headers().clone();
+        let url = response.url().to_string();
+        let too_large = |length: usize| max_size.filter(|&max_size| length > max_size);
+
+        if let Some(max_size) = response
+            .content_length()
+            .and_then(|length| too_large(length.try_into().unwrap_or(usize::MAX)))
+        {
+            return Err(NitterError::ResponseTooLarge(max_size));
+        }
+        let body_deadline = deadline(request.body_timeout);
+        let mut body = Vec::new();
+        loop {
+            let chunk = within(
+                response.chunk(),
+                deadline(request.read_timeout),
+                NitterError::ReadTimeout,
+            );
+            let chunk = within(chunk, body_deadline, NitterError::BodyTimeout).await??;
+            let Some(chunk) = chunk.map_err(NitterError::from_reqwest)? else {
+                break;
+            };
+            if let Some(max_size) = too_large(body.len() + chunk.len()) {
+                return Err(NitterError::ResponseTooLarge(max_size));
+            }
+            body.extend_from_slice(&chunk);
+        }
+        let body = String::from_utf8_lossy(&body).into_owned();
+        Ok(Self::from_parts(status, headers, url, body))
+    }
+}
+
+#[cfg(feature = "reqwest")]
+fn deadline(timeout: Option<Duration>) -> Option<Instant> {
+    timeout.map(|timeout| Instant::now() + timeout)
+}
+
+/// Fail with `error` if `future` isn't done by `deadline`
+#[cfg(feature = "reqwest")]
+async fn within<T>(
+    future: impl Future<Output = T>,
+    deadline: Option<Instant>,
+    error: NitterError,
+) -> Result<T, NitterError> {
+    match deadline {
+        Some(deadline) => timer::timeout_at(deadline, future).await.map_err(|_| error),
+        None => Ok(future.await),
+    }
+}
+
+#[cfg(feature = "reqwest")]
+impl HttpFetch for Client {
+    fn fetch(&self, request: HttpRequest) -> BoxFuture<'_, Result<HttpResponse, NitterError>> {
+        Box::pin(send_reqwest(self, request, None))
+    }
+
+    fn fetch_limited(
+        &self,
+        request: HttpRequest,
+        max_size: usize,
+    ) -> BoxFuture<'_, Result<HttpResponse, NitterError>> {
+        Box::pin(send_reqwest(self, request, Some(max_size)))
+    }
+}
+
+#[cfg(feature = "reqwest")]
+async fn send_reqwest(
+    client: &Client,
+    request: HttpRequest,
+    max_size: Option<usize>,
+) -> Result<HttpResponse, NitterError> {
+    let send = client
+        .request(request.method.to_reqwest(), &request.url)
+        .headers(request.reqwest_headers())
+        .send();
+    let response = within(
+        send,
+        deadline(request.read_timeout),
+        NitterError::ReadTimeout,
+    )
+    .await?
+    .map_err(NitterError::from_reqwest)?;
+    HttpResponse::read(response, &request, max_size).await
+}
+
+#[cfg(feature = "reqwest-middleware")]
+impl HttpFetch for reqwest_middleware::ClientWithMiddleware {
+    fn fetch(&self, request: HttpRequest) -> BoxFuture<'_, Result<HttpResponse, NitterError>> {
+        Box::pin(send_middleware(self, request, None))
+    }
+
+    fn fetch_limited(
+        &self,
+        request: HttpRequest,
+        max_size: usize,
+    ) -> BoxFuture<'_, Result<HttpResponse, NitterError>> {
+        Box::pin(send_middleware(self, request, Some(max_size)))
+    }
+}
+
+#[cfg(feature = "reqwest-middleware")]
+async fn send_middleware(
+    client: &reqwest_middleware::ClientWithMiddleware,
+    request: HttpRequest,
+    max_size: Option<usize>,
+) -> Result<HttpResponse, NitterError> {
+    let send = client
+        .request(request.method.to_reqwest(), &request.url)
+        .headers(request.reqwest_headers())
+        .send();
+    let response = within(
+        send,
+        deadline(request.read_timeout),
+        NitterError::ReadTimeout,
+    )
+    .await?
+    .map_err(|e| match e {
+        reqwest_middleware::Error::Reqwest(e) => NitterError::from_reqwest(e),
+        reqwest_middleware::Error::Middleware(e) => NitterError::Network(format!("{:#}", e)),
+    })?;
+    HttpResponse::read(response, &request, max_size).await
+}
+
+#[cfg(feature = "reqwest")]
+impl<'a> From<&'a Client> for HttpClient<'a> {
+    fn from(client: &'a Client) -> Self {
+        Self::Reqwest(client)
+    }
+}
+
+#[cfg(feature = "reqwest-middleware")]
+impl<'a> From<&'a reqwest_middleware::ClientWithMiddleware> for HttpClient<'a> {
+    fn from(client: &'a reqwest_middleware::ClientWithMiddleware) -> Self {
+        Self::Middleware(client)
+    }
+}
+
+/// Proxy for a SOCKS5 URL, e.g. [`TOR_PROXY`]
+///
+/// `socks5://` URLs are changed to `socks5h://` so host names are resolved by the proxy, which
+/// is needed to reach .onion instances and keeps lookups from bypassing Tor.
+#[cfg(feature = "socks")]
+pub fn socks_proxy(url: &str) -> Result<reqwest::Proxy, NitterError> {
+    let url = match url.strip_prefix("socks5://") {
+        Some(address) => format!("socks5h://{}", address),
+        None if url.starts_with("socks5h://") => url.to_owned(),
+        None => {
+            return Err(NitterError::InvalidProxy(format!(
+                "{} is not a SOCKS5 proxy",
+                url
+            )))
+        }
+    };
+    reqwest::Proxy::all(url).map_err(|e| NitterError::InvalidProxy(e.to_string()))
+}
+
+/// Client that sends requests through a SOCKS5 proxy, see [`socks_proxy`]
+///
+/// Timeouts are longer than usual since requests over Tor take a while.
+#[cfg(feature = "socks")]
+pub fn socks_client(url: &str) -> Result<Client, NitterError> {
+    Client::builder()
+        .proxy(socks_proxy(url)?)
+        .connect_timeout(Duration::from_secs(30))
+        .timeout(Duration::from_secs(60))
+        .build()
+        .map_err(NitterError::from_reqwest)
+}
+
+#[cfg(all(test, feature = "reqwest"))]
+mod test {
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::thread;
+
+    use super::*;
+
+    /// Serve responses that stall before the headers, stall in the body, or trickle the body in
+    fn serve_slowly() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        thread::spawn(move || {
+            for mut stream in listener.incoming().flatten() {
+                thread::spawn(move || {
+                    let mut request = [0; 1024];
+                    let len = stream.read(&mut request).unwrap();
+                    let request = String::from_utf8_lossy(&request[..len]).into_owned();
+                    let pause = Duration::from_millis(500);
+                    if request.starts_with("GET /quiet") {
+                        thread::sleep(pause);
+                    }
+                    let _ = stream.write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 10\r\n\r\n");
+                    for _ in 0..10 {
+                        let _ = stream.write_all(b"a");
+                        let _ = stream.flush();
+                        match request.starts_with("GET /stall") {
+                            true => thread::sleep(pause),
+                            false => thread::sleep(pause / 10),
+                        }
+                    }
+                });
+            }
+        });
+        format!("http://{}", address)
+    }
+
+    #[tokio::test]
+    async fn test_response_timeouts() {
+        let server = serve_slowly();
+        let client = Client::new();
+        let send = |path: &str| {
+            let limits = ResponseLimits {
+                max_size: None,
+                read_timeout: Some(Duration::from_millis(200)),
+                body_timeout: Some(Duration::from_millis(300)),
+            };
+            let url = format!("{}{}", server, path);
+            let client = HttpClient::Reqwest(&client);
+            async move {
+                client
+                    .send_limited(HttpMethod::Get, &url, HeaderMap::new(), limits)
+                    .await
+            }
+        };
+        assert!(matches!(
+            send("/quiet").await,
+            Err(NitterError::ReadTimeout)
+        ));
+        assert!(matches!(
+            send("/stall").await,
+            Err(NitterError::ReadTimeout)
+        ));
+        assert!(matches!(
+            send("/trickle").await,
+            Err(NitterError::BodyTimeout)
+        ));
+    }
+
+    #[cfg(feature = "socks")]
+    #[test]
+    fn test_socks_proxy() {
+        assert!(socks_proxy(TOR_PROXY).is_ok());
+        assert!(socks_proxy("socks5://127.0.0.1:9050").is_ok());
+        assert!(matches!(
+            socks_proxy("http://127.0.0.1:8080"),
+            Err(NitterError::InvalidProxy(_))
+        ));
+    }
+}
@@ -9,11 +9,31 @@ pub fn tweet_time(id: u128) -> OffsetDateTime {
     }
 }
 
-fn id_post_snowflake(id: u128) -> OffsetDateTime {
+/// Exact creation time in milliseconds, only available for snowflake IDs
+pub fn snowflake_time_ms(id: u128) -> Option<i64> {
+    if id <= 29700859247 {
+        return None;
+    }
     let id = id as i64;
     let offset = 1288834974657;
-    let timestamp = (id >> 22) + offset;
-    OffsetDateTime::from_unix_timestamp(timestamp).unwrap()
+    Some((id >> 22) + offset)
+}
+
+/// Smallest ID of tweets created at or after `time`
+pub fn time_to_id(time: OffsetDateTime) -> u128 {
+    // Sequential IDs were still used for a while after the snowflake epoch
+    if time.unix_timestamp() <= TWEET_TIMELINE[2361].1 {
+        return lookup_interp_inverse(time.unix_timestamp()) as u128;
+    }
+    let offset = 1288834974657;
+    let timestamp_ms = (time.unix_timestamp_nanos() / 1_000_000) as i64;
+    let id = ((timestamp_ms - offset) as u128) << 22;
+    id.max(29700859248)
+}
+
+fn id_post_snowflake(id: u128) -> OffsetDateTime {
+    let timestamp_ms = snowflake_time_ms(id).unwrap();
+    OffsetDateTime::from_unix_timestamp_nanos(timestamp_ms as i128 * 1_000_000).unwrap()
 }
 
 fn id_pre_snowflake(id: u128) -> OffsetDateTime {
@@ -37,31 +57,19 @@ fn lookup_interp(id: u64) -> i64 {
     }
 }
 
-#[cfg(test)]
-mod test {
-    use super::*;
+fn lookup_interp_inverse(timestamp: i64) -> u64 {
+    let pivot = TWEET_TIMELINE.partition_point(|&x| x.1 < timestamp);
 
-    #[test]
-    fn test_0() {
-        assert_eq!(lookup_interp(0), 1142974214);
-    }
-
-    #[test]
-    fn test_max() {
-        assert_eq!(lookup_interp(29700859247), 1288904568);
-    }
-
-    #[test]
-    fn test() {
-        assert_eq!(lookup_interp(20), 1142974214);
-        assert_eq!(lookup_interp(75), 1142985109);
-        assert!(lookup_interp(50) > 1142974214 && lookup_interp(50) < 1142985109);
-    }
-
-    #[test]
-    #[should_panic]
-    fn test_past_max() {
-        lookup_interp(29700859248);
+    match pivot {
+        1..=2361 => {
+            let x = (TWEET_TIMELINE[pivot - 1].1, TWEET_TIMELINE[pivot].1);
+            let y = (TWEET_TIMELINE[pivot - 1].0, TWEET_TIMELINE[pivot].0);
+            y.0 + ((timestamp - x.0) as f64 * ((y.1 - y.0) as f64 / (x.1 - x.0) as f64)) as u64
+        }
+        0 => 0,
+        // Between the last sequential ID and the first snowflake ID
+        2362 => 29700859248,
+        _ => unreachable!(),
     }
 }
 
@@ -2429,3 +2437,59 @@ static TWEET_TIMELINE: [(u64, i64); 2362] = [
     (29642849756, 1288848226),
     (29700859247, 1288904568),
 ];
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_0() {
+        assert_eq!(lookup_interp(0), 1142974214);
+    }
+
+    #[test]
+    fn test_max() {
+        assert_eq!(lookup_interp(29700859247), 1288904568);
+    }
+
+    #[test]
+    fn test_snowflake() {
+        assert_eq!(snowflake_time_ms(29700859247), None);
+        assert_eq!(snowflake_time_ms(1612439052567916544), Some(1673270402458));
+        assert_eq!(
+            tweet_time(1612439052567916544).unix_timestamp_nanos(),
+            1673270402458000000
+        );
+    }
+
+    #[test]
+    fn test() {
+        assert_eq!(lookup_interp(20), 1142974214);
+        assert_eq!(lookup_interp(75), 1142985109);
+        assert!(lookup_interp(50) > 1142974214 && lookup_interp(50) < 1142985109);
+    }
+
+    #[test]
+    fn test_time_to_id() {
+        let time = tweet_time(1612439052567916544);
+        assert_eq!(time_to_id(time), 1612439052567916544 >> 22 << 22);
+        assert!(time_to_id(time) <= 1612439052567916544);
+
+        let id = time_to_id(OffsetDateTime::from_unix_timestamp(1143000000).unwrap());
+        assert!(id > 75 && id < 108);
+        assert_eq!(
+            time_to_id(OffsetDateTime::from_unix_timestamp(0).unwrap()),
+            0
+        );
+        assert_eq!(
+            time_to_id(OffsetDateTime::from_unix_timestamp(1288904569).unwrap()),
+            (1288904569000 - 1288834974657) << 22
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_past_max() {
+        lookup_interp(29700859248);
+    }
+}
@@ -1,9 +1,51 @@
+mod atomic;
+#[cfg(feature = "blocking")]
+pub mod blocking;
+mod cache;
+mod cookie_jar;
+mod coverage;
+#[cfg(feature = "discovery")]
+mod discovery;
 mod error;
+mod health;
+mod http_client;
 mod id_time;
+mod multi_scraper;
 mod nitter_scraper;
 mod parse;
+mod pool;
+mod rate_limit;
+mod recheck;
+mod search_query;
+mod stats;
+mod thread;
+mod timer;
 mod tweet;
+mod warning;
 
+pub use atomic::write_atomic;
+pub use cache::ResponseCache;
+pub use cookie_jar::CookieJar;
+pub use coverage::FieldCoverage;
+#[cfg(feature = "discovery")]
+pub use discovery::{InstanceDiscovery, PublicInstance, DEFAULT_INSTANCE_LIST};
 pub use error::NitterError;
-pub use nitter_scraper::{NitterQuery, NitterScraper};
+pub use health::{InstanceCheck, InstanceHealth};
+#[cfg(feature = "socks")]
+pub use http_client::{socks_client, socks_proxy, TOR_PROXY};
+pub use http_client::{HttpClient, HttpFetch, HttpMethod, HttpRequest, HttpResponse};
+pub use multi_scraper::MultiScraper;
+pub use nitter_scraper::{
+    FinishReason, ImageQuality, MediaUrls, NitterQuery, NitterScraper, ResumeToken,
+    DEFAULT_MAX_RESPONSE_SIZE, DEFAULT_USER_AGENTS,
+};
+pub use parse::SelectorProfile;
+pub use pool::NitterScraperPool;
+pub use rate_limit::RateLimiter;
+pub use recheck::RecheckWindow;
+pub use search_query::SearchQuery;
+pub use stats::{ScrapeStats, StatsHandle};
+pub use thread::{threads, Thread};
+pub use tokio_util::sync::CancellationToken;
 pub use tweet::*;
+pub use warning::Warning;
@@ -0,0 +1,365 @@
+use std::collections::VecDeque;
+use std::future::Future;
+use std::pin::Pin;
+
+use futures_util::stream::FuturesUnordered;
+use futures_util::{Stream, StreamExt};
+use typed_builder::TypedBuilder;
+
+use crate::error::NitterError;
+use crate::http_client::HttpClient;
+use crate::nitter_scraper::{NitterQuery, NitterScraper};
+use crate::tweet::Tweet;
+
+type TweetResult = Result<Tweet, NitterError>;
+
+/// Tweets of one of the streams that take turns
+type Tweets<'s> = Pin<Box<dyn Stream<Item = TweetResult> + 's>>;
+
+/// Scrapes several queries, usually the timelines of several accounts, as one stream
+///
+/// The queries take turns: their pages are requested round robin, and each query has at most
+/// `max_in_flight_per_account` pages requested or waiting to be yielded at once. A query with many
+/// tweets can't hold up the others, which all get their first page before any gets its next one.
+#[derive(TypedBuilder)]
+pub struct MultiScraper<'a> {
+    scrapers: Vec<NitterScraper<'a>>,
+
+    /// Number of pages to request at once across all queries, or all of them if `None`
+    #[builder(default)]
+    concurrency: Option<usize>,
+
+    /// Number of pages of each query that can be requested or waiting to be yielded at once
+    #[builder(default = 1)]
+    max_in_flight_per_account: usize,
+
+    /// Most tweets of the pages that arrived to hold until they're yielded, no more pages are
+    /// requested while they hold this many
+    #[builder(default)]
+    max_buffered_items: Option<usize>,
+}
+
+impl<'a> MultiScraper<'a> {
+    /// Scrape the timelines of `users`, returning at most `limit_per_user` tweets of each
+    ///
+    /// Fails if `instance` isn't a valid instance URL.
+    pub fn users(
+        client: impl Into<HttpClient<'a>>,
+        instance: &str,
+        users: impl IntoIterator<Item = impl Into<String>>,
+        limit_per_user: Option<usize>,
+    ) -> Result<Self, NitterError> {
+        let client = client.into();
+        let scrapers = users
+            .into_iter()
+            .map(|user| {
+                NitterScraper::builder()
+                    .client(client)
+                    .instance(instance)
+                    .query(NitterQuery::User { user: user.into() })
+                    .reorder_pinned(true)
+                    .limit(limit_per_user)
+                    .try_build()
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self::builder().scrapers(scrapers).build())
+    }
+
+    /// Scrape every query, yielding their tweets tagged with the query they were returned by, a
+    /// page at a time in the order the pages arrive
+    ///
+    /// An error only ends the stream of the query it happened in, the other queries continue. A
+    /// page is only known to be complete once the first tweet of the next one arrives, so each
+    /// turn of a query requests the page after the one it yields.
+    pub async fn search<'s>(
+        &'s self,
+    ) -> impl Stream<Item = (NitterQuery, Result<Tweet, NitterError>)> + use<'s, 'a> {
+        let queries: Vec<_> = self.scrapers.iter().map(|s| s.query().clone()).collect();
+        let concurrency = self.concurrency;
+        let max_in_flight = self.max_in_flight_per_account;
+        let max_buffered = self.max_buffered_items;
+        let mut streams: Vec<Tweets<'s>> = vec![];
+        for scraper in self.scrapers.iter() {
+            streams.push(Box::pin(scraper.search().await));
+        }
+        take_turns(streams, concurrency, max_in_flight, max_buffered)
+            .map(move |(index, result)| (queries[index].clone(), result))
+    }
+}
+
+/// Yield the tweets of several streams, tagged with the index of their stream, taking turns a page
+/// at a time
+pub(crate) fn take_turns<'s>(
+    streams: Vec<Tweets<'s>>,
+    concurrency: Option<usize>,
+    max_in_flight: usize,
+    max_buffered: Option<usize>,
+) -> impl Stream<Item = (usize, TweetResult)> + 's {
+    let turns = Turns {
+        in_flight: vec![0; streams.len()],
+        waiting: streams
+            .into_iter()
+            .enumerate()
+            .map(|(index, tweets)| Account {
+                index,
+                tweets,
+                next: None,
+            })
+            .collect(),
+        requested: FuturesUnordered::new(),
+        held: vec![],
+        pages: VecDeque::new(),
+        concurrency: concurrency.unwrap_or(usize::MAX).max(1),
+        max_in_flight: max_in_flight.max(1),
+        max_buffered: max_buffered.unwrap_or(usize::MAX),
+    };
+    futures_util::stream::unfold(turns, |mut turns| async {
+        loop {
+            turns.request_pages();
+            if let Some(item) = turns.next_tweet() {
+                return Some((item, turns));
+            }
+            let (index, page, account) = turns.requested.next().await?;
+            turns.arrived(index, page, account);
+        }
+    })
+}
+
+/// A stream taking turns, with the first tweet of its next page if that already arrived
+struct Account<'s> {
+    index: usize,
+    tweets: Tweets<'s>,
+    next: Option<TweetResult>,
+}
+
+/// Index of an account, its next page, and the account unless it has no more pages
+type PageFuture<'s> =
+    Pin<Box<dyn Future<Output = (usize, Vec<TweetResult>, Option<Account<'s>>)> + 's>>;
+
+impl<'s> Account<'s> {
+    /// Take the tweets of the next page, which ends where a tweet of another page arrives
+    async fn next_page(mut self) -> (usize, Vec<TweetResult>, Option<Self>) {
+        let page_of = |result: &TweetResult| result.as_ref().ok().and_then(|tweet| tweet.page);
+        let mut page: Vec<_> = self.next.take().into_iter().collect();
+        while let Some(result) = self.tweets.next().await {
+            match page.first() {
+                Some(first) if page_of(first) != page_of(&result) => {
+                    self.next = Some(result);
+                    return (self.index, page, Some(self));
+                }
+                _ => page.push(result),
+            }
+        }
+        (self.index, page, None)
+    }
+}
+
+struct Turns<'s> {
+    /// Accounts waiting for their turn to request a page, in turn
+    waiting: VecDeque<Account<'s>>,
+    /// Accounts whose next page was requested
+    requested: FuturesUnordered<PageFuture<'s>>,
+    /// Accounts with as many pages in flight as allowed, until one of them is yielded
+    held: Vec<Account<'s>>,
+    /// Pages that arrived and weren't yielded yet, with the index of their account
+    pages: VecDeque<(usize, VecDeque<TweetResult>)>,
+    /// Number of pages of each account that were requested or are waiting to be yielded
+    in_flight: Vec<usize>,
+    concurrency: usize,
+    max_in_flight: usize,
+    max_buffered: usize,
+}
+
+impl<'s> Turns<'s> {
+    /// Request the next pages of the accounts whose turn it is, up to the concurrency limit and
+    /// while few enough tweets are waiting to be yielded
+    fn request_pages(&mut self) {
+        let buffered: usize = self.pages.iter().map(|(_, page)| page.len()).sum();
+        if buffered >= self.max_buffered {
+            return;
+        }
+        while self.requested.len() < self.concurrency {
+            let Some(account) = self.waiting.pop_front() else {
+                break;
+            };
+            self.in_flight[account.index] += 1;
+            self.requested.push(Box::pin(account.next_page()));
+        }
+    }
+
+    /// Queue a page that arrived, and give its account another turn if it can have one
+    fn arrived(&mut self, index: usize, page: Vec<TweetResult>, account: Option<Account<'s>>) {
+        match page.is_empty() {
+            true => self.in_flight[index] -= 1,
+            false => self.pages.push_back((index, page.into())),
+        }
+        if let Some(account) = account {
+            match self.in_flight[index] < self.max_in_flight {
+                true => self.waiting.push_back(account),
+                false => self.held.push(account),
+            }
+        }
+    }
+
+    /// Take the next tweet of the pages that arrived, giving its account another turn once its
+    /// page was yielded
+    fn next_tweet(&mut self) -> Option<(usize, TweetResult)> {
+        let (index, page) = self.pages.front_mut()?;
+        let index = *index;
+        let tweet = page.pop_front()?;
+        if page.is_empty() {
+            self.pages.pop_front();
+            self.in_flight[index] -= 1;
+            if let Some(i) = self.held.iter().position(|account| account.index == index) {
+                let account = self.held.remove(i);
+                self.waiting.push_back(account);
+            }
+        }
+        Some((index, tweet))
+    }
+}
+
+#[cfg(all(test, feature = "reqwest"))]
+mod test {
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::sync::{Arc, Mutex};
+
+    use reqwest::Client;
+
+    use super::*;
+
+    /// Serve timelines with one tweet per page, recording the requested paths
+    ///
+    /// The timeline of "busy" has three pages, "gone" doesn't exist, and the others have one page.
+    /// The pages are synthetic, with only the markup the parser reads.
+    fn serve_timelines() -> (String, Arc<Mutex<Vec<String>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let instance = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(vec![]));
+        let log = requests.clone();
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut lines = BufReader::new(&stream).lines();
+                let request = lines.next().unwrap().unwrap();
+                for line in lines {
+                    if line.unwrap().is_empty() {
+                        break;
+                    }
+                }
+                let path = request.split(' ').nth(1).unwrap().to_owned();
+                log.lock().unwrap().push(path.clone());
+
+                let (user, page) = match path.split_once("?cursor=") {
+                    Some((user, page)) => (user, page.parse::<u128>().unwrap()),
+                    None => (path.as_str(), 0),
+                };
+                let user = user.trim_start_matches('/');
+                if user == "gone" {
+                    write!(
+                        stream,
+                        "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+                    )
+                    .unwrap();
+                    continue;
+                }
+                let more = match user == "busy" && page < 2 {
+                    true => format!(
+                        r#"<div class="show-more"><a href="?cursor={}">Load more</a></div>"#,
+                        page + 1
+                    ),
+                    false => String::new(),
+                };
+                let body = format!(
+                    r#"<html><body><div class="timeline"><div class="timeline-item">
+                        <a class="fullname" href="/{user}" title="User">User</a>
+                        <span class="tweet-date"><a href="/{user}/status/{id}#m" title="Jan 1, 2023 · 1:00 PM UTC">1 Jan</a></span>
+                        <div class="tweet-content media-body">text</div>
+                    </div>{more}</div></body></html>"#,
+                    id = 100 - page
+                );
+                write!(
+                    stream,
+                    "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                )
+                .unwrap();
+            }
+        });
+        (instance, requests)
+    }
+
+    #[tokio::test]
+    async fn test_turns() {
+        let (instance, requests) = serve_timelines();
+        let client = Client::new();
+        let scrapers = ["busy", "u2", "u3"]
+            .into_iter()
+            .map(|user| {
+                NitterScraper::builder()
+                    .client(&client)
+                    .instance(instance.as_str())
+                    .query(NitterQuery::User { user: user.into() })
+                    .try_build()
+                    .unwrap()
+            })
+            .collect();
+        let scraper = MultiScraper::builder()
+            .scrapers(scrapers)
+            .concurrency(Some(1))
+            .build();
+        let tweets: Vec<_> = scraper
+            .search()
+            .await
+            .map(|(_, tweet)| {
+                let tweet = tweet.unwrap();
+                (tweet.user.screen_name, tweet.id)
+            })
+            .collect()
+            .await;
+
+        // Every account gets its first page before the busy one gets its next
+        let expected = [
+            ("busy", 100),
+            ("u2", 100),
+            ("u3", 100),
+            ("busy", 99),
+            ("busy", 98),
+        ];
+        let expected: Vec<_> = expected
+            .iter()
+            .map(|&(user, id)| (user.to_owned(), id))
+            .collect();
+        assert_eq!(tweets, expected);
+        // The first turn of the busy account also requests its second page, to find the end of
+        // its first one
+        assert_eq!(
+            *requests.lock().unwrap(),
+            ["/busy", "/busy?cursor=1", "/u2", "/u3", "/busy?cursor=2"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_errors() {
+        let (instance, _) = serve_timelines();
+        let client = Client::new();
+        let scraper = MultiScraper::users(&client, &instance, ["gone", "u2"], None).unwrap();
+        let results: Vec<_> = scraper.search().await.collect().await;
+
+        // The missing account doesn't stop the other one
+        assert_eq!(results.len(), 2);
+        assert!(results.iter().any(|(query, result)| {
+            *query
+                == NitterQuery::User {
+                    user: "gone".into(),
+                }
+                && matches!(result, Err(NitterError::NotFound))
+        }));
+        assert!(results.iter().any(|(query, result)| {
+            *query == NitterQuery::User { user: "u2".into() }
+                && matches!(result, Ok(tweet) if tweet.id == 100)
+        }));
+    }
+}
@@ -1,29 +1,239 @@
 use std::collections::VecDeque;
+use std::future::Future;
+use std::ops::Deref;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
-use clap::Subcommand;
-use futures_util::Stream;
+use clap::{Subcommand, ValueEnum};
+use futures_util::future::{BoxFuture, MaybeDone};
+use futures_util::{Stream, StreamExt, TryStreamExt};
+use http::header::{HeaderMap, HeaderName, HeaderValue, COOKIE, LOCATION, RETRY_AFTER, USER_AGENT};
+use http::StatusCode;
+use once_cell::sync::{Lazy, OnceCell};
 use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
-use reqwest::header::COOKIE;
-use reqwest::{Client, StatusCode};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use time::format_description::well_known::Rfc2822;
+use time::OffsetDateTime;
+use tokio::sync::mpsc::UnboundedSender;
+use tokio_util::sync::CancellationToken;
+use tokio_util::task::task_tracker::TaskTrackerToken;
+use tokio_util::task::TaskTracker;
 use typed_builder::TypedBuilder;
+use url::Url;
 
+use crate::cache::ResponseCache;
+use crate::cookie_jar::CookieJar;
+use crate::coverage::FieldCoverage;
 use crate::error::NitterError;
-use crate::parse::{parse_nitter_html, parse_nitter_single};
-use crate::tweet::Tweet;
+use crate::health::{self, InstanceCheck};
+use crate::http_client::{HttpClient, HttpMethod, HttpResponse, ResponseLimits};
+use crate::id_time::time_to_id;
+use crate::parse::{
+    parse_nitter_html_items, parse_nitter_profile, parse_nitter_rss, parse_nitter_single,
+    parse_nitter_version, ParseOptions, SelectorProfile,
+};
+use crate::rate_limit::RateLimiter;
+use crate::recheck::RecheckWindow;
+use crate::stats::{ScrapeStats, StatsCounters, StatsHandle};
+use crate::thread::{threads, Thread};
+use crate::timer::{self, Instant};
+use crate::tweet::{DisappearedTweet, Profile, TimelineItem, Tweet};
+use crate::warning::Warning;
+
+/// Preferences that keep media and links pointing where the parser expects
+pub(crate) const PREFERENCES_COOKIE: &str =
+    "proxyVideos=; replaceTwitter=; replaceYouTube=; replaceReddit=";
+
+/// Start of the error page instances show when Twitter rate limits them
+const RATE_LIMITED_PAGE: &str = r#"<div class="error-panel"><span>Instance has been rate limited"#;
+
+/// Default limit of the size of a response body, pages are far smaller unless something's wrong
+pub const DEFAULT_MAX_RESPONSE_SIZE: usize = 8 * 1024 * 1024;
+
+/// Common browser User-Agents, for instances that block HTTP libraries
+pub const DEFAULT_USER_AGENTS: &[&str] = &[
+    "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36",
+    "Mozilla/5.0 (Windows NT 10.0; Win64; x64; rv:121.0) Gecko/20100101 Firefox/121.0",
+    "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/17.2 Safari/605.1.15",
+    "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36",
+    "Mozilla/5.0 (X11; Linux x86_64; rv:121.0) Gecko/20100101 Firefox/121.0",
+    "Mozilla/5.0 (X11; Linux x86_64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36",
+];
+
+type CheckHook<'a> = Box<dyn Fn(Result<usize, &NitterError>) + Send + Sync + 'a>;
+type StopCondition<'a> = Box<dyn Fn(&Tweet) -> bool + Send + Sync + 'a>;
+type PageHook<'a> = Box<dyn Fn(u32, &str, usize) + Send + Sync + 'a>;
+type TweetHook<'a> = Box<dyn Fn(&Tweet) + Send + Sync + 'a>;
 
 #[derive(TypedBuilder)]
+#[builder(build_method(vis = "pub", name = try_build, into = Result<NitterScraper<'a>, NitterError>))]
 pub struct NitterScraper<'a> {
-    client: &'a Client,
+    #[builder(setter(into))]
+    client: HttpClient<'a>,
+
+    /// Cache to revalidate pages with instead of downloading them again
+    #[builder(default)]
+    cache: Option<&'a ResponseCache>,
+
+    /// Jar to keep the cookies instances set in and send them back with later requests
+    #[builder(default)]
+    cookie_jar: Option<&'a CookieJar>,
+
+    /// Clients set up with different proxies, rotated through per request instead of `client`, so
+    /// scrapes sharing a client can use their own egress IPs
+    #[builder(
+        default,
+        setter(transform = |clients: impl IntoIterator<Item = impl Into<HttpClient<'a>>>| {
+            clients.into_iter().map(Into::into).collect()
+        })
+    )]
+    proxies: Vec<HttpClient<'a>>,
+
+    /// User-Agents to rotate through per request instead of the client's, e.g.
+    /// [`DEFAULT_USER_AGENTS`]
+    ///
+    /// Strings that aren't valid header values are skipped.
+    #[builder(
+        default,
+        setter(transform = |user_agents: impl IntoIterator<Item = impl AsRef<str>>| {
+            user_agents
+                .into_iter()
+                .filter_map(|user_agent| HeaderValue::from_str(user_agent.as_ref()).ok())
+                .collect()
+        })
+    )]
+    user_agents: Vec<HeaderValue>,
+
+    /// Headers sent with every request to the instance, e.g. for instances behind authentication
+    #[builder(
+        via_mutators,
+        mutators(
+            /// Send a header with every request to the instance
+            pub fn header(&mut self, name: HeaderName, value: HeaderValue) {
+                self.headers.append(name, value);
+            }
+        )
+    )]
+    headers: HeaderMap,
+
+    /// Cookies sent with every request to the instance
+    #[builder(
+        via_mutators,
+        mutators(
+            /// Send a cookie with every request to the instance, `try_build` fails with
+            /// [`NitterError::InvalidCookie`] if the name or value contains characters that aren't
+            /// allowed in cookies
+            pub fn cookie(&mut self, name: &str, value: &str) {
+                self.cookies.push((name.to_owned(), value.to_owned()));
+            }
+        )
+    )]
+    cookies: Vec<(String, String)>,
+
+    /// Cookie header built from `cookies` by `try_build`
+    #[builder(setter(skip), default = HeaderValue::from_static(PREFERENCES_COOKIE))]
+    cookie_header: HeaderValue,
 
     #[builder(setter(into))]
     instance: String,
 
     query: NitterQuery,
 
+    /// Second instance to race every page request against, trading extra load for latency
+    #[builder(default)]
+    race_instance: Option<String>,
+
     #[builder(default)]
     limit: Option<usize>,
 
+    /// Maximum number of pages to fetch, not counting retries
+    #[builder(default)]
+    max_pages: Option<usize>,
+
+    /// Maximum number of requests per minute to send to the instance
+    #[builder(
+        default,
+        setter(transform = |requests_per_minute: Option<u32>| {
+            requests_per_minute.map(|rpm| Arc::new(RateLimiter::per_minute(rpm)))
+        })
+    )]
+    rate_limit: Option<Arc<RateLimiter>>,
+
+    /// Rate limiter shared with other scrapers, e.g. every scraper of the same instance, so their
+    /// requests stay within one budget together, on top of `rate_limit`
+    #[builder(default, setter(strip_option))]
+    shared_rate_limit: Option<Arc<RateLimiter>>,
+
+    /// Number of times to wait and retry a rate limited request before failing with
+    /// [`NitterError::RateLimited`], waiting as long as the instance asks for if it says
+    #[builder(default = 25)]
+    rate_limit_retries: usize,
+
+    /// Largest response body in bytes to read, after decompression, before failing with
+    /// [`NitterError::ResponseTooLarge`], `None` reads responses of any size
+    #[builder(default = Some(DEFAULT_MAX_RESPONSE_SIZE))]
+    max_response_size: Option<usize>,
+
+    /// Longest time to wait for a page's response to start, or for more of its body, before
+    /// failing with [`NitterError::ReadTimeout`]
+    #[builder(
+        default,
+        setter(transform = |read_timeout: impl Into<Option<Duration>>| read_timeout.into())
+    )]
+    read_timeout: Option<Duration>,
+
+    /// Longest time to read a page's body in, once the response started, before failing with
+    /// [`NitterError::BodyTimeout`]
+    #[builder(
+        default,
+        setter(transform = |body_timeout: impl Into<Option<Duration>>| body_timeout.into())
+    )]
+    body_timeout: Option<Duration>,
+
+    /// Number of pages to fetch ahead of the consumer while it processes buffered tweets
+    ///
+    /// Pages past `limit` or the minimum ID may be requested, as it isn't known in advance
+    /// where the consumer will stop.
+    #[builder(default)]
+    prefetch: usize,
+
+    /// Most items to hold ahead of the consumer, prefetching pauses once the buffered pages hold
+    /// this many, so many scrapers can run in little memory
+    #[builder(default)]
+    max_buffered_items: Option<usize>,
+
+    /// Time to wait between the requests for consecutive pages, as instance admins ask scrapers
+    /// to do, unlike `rate_limit` it applies to every search on its own
+    #[builder(default)]
+    page_delay: Duration,
+
+    /// Random extra time, up to this long, added to each `page_delay`
+    #[builder(default)]
+    jitter: Duration,
+
+    /// Longest time to wait for a page, including retries, before failing with
+    /// [`NitterError::Timeout`], independent of the client's own timeouts
+    #[builder(
+        default,
+        setter(transform = |page_timeout: impl Into<Option<Duration>>| page_timeout.into())
+    )]
+    page_timeout: Option<Duration>,
+
+    /// Time by which searches must be done, requests still running then or started after it fail
+    /// with [`NitterError::DeadlineExceeded`]
+    #[builder(
+        default,
+        setter(transform = |deadline: impl Into<Option<web_time::Instant>>| {
+            deadline.into().map(timer::instant)
+        })
+    )]
+    deadline: Option<Instant>,
+
     #[builder(default)]
     reorder_pinned: bool,
 
@@ -33,57 +243,498 @@ pub struct NitterScraper<'a> {
     #[builder(default)]
     min_id: Option<u128>,
 
+    /// Maximum tweet ID to return, searches start at it instead of at the newest tweets
+    #[builder(default)]
+    max_id: Option<u128>,
+
+    /// Only return tweets created at or after this time
+    #[builder(default)]
+    since: Option<OffsetDateTime>,
+
+    /// Only return tweets created before this time, searches start at it
+    #[builder(default)]
+    until: Option<OffsetDateTime>,
+
+    #[builder(default)]
+    include_profile: bool,
+
+    #[builder(default)]
+    include_raw_html: bool,
+
+    /// Also return the replies of a status query, following the "Load more" cursors
+    #[builder(default)]
+    include_replies: bool,
+
+    #[builder(default)]
+    image_quality: ImageQuality,
+
+    /// Seed of the random jitter added to `page_delay` and to the wait before retrying a rate
+    /// limited request, so runs can be reproduced
+    #[builder(default)]
+    seed: Option<u64>,
+
+    #[builder(default)]
+    media_urls: MediaUrls,
+
+    #[builder(default)]
+    expand_links: bool,
+
+    #[builder(default = 4)]
+    link_concurrency: usize,
+
+    /// How many levels of quoted tweets to fetch into `quoted_tweet`
+    #[builder(default)]
+    resolve_quotes: usize,
+
+    /// Read timelines from their RSS feeds, which are cheaper for instances to serve
+    ///
+    /// RSS feeds lack stats, videos, and tweet flags, so HTML pages are scraped instead when
+    /// the feed is unavailable or options that need the full markup are enabled.
+    #[builder(default)]
+    prefer_rss: bool,
+
+    /// Selectors to use instead of the built in ones, for forks with different markup
+    #[builder(default)]
+    selector_profile: SelectorProfile,
+
+    /// Stop at the first tweet this returns true for, e.g. one that's already stored, without
+    /// returning it or fetching further pages
+    #[builder(
+        default,
+        setter(transform = |stop_when: impl Fn(&Tweet) -> bool + Send + Sync + 'a| {
+            Some(Box::new(stop_when) as StopCondition<'a>)
+        })
+    )]
+    stop_when: Option<StopCondition<'a>>,
+
+    /// Called with the page number, URL and number of tweets of each page fetched, e.g. to drive
+    /// a progress bar
+    #[builder(
+        default,
+        setter(transform = |on_page: impl Fn(u32, &str, usize) + Send + Sync + 'a| {
+            Some(Box::new(on_page) as PageHook<'a>)
+        })
+    )]
+    on_page: Option<PageHook<'a>>,
+
+    /// Called with each tweet as it's returned
+    #[builder(
+        default,
+        setter(transform = |on_tweet: impl Fn(&Tweet) + Send + Sync + 'a| {
+            Some(Box::new(on_tweet) as TweetHook<'a>)
+        })
+    )]
+    on_tweet: Option<TweetHook<'a>>,
+
+    /// Yield an error for a timeline item that can't be parsed and continue with the rest of the
+    /// page, instead of failing the page and ending the search
+    #[builder(default)]
+    lenient_parse: bool,
+
+    /// Channel for non-fatal issues such as retries, which are printed to stderr without one
+    #[builder(default, setter(strip_option))]
+    warnings: Option<UnboundedSender<Warning>>,
+
+    /// Token to abort searches with, they end with [`FinishReason::Cancelled`] instead of fetching
+    /// their next page
+    #[builder(default, setter(strip_option))]
+    cancel: Option<CancellationToken>,
+
+    /// Continue a previous search of the same query and options where it stopped
+    #[builder(default)]
+    resume_from: Option<ResumeToken>,
+
+    /// Number of the newest tweets yielded by [`watch_items`](Self::watch_items) to keep
+    /// re-checking, 0 to not re-check any
+    #[builder(default)]
+    recheck_window: usize,
+
+    /// Most tweets of the `recheck_window` to re-check after each check, each one is a request
+    #[builder(default = 5)]
+    probe_budget: usize,
+
+    /// Called after each check of [`watch`](Self::watch) with the number of new tweets, or the
+    /// error it failed with, e.g. to report whether watching is healthy
+    #[builder(
+        default,
+        setter(transform = |on_check: impl Fn(Result<usize, &NitterError>) + Send + Sync + 'a| {
+            Some(Box::new(on_check) as CheckHook<'a>)
+        })
+    )]
+    on_check: Option<CheckHook<'a>>,
+
     #[builder(setter(skip), default)]
-    state: NitterSearchState,
+    next_proxy: AtomicUsize,
+
+    #[builder(setter(skip), default)]
+    next_user_agent: AtomicUsize,
+
+    /// How the last search ended, kept after its stream is dropped
+    #[builder(setter(skip), default)]
+    last_search: Mutex<LastSearch>,
+
+    #[builder(setter(skip), default)]
+    stats: Arc<StatsCounters>,
+
+    /// Generator of the random jitter, seeded on first use
+    #[builder(setter(skip), default)]
+    rng: OnceCell<SearchRng>,
+
+    /// Cancelled by `cancel` or [`shutdown`](Self::shutdown)
+    #[builder(setter(skip), default)]
+    stop: CancellationToken,
+
+    /// Searches that weren't dropped yet, waited for by [`shutdown`](Self::shutdown)
+    #[builder(setter(skip), default)]
+    searches: TaskTracker,
 }
 
 #[derive(Debug, Default)]
 struct NitterSearchState {
-    tweets: VecDeque<Tweet>,
+    items: VecDeque<TimelineItem>,
     cursor: NitterCursor,
     count: usize,
-    errored: bool,
+    page: u32,
+    pages_fetched: usize,
+    finish_reason: Option<FinishReason>,
     pinned: Option<Tweet>,
+    coverage: FieldCoverage,
+    rss_failed: bool,
+    /// Cursor of the page the buffered items are from, `None` for the first page
+    page_cursor: Option<String>,
+    /// Number of the page the buffered items are from
+    items_page: u32,
+    /// Number of items of the current page that were returned
+    page_consumed: usize,
+    /// Number of items of the next page to skip, because they were returned before resuming
+    resume_skip: usize,
+    /// Pages fetched ahead of the buffered items, and the error that stopped prefetching
+    pages: VecDeque<Result<Page, NitterError>>,
+    /// Items of the current page that couldn't be parsed, yielded before its other items
+    item_errors: VecDeque<NitterError>,
+    /// The first page is unchanged since the last check while watching
+    unchanged: bool,
+}
+
+impl NitterSearchState {
+    fn pop_item(&mut self) -> TimelineItem {
+        self.page_consumed += 1;
+        self.items.pop_front().unwrap()
+    }
+
+    fn start_page(&mut self, page: Page) {
+        self.page_cursor = page.cursor;
+        self.items_page = page.page;
+        self.page_consumed = page.skipped;
+        self.items.extend(page.items);
+        self.item_errors.extend(page.errors);
+    }
+
+    /// Number of items of the current page and the prefetched ones that weren't returned yet
+    fn buffered_items(&self) -> usize {
+        let prefetched = self.pages.iter().flatten().map(|page| page.items.len());
+        self.items.len() + prefetched.sum::<usize>()
+    }
+
+    /// Where the search is, or `None` if it reached the end of the timeline
+    fn resume_token(&self) -> Option<ResumeToken> {
+        if !self.items.is_empty() {
+            return Some(ResumeToken {
+                cursor: self.page_cursor.clone(),
+                page: self.items_page,
+                skip: self.page_consumed,
+            });
+        }
+        if let Some(Ok(page)) = self.pages.front() {
+            return Some(ResumeToken {
+                cursor: page.cursor.clone(),
+                page: page.page,
+                skip: page.skipped,
+            });
+        }
+        let cursor = match &self.cursor {
+            NitterCursor::Initial => None,
+            NitterCursor::More(cursor) => Some(cursor.clone()),
+            NitterCursor::End => return None,
+        };
+        Some(ResumeToken {
+            cursor,
+            page: self.page,
+            skip: self.resume_skip,
+        })
+    }
 }
 
+/// Processed items of a page, and where it is in the pagination
 #[derive(Debug)]
+struct Page {
+    cursor: Option<String>,
+    page: u32,
+    /// Number of items skipped when resuming
+    skipped: usize,
+    items: Vec<TimelineItem>,
+    /// Items that couldn't be parsed with lenient parsing
+    errors: Vec<NitterError>,
+}
+
+/// Headers and body of a fetched page
+struct Fetched {
+    headers: HeaderMap,
+    text: String,
+    /// The instance reported the cached copy as unchanged
+    not_modified: bool,
+}
+
+/// Request for a page that was sent before the page was needed
+struct Prefetch<'a> {
+    path: String,
+    response: MaybeDone<BoxFuture<'a, Result<Fetched, NitterError>>>,
+}
+
+/// A running search, with a position of its own so a scraper can run any number of them
+struct Search<'s, 'a> {
+    scraper: &'s NitterScraper<'a>,
+    state: NitterSearchState,
+    prefetched: Option<Prefetch<'a>>,
+    max_pages: Option<usize>,
+    /// Newest tweet ID seen while watching
+    watermark: Option<u128>,
+    /// The last check while watching succeeded, so it saw everything on the first page
+    checked: bool,
+    /// Earliest time the next page may be requested with `page_delay`
+    next_page_at: Option<Instant>,
+    _running: TaskTrackerToken,
+}
+
+#[derive(Debug, Default)]
+struct LastSearch {
+    finish_reason: Option<FinishReason>,
+    resume_token: Option<ResumeToken>,
+    coverage: FieldCoverage,
+}
+
+/// Position of a search, to continue it after it was interrupted
+///
+/// Get one from [`NitterScraper::resume_token`] and pass it to the builder's `resume_from`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResumeToken {
+    cursor: Option<String>,
+    page: u32,
+    skip: usize,
+}
+
+/// Why a search stream ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum FinishReason {
+    /// There were no more pages
+    EndOfTimeline,
+    /// The limit of returned items was reached
+    Limit,
+    /// The maximum number of pages was fetched
+    MaxPages,
+    /// A tweet older than the minimum ID was reached
+    MinId,
+    /// A tweet matching the `stop_when` condition was reached
+    StopCondition,
+    /// The stream ended after yielding an error
+    Error,
+    /// The `cancel` token was cancelled
+    Cancelled,
+}
+
+#[derive(Debug, Default)]
 pub enum NitterCursor {
+    #[default]
     Initial,
     More(String),
     End,
 }
 
-impl Default for NitterCursor {
-    fn default() -> Self {
-        Self::Initial
+/// Size variant of image URLs on pbs.twimg.com
+#[derive(ValueEnum, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ImageQuality {
+    /// Whatever size is served without a `name` parameter
+    #[default]
+    Default,
+    Orig,
+    Large,
+    Medium,
+    Small,
+    Thumb,
+}
+
+impl ImageQuality {
+    pub(crate) fn url_suffix(&self) -> &'static str {
+        match self {
+            Self::Default => "",
+            Self::Orig => "?name=orig",
+            Self::Large => "?name=large",
+            Self::Medium => "?name=medium",
+            Self::Small => "?name=small",
+            Self::Thumb => "?name=thumb",
+        }
     }
 }
 
-#[derive(Subcommand)]
+/// Which host media URLs point to
+#[derive(ValueEnum, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum MediaUrls {
+    /// Twitter's media servers, e.g. pbs.twimg.com
+    #[default]
+    Direct,
+    /// The instance's /pic/ proxy, for networks where Twitter's media servers are blocked
+    Proxied,
+    /// Direct URLs, with proxied URLs in separate `proxied_*` fields
+    Both,
+}
+
+#[derive(Subcommand, Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
 pub enum NitterQuery {
-    Search { query: String },
-    User { user: String },
-    UserWithReplies { user: String },
-    UserMedia { user: String },
-    UserSearch { user: String, query: String },
-    Status { id: u64 },
+    /// Full-text search of all tweets, newest first
+    Search {
+        /// Search query, supports operators such as "from:user", "since:2023-01-01", or
+        /// "filter:images"
+        query: String,
+    },
+    User {
+        user: String,
+    },
+    /// Tweets and replies from a user, along with the tweets they reply to
+    UserWithReplies {
+        /// Screen name of the user, without the "@"
+        user: String,
+    },
+    /// Tweets with images or videos from a user's media tab
+    UserMedia {
+        /// Screen name of the user, without the "@"
+        user: String,
+    },
+    UserSearch {
+        user: String,
+        query: String,
+    },
+    Status {
+        id: u64,
+    },
+    RepliesTo {
+        #[arg(value_parser = parse_tweet_id)]
+        tweet: u128,
+    },
+    QuotesOf {
+        #[arg(value_parser = parse_tweet_id)]
+        tweet: u128,
+    },
+    /// Users whose name or bio match a query
+    Users {
+        query: String,
+    },
+    /// Tweets from the members of a list
+    List {
+        /// Numeric list ID, or the owner's screen name and the list's slug, e.g. "jack/friends"
+        list: String,
+    },
+}
+
+/// Parse a tweet ID from either a bare ID or a tweet URL
+fn parse_tweet_id(s: &str) -> Result<u128, String> {
+    static TWEET_URL_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"/status(?:es)?/(\d+)").unwrap());
+
+    let id = match TWEET_URL_RE.captures(s) {
+        Some(caps) => caps.get(1).unwrap().as_str(),
+        None => s,
+    };
+    id.parse()
+        .map_err(|_| format!("invalid tweet ID or URL {:?}", s))
+}
+
+impl std::fmt::Display for NitterQuery {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Search { query } => write!(f, "search:{}", query),
+            Self::User { user } => write!(f, "user:{}", user),
+            Self::UserWithReplies { user } => write!(f, "user-with-replies:{}", user),
+            Self::UserMedia { user } => write!(f, "user-media:{}", user),
+            Self::UserSearch { user, query } => write!(f, "user-search:{}:{}", user, query),
+            Self::Status { id } => write!(f, "status:{}", id),
+            Self::RepliesTo { tweet } => write!(f, "replies-to:{}", tweet),
+            Self::QuotesOf { tweet } => write!(f, "quotes-of:{}", tweet),
+            Self::Users { query } => write!(f, "users:{}", query),
+            Self::List { list } => write!(f, "list:{}", list),
+        }
+    }
+}
+
+impl std::str::FromStr for NitterQuery {
+    type Err = NitterError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || NitterError::InvalidQuery(s.to_owned());
+        let (kind, value) = s.split_once(':').ok_or_else(invalid)?;
+        let query = match kind {
+            "search" => Self::Search {
+                query: value.to_owned(),
+            },
+            "user" => Self::User {
+                user: value.to_owned(),
+            },
+            "user-with-replies" => Self::UserWithReplies {
+                user: value.to_owned(),
+            },
+            "user-media" => Self::UserMedia {
+                user: value.to_owned(),
+            },
+            "user-search" => {
+                let (user, query) = value.split_once(':').ok_or_else(invalid)?;
+                Self::UserSearch {
+                    user: user.to_owned(),
+                    query: query.to_owned(),
+                }
+            }
+            "status" => Self::Status {
+                id: value.parse().map_err(|_| invalid())?,
+            },
+            "replies-to" => Self::RepliesTo {
+                tweet: value.parse().map_err(|_| invalid())?,
+            },
+            "quotes-of" => Self::QuotesOf {
+                tweet: value.parse().map_err(|_| invalid())?,
+            },
+            "users" => Self::Users {
+                query: value.to_owned(),
+            },
+            "list" => Self::List {
+                list: value.to_owned(),
+            },
+            _ => return Err(invalid()),
+        };
+        Ok(query)
+    }
 }
 
 impl NitterQuery {
     fn encode_get_params(&self) -> String {
         match self {
-            Self::Search { query } => {
-                let encoded = utf8_percent_encode(query, NON_ALPHANUMERIC);
-                format!("?f=tweets&q={}", encoded)
-            }
-            Self::User { .. } => "".into(),
-            Self::UserWithReplies { .. } => "".into(),
-            Self::UserMedia { .. } => "".into(),
-            Self::UserSearch { query, .. } => {
+            Self::Users { query } => {
                 let encoded = utf8_percent_encode(query, NON_ALPHANUMERIC);
-                format!("?f=tweets&q={}", encoded)
+                format!("?f=users&q={}", encoded)
             }
-            Self::Status { .. } => "".into(),
+            _ => self
+                .search_terms()
+                .map_or_else(String::new, |terms| tweet_search_params(&terms)),
+        }
+    }
+
+    /// Terms of the tweet search the query is read from, if it's one
+    fn search_terms(&self) -> Option<String> {
+        match self {
+            Self::Search { query } | Self::UserSearch { query, .. } => Some(query.clone()),
+            Self::RepliesTo { tweet } => Some(format!("conversation_id:{}", tweet)),
+            Self::QuotesOf { tweet } => Some(format!("quoted_tweet_id:{}", tweet)),
+            _ => None,
         }
     }
 
@@ -95,6 +746,13 @@ impl NitterQuery {
             Self::UserMedia { user } => format!("/{}/media", user),
             Self::UserSearch { user, .. } => format!("/{}/search", user),
             Self::Status { id } => format!("/i/status/{}", id),
+            Self::RepliesTo { .. } => "/search".into(),
+            Self::QuotesOf { .. } => "/search".into(),
+            Self::Users { .. } => "/search".into(),
+            Self::List { list } => match list.split_once('/') {
+                Some((owner, slug)) => format!("/{}/lists/{}", owner, slug),
+                None => format!("/i/lists/{}", list),
+            },
         }
     }
 
@@ -103,203 +761,2317 @@ impl NitterQuery {
     }
 }
 
+fn tweet_search_params(terms: &str) -> String {
+    let encoded = utf8_percent_encode(terms, NON_ALPHANUMERIC);
+    format!("?f=tweets&q={}", encoded)
+}
+
+/// Wait before the `retry`th retry of a rate limited request, doubling with each retry up to 256
+/// seconds, plus up to half as long again at random so retries of concurrent scrapes spread out
+fn retry_delay(retry: usize, rng: &mut StdRng) -> Duration {
+    let delay = Duration::from_secs(1 << std::cmp::min(retry, 8));
+    delay + delay.mul_f64(rng.gen_range(0.0..0.5))
+}
+
+/// Generator of a scraper's random numbers, shared by its requests, including prefetched ones
+#[derive(Debug, Clone)]
+struct SearchRng(Arc<Mutex<StdRng>>);
+
+impl SearchRng {
+    /// Seeded with `seed`, or from entropy without one
+    fn new(seed: Option<u64>) -> Self {
+        let rng = match seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        };
+        Self(Arc::new(Mutex::new(rng)))
+    }
+
+    /// Wait before the `retry`th retry of a rate limited request
+    fn retry_delay(&self, retry: usize) -> Duration {
+        retry_delay(retry, &mut self.0.lock().unwrap())
+    }
+
+    /// Random time up to `max`
+    fn jitter(&self, max: Duration) -> Duration {
+        max.mul_f64(self.0.lock().unwrap().gen_range(0.0..1.0))
+    }
+}
+
+/// Drop the items of a search that aren't tweets
+fn only_tweets<'s>(
+    items: impl Stream<Item = Result<TimelineItem, NitterError>> + 's,
+) -> impl Stream<Item = Result<Tweet, NitterError>> + 's {
+    items.filter_map(|item| async {
+        match item {
+            Ok(TimelineItem::Tweet(tweet)) => Some(Ok(tweet)),
+            Ok(
+                TimelineItem::Unavailable(_) | TimelineItem::User(_) | TimelineItem::Disappeared(_),
+            ) => None,
+            Err(e) => Some(Err(e)),
+        }
+    })
+}
+
 enum ReturnedTweet {
     Pinned,
     Normal,
-    None,
+    Skip,
+    Stop(FinishReason),
+}
+
+impl<'a> From<NitterScraper<'a>> for Result<NitterScraper<'a>, NitterError> {
+    /// Validate the instance URLs and cookies of a built scraper
+    fn from(mut scraper: NitterScraper<'a>) -> Self {
+        scraper.instance = normalize_instance(&scraper.instance)?;
+        if let Some(race_instance) = &scraper.race_instance {
+            scraper.race_instance = Some(normalize_instance(race_instance)?);
+        }
+        scraper.cookie_header = cookie_header(&scraper.cookies)?;
+        if let Some(cancel) = &scraper.cancel {
+            scraper.stop = cancel.child_token();
+        }
+        Ok(scraper)
+    }
+}
+
+/// The `Cookie` header with the preferences cookie and `cookies`
+fn cookie_header(cookies: &[(String, String)]) -> Result<HeaderValue, NitterError> {
+    let mut header = PREFERENCES_COOKIE.to_owned();
+    for (name, value) in cookies {
+        let cookie = format!("{}={}", name, value);
+        if name.is_empty()
+            || name.contains(['=', ';'])
+            || value.contains(';')
+            || HeaderValue::from_str(&cookie).is_err()
+        {
+            return Err(NitterError::InvalidCookie(cookie));
+        }
+        header.push_str("; ");
+        header.push_str(&cookie);
+    }
+    Ok(HeaderValue::from_str(&header).unwrap())
+}
+
+/// Check that `instance` is an HTTP(S) base URL and strip its trailing slashes, paths are
+/// appended to it as is
+fn normalize_instance(instance: &str) -> Result<String, NitterError> {
+    let instance = instance.trim().trim_end_matches('/');
+    let url = Url::parse(instance)
+        .map_err(|e| NitterError::InvalidInstance(format!("\"{}\": {}", instance, e)))?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(NitterError::InvalidInstance(format!(
+            "\"{}\": scheme must be http or https",
+            instance
+        )));
+    }
+    if url.query().is_some() || url.fragment().is_some() {
+        return Err(NitterError::InvalidInstance(format!(
+            "\"{}\": must not have a query or fragment",
+            instance
+        )));
+    }
+    Ok(instance.to_owned())
 }
 
 impl<'a> NitterScraper<'a> {
-    pub async fn search(&'a mut self) -> impl Stream<Item = Result<Tweet, NitterError>> + '_ {
-        // Reset internal state
-        self.state = Default::default();
+    /// Start a new search, each call returns a stream with its own position so the same scraper
+    /// can be used to poll the query repeatedly
+    pub async fn search<'s>(
+        &'s self,
+    ) -> impl Stream<Item = Result<Tweet, NitterError>> + use<'s, 'a> {
+        only_tweets(self.search_items().await)
+    }
 
-        futures_util::stream::unfold(self, |state| async {
-            // Stop if previously errored
-            if state.state.errored {
-                return None;
-            }
+    /// Like [`search`](Self::search), but groups self-threads together, see [`threads`]
+    pub async fn threads<'s>(
+        &'s self,
+    ) -> impl Stream<Item = Result<Thread, NitterError>> + use<'s, 'a> {
+        threads(self.search().await)
+    }
 
-            // Stop if limit reached
-            if let Some(limit) = state.limit {
-                if state.state.count >= limit {
-                    return None;
-                }
-            }
+    /// Like [`search`](Self::search), but also yields placeholders for unavailable tweets so
+    /// deleted or withheld tweets can be detected in context, and the results of
+    /// [`NitterQuery::Users`]
+    pub async fn search_items<'s>(
+        &'s self,
+    ) -> impl Stream<Item = Result<TimelineItem, NitterError>> + use<'s, 'a> {
+        Search::new(self).into_stream()
+    }
 
-            // Since skip-retweets may cause entire page to be empty, loop until cursor doesn't
-            // exist anymore
-            loop {
-                // Return tweet if available
-                if let Some(tweet) = state.state.tweets.iter().next() {
-                    match Self::should_return_tweet(
-                        tweet,
-                        &state.state.pinned,
-                        state.min_id,
-                        state.reorder_pinned,
-                    ) {
-                        ReturnedTweet::Normal => {
-                            state.state.count += 1;
-                            return Some((Ok(state.state.tweets.pop_front().unwrap()), state));
+    /// Collect every tweet of [`search`](Self::search), failing at the first error
+    pub async fn fetch_all(&self) -> Result<Vec<Tweet>, NitterError> {
+        self.search().await.try_collect().await
+    }
+
+    /// Collect the tweets of one page of [`search`](Self::search), the first one or the one
+    /// `resume_from` points at
+    ///
+    /// The next page can be fetched by a scraper built with the [`resume_token`](Self::resume_token)
+    /// as its `resume_from`.
+    pub async fn fetch_page(&self) -> Result<Vec<Tweet>, NitterError> {
+        let mut search = Search::new(self);
+        search.max_pages = Some(1);
+        only_tweets(search.into_stream()).try_collect().await
+    }
+
+    /// Endlessly re-check the timeline every `interval`, yielding only tweets newer than the
+    /// newest one seen so far, oldest first
+    ///
+    /// Without `min_id` or `since`, only tweets posted after watching started are yielded. Errors
+    /// are yielded as they happen, and the timeline is checked again after the next interval.
+    /// Watching ends once the `cancel` token is cancelled, or after the `deadline` passes.
+    pub async fn watch<'s>(
+        &'s self,
+        interval: Duration,
+    ) -> impl Stream<Item = Result<Tweet, NitterError>> + use<'s, 'a> {
+        only_tweets(self.watch_items(interval).await)
+    }
+
+    /// Like [`watch`](Self::watch), but also re-checks up to `probe_budget` of the newest
+    /// `recheck_window` tweets it yielded after each check, in turn, and yields a
+    /// [`TimelineItem::Disappeared`] for each one that no longer resolves
+    pub async fn watch_items<'s>(
+        &'s self,
+        interval: Duration,
+    ) -> impl Stream<Item = Result<TimelineItem, NitterError>> + use<'s, 'a> {
+        let mut search = Search::new(self);
+        if search.lower_bound().is_none() {
+            search.watermark = Some(time_to_id(OffsetDateTime::now_utc()).saturating_sub(1));
+        }
+
+        let window = RecheckWindow::new(self.recheck_window);
+        let state = (search, VecDeque::new(), true, window);
+        futures_util::stream::unfold(
+            state,
+            move |(mut search, mut new, mut first, mut window)| async move {
+                loop {
+                    if let Some(result) = new.pop_front() {
+                        return Some((result, (search, new, first, window)));
+                    }
+                    if !first {
+                        // The check that ran into the deadline already yielded its error
+                        if search.deadline_passed() {
+                            return None;
                         }
-                        ReturnedTweet::Pinned => {
-                            state.state.count += 1;
-                            return Some((Ok(state.state.pinned.take().unwrap()), state));
+                        tokio::select! {
+                            _ = timer::sleep(interval) => {}
+                            _ = search.cancelled() => {}
+                            _ = search.deadline_reached() => {}
                         }
-                        ReturnedTweet::None => break,
                     }
-                }
-
-                if let NitterCursor::End = state.state.cursor {
-                    break;
-                }
-
-                // Scrape nitter
-                match state.scrape_page().await {
-                    Ok(tweets) => {
-                        state.state.tweets.extend(tweets.into_iter());
+                    if search.is_cancelled() {
+                        search.finish(FinishReason::Cancelled);
+                        return None;
+                    }
+                    first = false;
+                    let new_tweets = search.new_tweets().await;
+                    // Watching goes on after each check's search ends
+                    search.stats.resume();
+                    if let Some(on_check) = &search.on_check {
+                        on_check(
+                            new_tweets.as_ref().map(|results| {
+                                results.iter().filter(|result| result.is_ok()).count()
+                            }),
+                        );
                     }
-                    Err(e) => {
-                        state.state.errored = true;
-                        return Some((Err(e), state));
+                    match new_tweets {
+                        Ok(results) => {
+                            let disappeared =
+                                search.recheck(&mut window, search.probe_budget).await;
+                            for tweet in results.iter().flatten() {
+                                window.push(tweet);
+                            }
+                            new.extend(results.into_iter().map(|r| r.map(TimelineItem::Tweet)));
+                            new.extend(
+                                disappeared
+                                    .into_iter()
+                                    .map(|tweet| Ok(TimelineItem::Disappeared(tweet))),
+                            );
+                        }
+                        Err(e) => return Some((Err(e), (search, new, first, window))),
                     }
                 }
-            }
+            },
+        )
+    }
 
-            // Return pinned tweet if needed
-            if let Some(t) = state.state.pinned.take() {
-                return Some((Ok(t), state));
-            }
+    /// Check that an instance serves timelines that can be parsed, and how quickly it responds
+    pub async fn check_instance(
+        client: impl Into<HttpClient<'_>>,
+        instance: &str,
+    ) -> InstanceCheck {
+        health::check_instance(client.into(), instance).await
+    }
 
-            None
-        })
+    /// Fetch the instance's Nitter version from its about page, useful for bug reports about
+    /// markup changes
+    pub async fn instance_version(&self) -> Result<Option<String>, NitterError> {
+        let url = format!("{}/about", self.instance);
+        let response = self
+            .http_client()
+            .send(HttpMethod::Get, &url, self.instance_headers())
+            .await?;
+        if !response.status.is_success() {
+            return Err(NitterError::Network(format!(
+                "received status code {}",
+                response.status.as_u16()
+            )));
+        }
+        Ok(parse_nitter_version(&response.body))
     }
 
-    fn should_return_tweet(
-        tweet: &Tweet,
-        pinned: &Option<Tweet>,
-        min_id: Option<u128>,
-        reorder_pinned: bool,
-    ) -> ReturnedTweet {
-        if reorder_pinned {
-            if let Some(p) = pinned {
-                // Should use tweet id here but nitter doesn't expose it for retweets
-                if p.created_at_ts > tweet.created_at_ts {
-                    return ReturnedTweet::Pinned;
+    /// Re-check up to `probe_budget` tweets of `window` whose turn it is, each one a request for
+    /// its status page, and return the ones that no longer resolve, removing them from the window
+    ///
+    /// Only a 404 counts as disappeared, tweets that fail to be re-checked otherwise stay in the
+    /// window for a later turn.
+    pub async fn recheck(
+        &self,
+        window: &mut RecheckWindow,
+        probe_budget: usize,
+    ) -> Vec<DisappearedTweet> {
+        let due = window.due(probe_budget);
+        let disappeared =
+            futures_util::future::join_all(due.iter().map(|(id, _)| self.tweet_disappeared(*id)))
+                .await;
+        due.into_iter()
+            .zip(disappeared)
+            .filter(|(_, disappeared)| *disappeared == Some(true))
+            .map(|((id, screen_name), _)| {
+                window.remove(id);
+                DisappearedTweet {
+                    id,
+                    id_str: id.to_string(),
+                    screen_name,
                 }
-            }
-        }
+            })
+            .collect()
+    }
 
-        // Stop if minimum tweet id reached
-        if let Some(min_id) = min_id {
-            if tweet.id < min_id {
-                return ReturnedTweet::None;
-            }
+    /// Whether a tweet no longer resolves, or `None` if that couldn't be checked
+    async fn tweet_disappeared(&self, id: u128) -> Option<bool> {
+        let path = NitterQuery::Status {
+            id: id.try_into().ok()?,
+        }
+        .url_path();
+        match self.fetch_path(&self.instance, &path).await {
+            Ok(_) => Some(false),
+            Err(NitterError::NotFound) => Some(true),
+            Err(_) => None,
         }
-
-        // Return next tweet
-        ReturnedTweet::Normal
     }
 
-    async fn scrape_page(&mut self) -> Result<Vec<Tweet>, NitterError> {
-        // Use cursor if it exists
-        let get_params = match self.state.cursor {
-            NitterCursor::Initial => self.query.encode_get_params(),
-            NitterCursor::More(ref c) => c.clone(),
-            NitterCursor::End => return Ok(vec![]),
+    /// Cancel the searches and wait up to `timeout` for them to end and for the cache and cookie
+    /// files they're writing, so an application can exit without cutting them off
+    ///
+    /// A search ends with [`FinishReason::Cancelled`] once its current page arrives, as long as
+    /// its stream is polled or dropped. Returns whether everything ended in time, later searches
+    /// end right away.
+    pub async fn shutdown(&self, timeout: Duration) -> bool {
+        self.stop.cancel();
+        self.searches.close();
+        let ended = async {
+            self.searches.wait().await;
+            if let Some(cache) = self.cache {
+                cache.wait_for_writes().await;
+            }
+            if let Some(cookie_jar) = self.cookie_jar {
+                cookie_jar.wait_for_writes().await;
+            }
         };
+        timer::timeout(timeout, ended).await.is_ok()
+    }
 
-        let mut nitter_retry = 0;
-        let tweets = loop {
-            // Send request
-            let url = format!("{}{}{}", self.instance, self.query.url_path(), get_params);
-            let mut i = 0;
-            let response = loop {
-                let response = self
-                    .client
-                    .get(&url)
-                    .header(COOKIE, "proxyVideos=; replaceTwitter=; replaceYouTube=; replaceReddit=")
-                    .send()
-                    .await
-                    .map_err(|e| NitterError::Network(e.to_string()))?;
-
-                if response.status() == StatusCode::TOO_MANY_REQUESTS {
-                    // Retry if 429
-                    if i < 25 {
-                        i += 1;
-                        let sleep_s = 1 << std::cmp::min(i, 8);
-                        eprintln!(
-                            "Received status code {}, sleeping for {} seconds",
-                            response.status().as_u16(),
-                            sleep_s
-                        );
-                        tokio::time::sleep(Duration::from_secs(sleep_s)).await;
-                        continue;
-                    } else {
-                        return Err(NitterError::Network(format!(
-                            "received status code {}",
-                            response.status().as_u16()
-                        )));
-                    }
-                } else if response.status() == StatusCode::NOT_FOUND {
-                    // Return nothing on 404
-                    return Err(NitterError::NotFound);
-                } else if !response.status().is_success() {
-                    // Error if bad status code
-                    return Err(NitterError::Network(format!(
-                        "received status code {}",
-                        response.status().as_u16()
-                    )));
-                }
+    pub fn query(&self) -> &NitterQuery {
+        &self.query
+    }
 
-                break response;
-            };
+    /// Fetch the profile header of a user
+    pub async fn profile(&self, user: &str) -> Result<Profile, NitterError> {
+        let text = self
+            .fetch_path(&self.instance, &format!("/{}", user))
+            .await?;
+        parse_nitter_profile(&text)
+    }
 
-            let text = response.text().await.unwrap();
+    /// Why the last search ended, or `None` if its stream was dropped before it ended
+    pub fn finish_reason(&self) -> Option<FinishReason> {
+        self.last_search.lock().unwrap().finish_reason
+    }
 
-            // Parse html and update cursor
-            let (tweets, cursor) = if self.query.is_single() {
-                let (tweet, cursor) = parse_nitter_single(text)?;
-                (vec![tweet], cursor)
-            } else {
-                parse_nitter_html(text)?
-            };
+    /// Where the last search stopped, or `None` if it reached the end of the timeline
+    ///
+    /// Resuming from it skips everything that was returned before. It's recorded when the
+    /// search's stream ends or is dropped.
+    pub fn resume_token(&self) -> Option<ResumeToken> {
+        self.last_search.lock().unwrap().resume_token.clone()
+    }
 
-            let tweets = if self.reorder_pinned {
-                // Extract pinned tweet
-                let (mut pinned, unpinned): (Vec<_>, Vec<_>) =
-                    tweets.into_iter().partition(|t| t.pinned);
-                if let Some(t) = pinned.pop() {
-                    if let Some(min_id) = self.min_id {
-                        if t.id >= min_id {
-                            self.state.pinned = Some(t);
-                        }
-                    } else {
-                        self.state.pinned = Some(t);
-                    }
-                }
-                unpinned
-            } else {
-                tweets
-            };
+    /// Counts of the work done by the last search, so far if it's still running
+    pub fn stats(&self) -> ScrapeStats {
+        self.stats_handle().get()
+    }
 
-            // Sometimes nitter will return nothing, retry a few times to make sure it's correct
-            if !tweets.is_empty() || nitter_retry > 10 {
-                self.state.cursor = cursor;
-                break tweets;
-            }
+    /// Handle to read [`stats`](Self::stats) with while a search stream borrows the scraper
+    pub fn stats_handle(&self) -> StatsHandle {
+        StatsHandle(self.stats.clone())
+    }
 
-            tokio::time::sleep(Duration::from_secs(1)).await;
-            nitter_retry += 1;
-        };
+    /// How often optional fields were extracted during the last search
+    pub fn field_coverage(&self) -> FieldCoverage {
+        self.last_search.lock().unwrap().coverage.clone()
+    }
+
+    /// Pass a returned tweet to the `on_tweet` hook
+    fn returned(&self, item: TimelineItem) -> TimelineItem {
+        if let (Some(on_tweet), TimelineItem::Tweet(tweet)) = (&self.on_tweet, &item) {
+            on_tweet(tweet);
+        }
+        item
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.stop.is_cancelled()
+    }
+
+    /// Wait until the `cancel` token is cancelled or the scraper is shut down
+    async fn cancelled(&self) {
+        self.stop.cancelled().await
+    }
+
+    fn deadline_passed(&self) -> bool {
+        self.deadline
+            .is_some_and(|deadline| deadline <= Instant::now())
+    }
+
+    /// Wait until the `deadline`, forever without one
+    async fn deadline_reached(&self) {
+        match self.deadline {
+            Some(deadline) => timer::sleep_until(deadline).await,
+            None => std::future::pending().await,
+        }
+    }
+
+    fn stops_at(&self, tweet: &Tweet) -> bool {
+        self.stop_when
+            .as_ref()
+            .is_some_and(|stop_when| stop_when(tweet))
+    }
+
+    /// Smallest tweet ID to return, from `min_id` and `since`
+    fn lower_bound(&self) -> Option<u128> {
+        let since = self.since.map(time_to_id);
+        self.min_id.max(since)
+    }
+
+    /// Largest tweet ID to return, from `max_id` and `until`
+    fn upper_bound(&self) -> Option<u128> {
+        let until = self.until.map(|until| time_to_id(until).saturating_sub(1));
+        match (self.max_id, until) {
+            (Some(max_id), Some(until)) => Some(max_id.min(until)),
+            (max_id, until) => max_id.or(until),
+        }
+    }
+
+    /// Parameters of the first page, with searches narrowed to the maximum ID up front
+    fn initial_get_params(&self) -> String {
+        match (self.query.search_terms(), self.upper_bound()) {
+            (Some(terms), Some(max_id)) => {
+                tweet_search_params(&format!("{} max_id:{}", terms, max_id))
+            }
+            _ => self.query.encode_get_params(),
+        }
+    }
+
+    fn parse_options(&self) -> ParseOptions {
+        ParseOptions {
+            include_profile: self.include_profile,
+            include_raw_html: self.include_raw_html,
+            include_replies: self.include_replies,
+            image_quality: self.image_quality,
+            media_urls: self.media_urls,
+            instance: self.instance.clone(),
+            selectors: self.selector_profile.clone(),
+        }
+    }
+
+    /// Proxied client to send the next request with, if there are any
+    fn next_proxy(&self) -> Option<HttpClient<'a>> {
+        if self.proxies.is_empty() {
+            return None;
+        }
+        let i = self.next_proxy.fetch_add(1, Ordering::Relaxed);
+        Some(self.proxies[i % self.proxies.len()])
+    }
+
+    fn rng(&self) -> &SearchRng {
+        self.rng.get_or_init(|| SearchRng::new(self.seed))
+    }
+
+    fn requester(&self) -> Requester<'_> {
+        Requester {
+            client: self.http_client(),
+            rate_limit: self.rate_limit.as_deref(),
+            shared_rate_limit: self.shared_rate_limit.as_deref(),
+            warnings: self.warnings.as_ref(),
+            rng: self.rng(),
+            cache: self.cache,
+            cookie_jar: self.cookie_jar,
+            limits: self.response_limits(),
+            rate_limit_retries: self.rate_limit_retries,
+            stats: &self.stats,
+        }
+    }
+
+    fn response_limits(&self) -> ResponseLimits {
+        ResponseLimits {
+            max_size: self.max_response_size,
+            read_timeout: self.read_timeout,
+            body_timeout: self.body_timeout,
+        }
+    }
+
+    /// Client to send the next request with
+    fn http_client(&self) -> HttpClient<'_> {
+        self.next_proxy().unwrap_or(self.client)
+    }
+
+    /// User-Agent to send the next request with, if the client's isn't used
+    fn next_user_agent(&self) -> Option<&HeaderValue> {
+        if self.user_agents.is_empty() {
+            return None;
+        }
+        let i = self.next_user_agent.fetch_add(1, Ordering::Relaxed);
+        Some(&self.user_agents[i % self.user_agents.len()])
+    }
+
+    /// Headers for a request to the instance
+    fn instance_headers(&self) -> HeaderMap {
+        let mut headers = self.headers.clone();
+        headers.insert(COOKIE, self.cookie_header.clone());
+        self.add_user_agent(&mut headers);
+        headers
+    }
+
+    fn add_user_agent(&self, headers: &mut HeaderMap) {
+        if headers.contains_key(USER_AGENT) {
+            return;
+        }
+        if let Some(user_agent) = self.next_user_agent() {
+            headers.insert(USER_AGENT, user_agent.clone());
+        }
+    }
+
+    /// Report a warning to the warnings channel, or stderr without one
+    fn warn(&self, warning: Warning) {
+        warn(self.warnings.as_ref(), warning)
+    }
+
+    async fn fetch_path(&self, instance: &str, path: &str) -> Result<String, NitterError> {
+        let url = format!("{}{}", instance, path);
+        let headers = self.instance_headers();
+        let requester = self.requester();
+        let request = requester.fetch_text(&headers, &url);
+        let fetched = time_limited(request, self.page_timeout, self.deadline).await?;
+        Ok(fetched.text)
+    }
+
+    /// Request a timeline page, racing it against `race_instance` if `race` is set, once it's
+    /// `not_before`
+    ///
+    /// The returned future doesn't borrow the scraper, so it can be kept as a prefetch.
+    fn request_page(
+        &self,
+        path: &str,
+        race: bool,
+        not_before: Option<Instant>,
+    ) -> BoxFuture<'a, Result<Fetched, NitterError>> {
+        let client = self.next_proxy().unwrap_or(self.client);
+        let headers = self.instance_headers();
+        let rate_limit = self.rate_limit.clone();
+        let shared_rate_limit = self.shared_rate_limit.clone();
+        let warnings = self.warnings.clone();
+        let rng = self.rng().clone();
+        let cache = self.cache;
+        let cookie_jar = self.cookie_jar;
+        let limits = self.response_limits();
+        let rate_limit_retries = self.rate_limit_retries;
+        let page_timeout = self.page_timeout;
+        let deadline = self.deadline;
+        let stats = self.stats.clone();
+        let urls: Vec<_> = std::iter::once(&self.instance)
+            .chain(self.race_instance.as_ref().filter(|_| race))
+            .map(|instance| format!("{}{}", instance, path))
+            .collect();
+        Box::pin(async move {
+            let requester = Requester {
+                client,
+                rate_limit: rate_limit.as_deref(),
+                shared_rate_limit: shared_rate_limit.as_deref(),
+                warnings: warnings.as_ref(),
+                rng: &rng,
+                cache,
+                cookie_jar,
+                limits,
+                rate_limit_retries,
+                stats: &stats,
+            };
+            // Take whichever instance responds successfully first
+            let requests = urls
+                .iter()
+                .map(|url| Box::pin(requester.fetch_text(&headers, url)));
+            let request = async {
+                if let Some(not_before) = not_before {
+                    timer::sleep_until(not_before).await;
+                }
+                let requests = futures_util::future::select_ok(requests);
+                let (page, _) = time_limited(requests, page_timeout, None).await?;
+                Ok(page)
+            };
+            time_limited(request, None, deadline).await
+        })
+    }
+
+    /// Path of a timeline page, `rss` selects its feed instead of its HTML
+    fn page_path(&self, get_params: &str, rss: bool) -> String {
+        let feed = if rss { "/rss" } else { "" };
+        format!("{}{}{}", self.query.url_path(), feed, get_params)
+    }
+
+    /// Separate the items that couldn't be parsed, which fail the page unless parsing is lenient
+    fn split_item_errors(
+        &self,
+        items: Vec<Result<TimelineItem, NitterError>>,
+    ) -> Result<(Vec<TimelineItem>, Vec<NitterError>), NitterError> {
+        if !self.lenient_parse {
+            return Ok((items.into_iter().collect::<Result<_, _>>()?, vec![]));
+        }
+        let (items, errors): (Vec<_>, Vec<_>) = items.into_iter().partition(|item| item.is_ok());
+        Ok((
+            items.into_iter().filter_map(Result::ok).collect(),
+            errors.into_iter().filter_map(Result::err).collect(),
+        ))
+    }
+
+    /// Fetch the quoted tweets of tweets, and of those quoted tweets up to `resolve_quotes` deep
+    async fn resolve_tweet_quotes(&self, tweets: Vec<&mut Tweet>) {
+        let mut level = tweets;
+        for _ in 0..self.resolve_quotes {
+            let quoted = futures_util::future::join_all(
+                level
+                    .iter()
+                    .map(|t| async move { self.fetch_quoted_tweet(t.quoted_id?).await }),
+            )
+            .await;
+
+            let mut next_level = Vec::new();
+            for (t, quoted) in level.into_iter().zip(quoted) {
+                t.quoted_tweet = quoted.map(Box::new);
+                next_level.extend(t.quoted_tweet.as_deref_mut());
+            }
+            level = next_level;
+        }
+    }
+
+    /// Fetch a tweet by ID, or `None` if it's unavailable
+    async fn fetch_quoted_tweet(&self, id: u128) -> Option<Tweet> {
+        let path = NitterQuery::Status {
+            id: id.try_into().ok()?,
+        }
+        .url_path();
+        let text = match self.fetch_path(&self.instance, &path).await {
+            Ok(text) => text,
+            // Deleted and withheld quoted tweets are common, don't report them
+            Err(NitterError::NotFound) => return None,
+            Err(e) => {
+                self.warn(Warning::QuoteUnavailable {
+                    id,
+                    reason: e.to_string(),
+                });
+                return None;
+            }
+        };
+
+        // Quoted tweets shouldn't skew the coverage of the scraped timeline
+        let options = ParseOptions {
+            include_replies: false,
+            ..self.parse_options()
+        };
+        let (items, _) = parse_nitter_single(text, &options, &mut FieldCoverage::default()).ok()?;
+        match items.into_iter().next()? {
+            TimelineItem::Tweet(t) => Some(t),
+            _ => None,
+        }
+    }
+
+    /// Resolve t.co and instance redirect links of tweets
+    async fn expand_tweet_links(&self, tweets: Vec<&mut Tweet>) {
+        let links: Vec<_> = tweets
+            .iter()
+            .flat_map(|t| t.links.iter().cloned())
+            .collect();
+        let mut expanded = futures_util::stream::iter(links)
+            .map(|link| self.expand_link(link))
+            .buffered(self.link_concurrency.max(1))
+            .collect::<Vec<_>>()
+            .await
+            .into_iter();
+
+        for t in tweets {
+            t.expanded_links = Some(expanded.by_ref().take(t.links.len()).collect());
+        }
+    }
+
+    async fn expand_link(&self, link: String) -> String {
+        let instance_host = Url::parse(&self.instance)
+            .ok()
+            .and_then(|u| u.host_str().map(|h| h.to_owned()));
+        let host = Url::parse(&link)
+            .ok()
+            .and_then(|u| u.host_str().map(|h| h.to_owned()));
+        let headers = match host {
+            // Don't send the instance's credentials elsewhere
+            Some(host) if host == "t.co" => {
+                let mut headers = HeaderMap::new();
+                self.add_user_agent(&mut headers);
+                headers
+            }
+            Some(host) if Some(&host) == instance_host.as_ref() => self.instance_headers(),
+            _ => return link,
+        };
+
+        StatsCounters::add(&self.stats.requests, 1);
+        let Ok(response) = self
+            .http_client()
+            .send(HttpMethod::Head, &link, headers)
+            .await
+        else {
+            return link;
+        };
+        if response.status.is_redirection() {
+            // Client doesn't follow redirects, use the first hop
+            response
+                .headers
+                .get(LOCATION)
+                .and_then(|l| l.to_str().ok())
+                .map(|l| l.to_owned())
+                .unwrap_or(link)
+        } else {
+            response.url
+        }
+    }
+}
+
+impl<'s, 'a> Search<'s, 'a> {
+    fn new(scraper: &'s NitterScraper<'a>) -> Self {
+        scraper.stats.start();
+        *scraper.last_search.lock().unwrap() = LastSearch::default();
+        let mut search = Self {
+            scraper,
+            state: Default::default(),
+            prefetched: None,
+            max_pages: scraper.max_pages,
+            watermark: None,
+            checked: false,
+            next_page_at: None,
+            _running: scraper.searches.token(),
+        };
+        search.reset();
+        search
+    }
+
+    /// Start over from the first page, or the one `resume_from` points at
+    fn reset(&mut self) {
+        self.state = Default::default();
+        self.prefetched = None;
+        if let Some(token) = &self.scraper.resume_from {
+            self.state.cursor = match &token.cursor {
+                Some(cursor) => NitterCursor::More(cursor.clone()),
+                None => NitterCursor::Initial,
+            };
+            self.state.page = token.page;
+            self.state.resume_skip = token.skip;
+        }
+    }
+
+    fn into_stream(self) -> impl Stream<Item = Result<TimelineItem, NitterError>> + use<'s, 'a> {
+        futures_util::stream::unfold(self, |mut search| async {
+            let item = search.next_item().await?;
+            Some((item, search))
+        })
+    }
+
+    /// Search for the tweets newer than the watermark, oldest first after the items that couldn't
+    /// be parsed, and advance it
+    async fn new_tweets(&mut self) -> Result<Vec<Result<Tweet, NitterError>>, NitterError> {
+        let mut errors = vec![];
+        let mut tweets = vec![];
+        self.reset();
+        while let Some(item) = self.next_item().await {
+            match item {
+                Ok(TimelineItem::Tweet(tweet)) => tweets.push(tweet),
+                Ok(_) => {}
+                Err(e) => errors.push(e),
+            }
+        }
+        // Missing some of the new tweets, they're searched for again at the next check
+        if self.state.finish_reason == Some(FinishReason::Error) {
+            self.checked = false;
+            return Err(errors.pop().unwrap());
+        }
+        // Tweets of the first page past the limit weren't seen
+        self.checked = self.state.finish_reason != Some(FinishReason::Limit);
+
+        tweets.sort_by_key(|t| t.id);
+        if let Some(newest) = tweets.last() {
+            self.watermark = self.watermark.max(Some(newest.id));
+        }
+        Ok(errors
+            .into_iter()
+            .map(Err)
+            .chain(tweets.into_iter().map(Ok))
+            .collect())
+    }
+
+    /// Advance the search to its next item, `None` once it ended
+    async fn next_item(&mut self) -> Option<Result<TimelineItem, NitterError>> {
+        // Stop if previously errored
+        if self.state.finish_reason == Some(FinishReason::Error) {
+            return None;
+        }
+
+        // Stop if limit reached
+        if let Some(limit) = self.limit {
+            if self.state.count >= limit {
+                self.finish(FinishReason::Limit);
+                return None;
+            }
+        }
+
+        if self.prefetch > 0 {
+            self.prefetch_pages().await;
+        }
+
+        // Since skip-retweets may cause entire page to be empty, loop until cursor doesn't
+        // exist anymore
+        let finish_reason = loop {
+            if let Some(e) = self.state.item_errors.pop_front() {
+                return Some(Err(e));
+            }
 
-        let tweets = if self.skip_retweets {
+            // Return tweet if available
+            if let Some(item) = self.state.items.front() {
+                let tweet = match item {
+                    TimelineItem::Tweet(tweet) => tweet,
+                    TimelineItem::Unavailable(_) | TimelineItem::Disappeared(_) => {
+                        return Some(Ok(self.state.pop_item()));
+                    }
+                    TimelineItem::User(_) => {
+                        self.state.count += 1;
+                        return Some(Ok(self.state.pop_item()));
+                    }
+                };
+                match self.should_return_tweet(tweet) {
+                    ReturnedTweet::Normal => {
+                        self.state.count += 1;
+                        let item = self.state.pop_item();
+                        return Some(Ok(self.returned(item)));
+                    }
+                    ReturnedTweet::Pinned => {
+                        let pinned = self.state.pinned.take().unwrap();
+                        if self.stops_at(&pinned) {
+                            StatsCounters::add(&self.stats.items_skipped, 1);
+                            continue;
+                        }
+                        self.state.count += 1;
+                        let item = self.returned(TimelineItem::Tweet(pinned));
+                        return Some(Ok(item));
+                    }
+                    ReturnedTweet::Skip => {
+                        self.state.pop_item();
+                        StatsCounters::add(&self.stats.items_skipped, 1);
+                        continue;
+                    }
+                    ReturnedTweet::Stop(reason) => break reason,
+                }
+            }
+
+            match self.state.pages.pop_front() {
+                Some(Ok(page)) => {
+                    self.state.start_page(page);
+                    continue;
+                }
+                Some(Err(e)) => {
+                    self.finish(FinishReason::Error);
+                    return Some(Err(e));
+                }
+                None => {}
+            }
+
+            if let NitterCursor::End = self.state.cursor {
+                break FinishReason::EndOfTimeline;
+            }
+            if self
+                .max_pages
+                .is_some_and(|max_pages| self.state.pages_fetched >= max_pages)
+            {
+                break FinishReason::MaxPages;
+            }
+
+            if self.is_cancelled() {
+                break FinishReason::Cancelled;
+            }
+
+            // Scrape nitter
+            match self.scrape_page().await {
+                Ok(page) => {
+                    self.state.pages_fetched += 1;
+                    self.state.start_page(page);
+                }
+                Err(e) => {
+                    self.finish(FinishReason::Error);
+                    return Some(Err(e));
+                }
+            }
+        };
+
+        // Return pinned tweet if needed
+        if let Some(t) = self.state.pinned.take().filter(|t| !self.stops_at(t)) {
+            let item = self.returned(TimelineItem::Tweet(t));
+            return Some(Ok(item));
+        }
+
+        self.finish(finish_reason);
+        None
+    }
+
+    fn finish(&mut self, reason: FinishReason) {
+        self.state.finish_reason = Some(reason);
+        self.stats.finish();
+        self.publish();
+    }
+
+    /// Record how the search ended for the scraper's accessors
+    fn publish(&self) {
+        *self.scraper.last_search.lock().unwrap() = LastSearch {
+            finish_reason: self.state.finish_reason,
+            resume_token: self.state.resume_token(),
+            coverage: self.state.coverage.clone(),
+        };
+    }
+
+    fn should_return_tweet(&self, tweet: &Tweet) -> ReturnedTweet {
+        if self.reorder_pinned {
+            if let Some(p) = &self.state.pinned {
+                // Should use tweet id here but nitter doesn't expose it for retweets
+                if p.created_at_ts > tweet.created_at_ts {
+                    return ReturnedTweet::Pinned;
+                }
+            }
+        }
+
+        // Stop if minimum tweet id reached or the stop condition is met, pinned tweets are older
+        // than the tweets around them
+        let reason = if self.lower_bound().is_some_and(|min_id| tweet.id < min_id) {
+            FinishReason::MinId
+        } else if self.stops_at(tweet) {
+            FinishReason::StopCondition
+        } else {
+            // Return next tweet
+            return ReturnedTweet::Normal;
+        };
+        if tweet.pinned {
+            ReturnedTweet::Skip
+        } else {
+            ReturnedTweet::Stop(reason)
+        }
+    }
+
+    /// Smallest tweet ID to return, also excluding the tweets already seen while watching
+    fn lower_bound(&self) -> Option<u128> {
+        let watermark = self.watermark.map(|id| id + 1);
+        self.scraper.lower_bound().max(watermark)
+    }
+
+    /// Get a timeline page, using the prefetched request if it was for the same path
+    async fn timeline_page(&mut self, path: &str, race: bool) -> Result<Fetched, NitterError> {
+        match self.prefetched.take() {
+            Some(mut prefetch) if prefetch.path == path => {
+                (&mut prefetch.response).await;
+                Pin::new(&mut prefetch.response).take_output().unwrap()
+            }
+            _ => {
+                let not_before = self.page_slot();
+                self.request_page(path, race, not_before).await
+            }
+        }
+    }
+
+    /// When the next page may be requested with `page_delay`, reserving the time for it
+    fn page_slot(&mut self) -> Option<Instant> {
+        if self.page_delay.is_zero() && self.jitter.is_zero() {
+            return None;
+        }
+        let now = Instant::now();
+        let slot = self.next_page_at.map_or(now, |next| next.max(now));
+        self.next_page_at = Some(slot + self.page_delay + self.rng().jitter(self.jitter));
+        Some(slot)
+    }
+
+    /// Process pages ahead of the consumer up to `prefetch`, without waiting for responses
+    async fn prefetch_pages(&mut self) {
+        while self.state.pages.len() < self.prefetch {
+            let get_params = match &self.state.cursor {
+                NitterCursor::More(cursor) => cursor.clone(),
+                NitterCursor::Initial | NitterCursor::End => return,
+            };
+            if matches!(self.state.pages.back(), Some(Err(_)))
+                || self.is_cancelled()
+                || self
+                    .max_buffered_items
+                    .is_some_and(|max| self.state.buffered_items() >= max)
+                || self
+                    .max_pages
+                    .is_some_and(|max_pages| self.state.pages_fetched >= max_pages)
+            {
+                return;
+            }
+
+            let rss = self.use_rss();
+            let path = self.page_path(&get_params, rss);
+            if self.prefetched.as_ref().is_none_or(|p| p.path != path) {
+                let not_before = self.page_slot();
+                let request = self.request_page(&path, !rss, not_before);
+                self.prefetched = Some(Prefetch {
+                    response: futures_util::future::maybe_done(request),
+                    path,
+                });
+            }
+            let prefetch = self.prefetched.as_mut().unwrap();
+            if futures_util::poll!(&mut prefetch.response).is_pending() {
+                return;
+            }
+
+            let page = self.scrape_page().await;
+            if page.is_ok() {
+                self.state.pages_fetched += 1;
+            }
+            self.state.pages.push_back(page);
+        }
+    }
+
+    /// Fetch and parse a page of the HTML timeline
+    async fn scrape_html(
+        &mut self,
+        get_params: &str,
+    ) -> Result<(Vec<TimelineItem>, Vec<NitterError>, NitterCursor), NitterError> {
+        // Send request
+        let path = self.page_path(get_params, false);
+        let fetched = self.timeline_page(&path, true).await?;
+        if self.first_page_unchanged(&fetched) {
+            return Ok((vec![], vec![], NitterCursor::End));
+        }
+        let text = fetched.text;
+
+        // Parse html and update cursor
+        let options = self.parse_options();
+        if self.query.is_single() {
+            let (mut items, cursor) =
+                parse_nitter_single(text, &options, &mut self.state.coverage)?;
+            // Every page of replies repeats the main tweet
+            let repeated = match (&self.query, items.first()) {
+                (NitterQuery::Status { id }, Some(TimelineItem::Tweet(tweet))) => {
+                    tweet.id == u128::from(*id)
+                }
+                _ => false,
+            };
+            if self.state.page > 0 && repeated {
+                items.remove(0);
+            }
+            Ok((items, vec![], cursor))
+        } else {
+            let (items, cursor) =
+                parse_nitter_html_items(text, &options, &mut self.state.coverage)?;
+            let (items, errors) = self.split_item_errors(items)?;
+            Ok((items, errors, cursor))
+        }
+    }
+
+    /// Whether a fetched page is the first one and is unchanged since the last check while
+    /// watching, which saw everything on it, so it doesn't need to be parsed again
+    fn first_page_unchanged(&mut self, fetched: &Fetched) -> bool {
+        self.state.unchanged = fetched.not_modified && self.state.page == 0 && self.checked;
+        self.state.unchanged
+    }
+
+    /// Whether the current query can be read from its RSS feed with the configured options
+    fn use_rss(&self) -> bool {
+        self.prefer_rss
+            && !self.state.rss_failed
+            && !self.include_profile
+            && !self.include_raw_html
+            && !matches!(
+                self.query,
+                NitterQuery::Status { .. } | NitterQuery::Users { .. }
+            )
+    }
+
+    /// Fetch a page of the RSS feed, or `None` to fall back to HTML
+    async fn scrape_rss(
+        &mut self,
+        get_params: &str,
+    ) -> Result<Option<(Vec<TimelineItem>, Vec<NitterError>, NitterCursor)>, NitterError> {
+        let path = self.page_path(get_params, true);
+        let result = async {
+            let fetched = self.timeline_page(&path, false).await?;
+            if self.first_page_unchanged(&fetched) {
+                return Ok((vec![], vec![], None));
+            }
+            // The feed's next page cursor is only sent as a header
+            let min_id = fetched
+                .headers
+                .get("Min-Id")
+                .and_then(|v| v.to_str().ok())
+                .map(|v| v.to_owned());
+            let items = parse_nitter_rss(&fetched.text, &self.parse_options())?;
+            let (items, errors) = self.split_item_errors(items)?;
+            Ok::<_, NitterError>((items, errors, min_id))
+        }
+        .await;
+
+        match result {
+            Ok((items, errors, min_id)) => {
+                let cursor = match min_id {
+                    Some(min_id) if !items.is_empty() => {
+                        let params = self.initial_get_params();
+                        let separator = if params.is_empty() { '?' } else { '&' };
+                        let min_id = utf8_percent_encode(&min_id, NON_ALPHANUMERIC);
+                        NitterCursor::More(format!("{}{}cursor={}", params, separator, min_id))
+                    }
+                    _ => NitterCursor::End,
+                };
+                Ok(Some((items, errors, cursor)))
+            }
+            // The HTML pages are rate limited just the same, and can't beat the deadline either
+            Err(e @ (NitterError::RateLimited { .. } | NitterError::DeadlineExceeded)) => Err(e),
+            Err(e) => {
+                self.state.rss_failed = true;
+                self.warn(Warning::RssUnavailable {
+                    reason: e.to_string(),
+                });
+                Ok(None)
+            }
+        }
+    }
+
+    async fn scrape_page(&mut self) -> Result<Page, NitterError> {
+        let page = self.state.page;
+        // Use cursor if it exists
+        let (get_params, page_cursor) = match self.state.cursor {
+            NitterCursor::Initial => (self.initial_get_params(), None),
+            NitterCursor::More(ref c) => (c.clone(), Some(c.clone())),
+            NitterCursor::End => {
+                return Ok(Page {
+                    cursor: None,
+                    page,
+                    skipped: 0,
+                    items: vec![],
+                    errors: vec![],
+                })
+            }
+        };
+
+        let mut nitter_retry = 0;
+        let (items, mut errors, rss) = loop {
+            let rss_page = if self.use_rss() {
+                self.scrape_rss(&get_params).await?
+            } else {
+                None
+            };
+            let rss = rss_page.is_some();
+            let (mut items, errors, cursor) = match rss_page {
+                Some(page) => page,
+                None => self.scrape_html(&get_params).await?,
+            };
+            StatsCounters::add(
+                &self.stats.items_parsed,
+                (items.len() + errors.len()) as u64,
+            );
+
+            // Record where in the pagination each tweet was seen
+            for item in items.iter_mut() {
+                match item {
+                    TimelineItem::Tweet(tweet) => tweet.page = Some(self.state.page),
+                    TimelineItem::Unavailable(unavailable) => {
+                        unavailable.page = Some(self.state.page)
+                    }
+                    TimelineItem::User(user) => user.page = Some(self.state.page),
+                    TimelineItem::Disappeared(_) => {}
+                }
+            }
+
+            let items = if self.reorder_pinned {
+                // Extract pinned tweet
+                let (mut pinned, unpinned): (Vec<_>, Vec<_>) = items
+                    .into_iter()
+                    .partition(|item| matches!(item, TimelineItem::Tweet(t) if t.pinned));
+                if let Some(TimelineItem::Tweet(t)) = pinned.pop() {
+                    let in_bounds = self.lower_bound().is_none_or(|min_id| t.id >= min_id)
+                        && self.upper_bound().is_none_or(|max_id| t.id <= max_id);
+                    if in_bounds {
+                        self.state.pinned = Some(t);
+                    } else {
+                        StatsCounters::add(&self.stats.items_skipped, 1);
+                    }
+                }
+                unpinned
+            } else {
+                items
+            };
+
+            // Sometimes nitter will return nothing, retry a few times to make sure it's correct
+            // An unchanged page was already checked, it has no new tweets
+            let unchanged = std::mem::take(&mut self.state.unchanged);
+            if !items.is_empty() || !errors.is_empty() || unchanged || nitter_retry > 10 {
+                self.state.cursor = match (cursor, &self.state.cursor) {
+                    // Following the same cursor again would loop forever
+                    (NitterCursor::More(new), NitterCursor::More(old)) if &new == old => {
+                        self.warn(Warning::RepeatedCursor { cursor: new });
+                        NitterCursor::End
+                    }
+                    (cursor, _) => cursor,
+                };
+                self.state.page += 1;
+                StatsCounters::add(&self.stats.pages_fetched, 1);
+                break (items, errors, rss);
+            }
+
+            nitter_retry += 1;
+            self.warn(Warning::EmptyPage {
+                page: self.state.page,
+                attempt: nitter_retry,
+            });
+            StatsCounters::add(&self.stats.retries, 1);
+            timer::sleep(Duration::from_secs(1)).await;
+        };
+
+        let parsed = items.len() + errors.len();
+        let items = if self.skip_retweets {
             // Filter out retweets
-            tweets.into_iter().filter(|t| !t.retweet).collect()
+            items
+                .into_iter()
+                .filter(|item| !matches!(item, TimelineItem::Tweet(t) if t.retweet))
+                .collect()
         } else {
-            tweets
+            items
         };
 
-        Ok(tweets)
+        // Filter out tweets newer than the maximum ID
+        let mut items = match self.upper_bound() {
+            Some(max_id) => items
+                .into_iter()
+                .filter(|item| !matches!(item, TimelineItem::Tweet(t) if t.id > max_id))
+                .collect(),
+            None => items,
+        };
+
+        // Skip what was returned before resuming, before spending requests on it
+        let skipped = std::mem::take(&mut self.state.resume_skip).min(items.len());
+        items.drain(..skipped);
+        if skipped > 0 {
+            // The page's errors were yielded before its items
+            errors.clear();
+        }
+        StatsCounters::add(
+            &self.stats.items_skipped,
+            (parsed - items.len() - errors.len()) as u64,
+        );
+
+        if self.expand_links {
+            // The pinned tweet may have been extracted from this page already
+            let mut pinned = self.state.pinned.take();
+            let tweets = items
+                .iter_mut()
+                .filter_map(|item| match item {
+                    TimelineItem::Tweet(t) => Some(t),
+                    TimelineItem::Unavailable(_)
+                    | TimelineItem::User(_)
+                    | TimelineItem::Disappeared(_) => None,
+                })
+                .chain(pinned.as_mut().filter(|t| t.expanded_links.is_none()))
+                .collect();
+            self.expand_tweet_links(tweets).await;
+            self.state.pinned = pinned;
+        }
+
+        if self.resolve_quotes > 0 {
+            let mut pinned = self.state.pinned.take();
+            let tweets = items
+                .iter_mut()
+                .filter_map(|item| match item {
+                    TimelineItem::Tweet(t) => Some(t),
+                    TimelineItem::Unavailable(_)
+                    | TimelineItem::User(_)
+                    | TimelineItem::Disappeared(_) => None,
+                })
+                .chain(pinned.as_mut().filter(|t| t.quoted_tweet.is_none()))
+                .collect();
+            self.resolve_tweet_quotes(tweets).await;
+            self.state.pinned = pinned;
+        }
+
+        if let Some(on_page) = &self.on_page {
+            let url = format!("{}{}", self.instance, self.page_path(&get_params, rss));
+            let tweets = items
+                .iter()
+                .filter(|item| matches!(item, TimelineItem::Tweet(_)))
+                .count();
+            on_page(page, &url, tweets);
+        }
+
+        Ok(Page {
+            cursor: page_cursor,
+            page,
+            skipped,
+            items,
+            errors,
+        })
+    }
+}
+
+impl<'a> Deref for Search<'_, 'a> {
+    type Target = NitterScraper<'a>;
+
+    fn deref(&self) -> &Self::Target {
+        self.scraper
+    }
+}
+
+impl Drop for Search<'_, '_> {
+    fn drop(&mut self) {
+        self.publish();
+    }
+}
+
+/// Report a warning to the warnings channel, or stderr without one
+fn warn(warnings: Option<&UnboundedSender<Warning>>, warning: Warning) {
+    match warnings {
+        Some(sender) => {
+            let _ = sender.send(warning);
+        }
+        // Empty pages are also how timelines without tweets look, don't clutter stderr
+        None if matches!(warning, Warning::EmptyPage { .. }) => {}
+        None => eprintln!("{}", warning),
+    }
+}
+
+/// Fail `request` with [`NitterError::Timeout`] if it takes longer than `timeout`, or with
+/// [`NitterError::DeadlineExceeded`] if it's still running at `deadline`
+async fn time_limited<T>(
+    request: impl Future<Output = Result<T, NitterError>>,
+    timeout: Option<Duration>,
+    deadline: Option<Instant>,
+) -> Result<T, NitterError> {
+    if deadline.is_some_and(|deadline| deadline <= Instant::now()) {
+        return Err(NitterError::DeadlineExceeded);
+    }
+    let request = async {
+        match timeout {
+            Some(timeout) => timer::timeout(timeout, request)
+                .await
+                .unwrap_or(Err(NitterError::Timeout)),
+            None => request.await,
+        }
+    };
+    match deadline {
+        Some(deadline) => timer::timeout_at(deadline, request)
+            .await
+            .unwrap_or(Err(NitterError::DeadlineExceeded)),
+        None => request.await,
+    }
+}
+
+/// Everything needed to send requests to the instance, borrowed from the scraper or from what a
+/// prefetch owns
+#[derive(Clone, Copy)]
+struct Requester<'r> {
+    client: HttpClient<'r>,
+    rate_limit: Option<&'r RateLimiter>,
+    shared_rate_limit: Option<&'r RateLimiter>,
+    warnings: Option<&'r UnboundedSender<Warning>>,
+    rng: &'r SearchRng,
+    cache: Option<&'r ResponseCache>,
+    cookie_jar: Option<&'r CookieJar>,
+    limits: ResponseLimits,
+    rate_limit_retries: usize,
+    stats: &'r StatsCounters,
+}
+
+impl Requester<'_> {
+    /// Fetch a page's headers and body, waiting and retrying while rate limited
+    async fn fetch_text(&self, headers: &HeaderMap, url: &str) -> Result<Fetched, NitterError> {
+        let mut i = 0;
+        loop {
+            match self.try_fetch_text(headers.clone(), url).await {
+                Err(NitterError::RateLimited {
+                    status,
+                    retry_after,
+                }) if i < self.rate_limit_retries => {
+                    i += 1;
+                    StatsCounters::add(&self.stats.retries, 1);
+                    let delay = retry_after.unwrap_or_else(|| self.rng.retry_delay(i));
+                    warn(self.warnings, Warning::RateLimited { status, delay });
+                    timer::sleep(delay).await;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Fetch a page once, revalidating the cached copy if there is one
+    async fn try_fetch_text(
+        &self,
+        mut headers: HeaderMap,
+        url: &str,
+    ) -> Result<Fetched, NitterError> {
+        let cached = match self.cache {
+            Some(cache) => cache.get(url).await,
+            None => None,
+        };
+        if let Some(cached) = &cached {
+            cached.add_validators(&mut headers);
+        }
+        let response = self.fetch_response(headers, url).await?;
+        if response.status == StatusCode::NOT_MODIFIED {
+            return match cached {
+                Some(cached) => Ok(Fetched {
+                    headers: cached.headers(),
+                    text: cached.into_body(),
+                    not_modified: true,
+                }),
+                None => Err(NitterError::Network(format!(
+                    "received status code {}",
+                    response.status.as_u16()
+                ))),
+            };
+        }
+
+        let HttpResponse {
+            status,
+            headers,
+            body: text,
+            ..
+        } = response;
+        StatsCounters::add(&self.stats.bytes_downloaded, text.len() as u64);
+        // Some instances serve their rate limit error page with a success status
+        if text.contains(RATE_LIMITED_PAGE) {
+            return Err(NitterError::RateLimited {
+                status: status.as_u16(),
+                retry_after: None,
+            });
+        }
+        if let Some(cache) = self.cache {
+            cache.put(url, &headers, &text).await;
+        }
+        Ok(Fetched {
+            headers,
+            text,
+            not_modified: false,
+        })
+    }
+
+    async fn fetch_response(
+        &self,
+        mut headers: HeaderMap,
+        url: &str,
+    ) -> Result<HttpResponse, NitterError> {
+        if let Some(jar_cookies) = self.cookie_jar.and_then(|jar| jar.header(url)) {
+            let cookies = match headers.get(COOKIE).and_then(|v| v.to_str().ok()) {
+                Some(cookies) => format!("{}; {}", cookies, jar_cookies),
+                None => jar_cookies,
+            };
+            if let Ok(cookies) = HeaderValue::from_str(&cookies) {
+                headers.insert(COOKIE, cookies);
+            }
+        }
+        for rate_limiter in [self.rate_limit, self.shared_rate_limit]
+            .into_iter()
+            .flatten()
+        {
+            rate_limiter.wait().await;
+        }
+        StatsCounters::add(&self.stats.requests, 1);
+        let response = self
+            .client
+            .send_limited(HttpMethod::Get, url, headers, self.limits)
+            .await?;
+        // Challenge pages set cookies with error statuses too
+        if let Some(jar) = self.cookie_jar {
+            jar.store(&response.url, &response.headers).await;
+        }
+        if let Some(provider) = challenge_provider(&response) {
+            return Err(NitterError::Challenge(provider.to_owned()));
+        }
+
+        if response.status == StatusCode::TOO_MANY_REQUESTS {
+            let retry_after = response
+                .headers
+                .get(RETRY_AFTER)
+                .and_then(|v| v.to_str().ok())
+                .and_then(parse_retry_after);
+            return Err(NitterError::RateLimited {
+                status: response.status.as_u16(),
+                retry_after,
+            });
+        } else if response.status == StatusCode::NOT_MODIFIED {
+            // Conditional request for a cached page
+            return Ok(response);
+        } else if response.status == StatusCode::NOT_FOUND {
+            // Return nothing on 404
+            return Err(NitterError::NotFound);
+        } else if !response.status.is_success() {
+            // Error if bad status code
+            return Err(NitterError::Network(format!(
+                "received status code {}",
+                response.status.as_u16()
+            )));
+        }
+
+        Ok(response)
+    }
+}
+
+/// Name of the anti-bot service whose challenge page a response is, if it is one
+pub(crate) fn challenge_provider(response: &HttpResponse) -> Option<&'static str> {
+    if response
+        .headers
+        .get("cf-mitigated")
+        .is_some_and(|v| v == "challenge")
+    {
+        return Some("Cloudflare");
+    }
+    // Cloudflare also injects its challenge scripts into pages that aren't challenges
+    if !response.status.is_success()
+        && (response.body.contains("window._cf_chl_opt")
+            || response.body.contains("/cdn-cgi/challenge-platform/h/"))
+    {
+        return Some("Cloudflare");
+    }
+    if response.body.contains(r#"id="anubis_challenge""#) {
+        return Some("Anubis");
+    }
+    None
+}
+
+/// Parse a Retry-After header, which is either a number of seconds or a date
+fn parse_retry_after(value: &str) -> Option<Duration> {
+    if let Ok(seconds) = value.trim().parse() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let date = OffsetDateTime::parse(value.trim(), &Rfc2822).ok()?;
+    let delay = date - OffsetDateTime::now_utc();
+    Some(delay.try_into().unwrap_or(Duration::ZERO))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::http_client::{HttpFetch, HttpRequest};
+    use crate::parse::parse_nitter_html;
+
+    // The pages in these tests are synthetic, with only the markup the parser reads, not captured
+    // from an instance
+
+    #[test]
+    fn test_search_url() {
+        let query = NitterQuery::Search {
+            query: "from:jack #rust".into(),
+        };
+        assert_eq!(query.url_path(), "/search");
+        assert_eq!(
+            query.encode_get_params(),
+            "?f=tweets&q=from%3Ajack%20%23rust"
+        );
+    }
+
+    #[test]
+    fn test_max_id_params() {
+        let scraper = |query| {
+            NitterScraper::builder()
+                .client(HttpClient::Custom(&OFFLINE))
+                .instance("https://nitter.net")
+                .query(query)
+                .max_id(Some(100))
+                .try_build()
+                .unwrap()
+        };
+        let search = scraper(NitterQuery::Search {
+            query: "#rust".into(),
+        });
+        assert_eq!(
+            search.initial_get_params(),
+            "?f=tweets&q=%23rust%20max%5Fid%3A100"
+        );
+        let replies = scraper(NitterQuery::RepliesTo { tweet: 20 });
+        assert_eq!(
+            replies.initial_get_params(),
+            "?f=tweets&q=conversation%5Fid%3A20%20max%5Fid%3A100"
+        );
+        let user = scraper(NitterQuery::User {
+            user: "jack".into(),
+        });
+        assert_eq!(user.initial_get_params(), "");
+    }
+
+    #[test]
+    fn test_time_bounds() {
+        let scraper = NitterScraper::builder()
+            .client(HttpClient::Custom(&OFFLINE))
+            .instance("https://nitter.net")
+            .query(NitterQuery::Search {
+                query: "#rust".into(),
+            })
+            .min_id(Some(5 << 22))
+            .max_id(Some(1 << 50))
+            .since(OffsetDateTime::from_unix_timestamp(1288904570).ok())
+            .until(OffsetDateTime::from_unix_timestamp(1288904600).ok())
+            .try_build()
+            .unwrap();
+        // The later of the lower bounds and the earlier of the upper bounds are used
+        assert_eq!(scraper.lower_bound(), Some(69595343 << 22));
+        assert_eq!(scraper.upper_bound(), Some((69625343 << 22) - 1));
+
+        // Tweets already seen while watching aren't returned again
+        let mut search = Search::new(&scraper);
+        search.watermark = Some(1 << 60);
+        assert_eq!(search.lower_bound(), Some((1 << 60) + 1));
+    }
+
+    #[test]
+    fn test_instance_validation() {
+        let build = |instance: &str| {
+            NitterScraper::builder()
+                .client(HttpClient::Custom(&OFFLINE))
+                .instance(instance)
+                .query(NitterQuery::User {
+                    user: "jack".into(),
+                })
+                .race_instance(Some("http://localhost:8080/".into()))
+                .try_build()
+        };
+
+        let scraper = build(" https://nitter.net/ ").unwrap();
+        assert_eq!(scraper.instance, "https://nitter.net");
+        assert_eq!(
+            scraper.race_instance.as_deref(),
+            Some("http://localhost:8080")
+        );
+        // Instances hosted under a path keep it
+        assert_eq!(
+            build("https://example.com/nitter//").unwrap().instance,
+            "https://example.com/nitter"
+        );
+
+        for instance in [
+            "nitter.net",
+            "ftp://nitter.net",
+            "https://nitter.net/?a=b",
+            "",
+        ] {
+            assert!(matches!(
+                build(instance),
+                Err(NitterError::InvalidInstance(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_stop_when() {
+        let item = |id: u128, extra: &str| {
+            format!(
+                r#"<div class="timeline-item">{extra}
+                    <a class="fullname" href="/user" title="User">User</a>
+                    <span class="tweet-date"><a href="/user/status/{id}#m" title="Jan 1, 2023 · 1:00 PM UTC">1 Jan</a></span>
+                    <div class="tweet-content media-body">text</div>
+                </div>"#
+            )
+        };
+        let html = format!(
+            r#"<html><body><div class="timeline">{}{}{}</div></body></html>"#,
+            item(100, r#"<div class="pinned">Pinned Tweet</div>"#),
+            item(300, ""),
+            item(200, ""),
+        );
+        let (items, _) = parse_nitter_html(
+            html,
+            &ParseOptions::default(),
+            &mut FieldCoverage::default(),
+        )
+        .unwrap();
+
+        let scraper = NitterScraper::builder()
+            .client(HttpClient::Custom(&OFFLINE))
+            .instance("https://nitter.net")
+            .query(NitterQuery::User {
+                user: "user".into(),
+            })
+            .stop_when(|tweet| tweet.id <= 200)
+            .try_build()
+            .unwrap();
+        let search = Search::new(&scraper);
+        let returned: Vec<_> = items
+            .iter()
+            .map(|item| match item {
+                TimelineItem::Tweet(tweet) => search.should_return_tweet(tweet),
+                _ => panic!("expected tweet"),
+            })
+            .collect();
+        // The pinned tweet is out of order, it doesn't stop the search
+        assert!(matches!(returned[0], ReturnedTweet::Skip));
+        assert!(matches!(returned[1], ReturnedTweet::Normal));
+        assert!(matches!(
+            returned[2],
+            ReturnedTweet::Stop(FinishReason::StopCondition)
+        ));
+    }
+
+    const TIMELINE_PAGE: &str = r#"<html><body><div class="timeline"><div class="timeline-item">
+        <a class="fullname" href="/user" title="User">User</a>
+        <span class="tweet-date"><a href="/user/status/100#m" title="Jan 1, 2023 · 1:00 PM UTC">1 Jan</a></span>
+        <div class="tweet-content media-body">text</div>
+    </div></div></body></html>"#;
+
+    /// Serves the same timeline page for every request
+    struct FakeInstance(String);
+
+    /// Client for tests that don't send requests
+    static OFFLINE: FakeInstance = FakeInstance(String::new());
+
+    impl HttpFetch for FakeInstance {
+        fn fetch(&self, request: HttpRequest) -> BoxFuture<'_, Result<HttpResponse, NitterError>> {
+            let response = HttpResponse::new(200, vec![], request.url, self.0.clone());
+            Box::pin(async { response })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_custom_fetch() {
+        let fetch = FakeInstance(TIMELINE_PAGE.to_owned());
+        let scraper = NitterScraper::builder()
+            .client(HttpClient::Custom(&fetch))
+            .instance("https://nitter.net")
+            .query(NitterQuery::User {
+                user: "user".into(),
+            })
+            .try_build()
+            .unwrap();
+        let tweets = scraper.fetch_all().await.unwrap();
+        assert_eq!(tweets.len(), 1);
+        assert_eq!(tweets[0].id, 100);
+    }
+
+    #[tokio::test]
+    async fn test_independent_searches() {
+        let fetch = FakeInstance(TIMELINE_PAGE.to_owned());
+        let scraper = NitterScraper::builder()
+            .client(HttpClient::Custom(&fetch))
+            .instance("https://nitter.net")
+            .query(NitterQuery::User {
+                user: "user".into(),
+            })
+            .try_build()
+            .unwrap();
+        let first = scraper.search().await;
+        let second = scraper.search().await;
+        futures_util::pin_mut!(first, second);
+        assert_eq!(second.next().await.unwrap().unwrap().id, 100);
+        assert_eq!(first.next().await.unwrap().unwrap().id, 100);
+        assert!(second.next().await.is_none());
+        assert_eq!(scraper.finish_reason(), Some(FinishReason::EndOfTimeline));
+        assert!(scraper.resume_token().is_none());
+
+        // Searching again starts from the top
+        assert_eq!(scraper.fetch_all().await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_cancel() {
+        let fetch = FakeInstance(TIMELINE_PAGE.to_owned());
+        let cancel = CancellationToken::new();
+        let scraper = NitterScraper::builder()
+            .client(HttpClient::Custom(&fetch))
+            .instance("https://nitter.net")
+            .query(NitterQuery::User {
+                user: "user".into(),
+            })
+            .cancel(cancel.clone())
+            .try_build()
+            .unwrap();
+        assert_eq!(scraper.fetch_all().await.unwrap().len(), 1);
+
+        // No more pages are fetched once cancelled
+        cancel.cancel();
+        assert!(scraper.fetch_all().await.unwrap().is_empty());
+        assert_eq!(scraper.finish_reason(), Some(FinishReason::Cancelled));
+        assert_eq!(scraper.stats().requests, 0);
+        let watch = scraper.watch(Duration::from_secs(60)).await;
+        assert_eq!(watch.count().await, 0);
+    }
+
+    /// Serves endless timelines with one tweet per page
+    struct EndlessInstance;
+
+    impl HttpFetch for EndlessInstance {
+        fn fetch(&self, request: HttpRequest) -> BoxFuture<'_, Result<HttpResponse, NitterError>> {
+            let page: u128 = match request.url.split_once("?cursor=") {
+                Some((_, page)) => page.parse().unwrap(),
+                None => 0,
+            };
+            let more = format!(
+                r#"<div class="show-more"><a href="?cursor={}">Load more</a></div></div></body>"#,
+                page + 1
+            );
+            let body = TIMELINE_PAGE
+                .replace("/status/100", &format!("/status/{}", 1000 - page))
+                .replacen("</div></body>", &more, 1);
+            let response = HttpResponse::new(200, vec![], request.url, body);
+            Box::pin(async { response })
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_rate_limited_page() {
+        let fetch = FakeInstance(format!(
+            "<html><body>{}</span></div></body></html>",
+            RATE_LIMITED_PAGE
+        ));
+        let (warnings, mut received) = tokio::sync::mpsc::unbounded_channel();
+        let scraper = NitterScraper::builder()
+            .client(HttpClient::Custom(&fetch))
+            .instance("https://nitter.net")
+            .query(NitterQuery::User {
+                user: "user".into(),
+            })
+            .rate_limit_retries(1)
+            .warnings(warnings)
+            .try_build()
+            .unwrap();
+
+        // The error page is served with a success status, which is what's reported
+        let error = scraper.fetch_all().await.unwrap_err();
+        assert!(matches!(
+            error,
+            NitterError::RateLimited { status: 200, .. }
+        ));
+        let warning = received.recv().await.unwrap();
+        assert!(matches!(warning, Warning::RateLimited { status: 200, .. }));
+    }
+
+    /// Serves the timeline page with an ETag, and a 304 to requests revalidating it
+    struct RevalidatedInstance;
+
+    impl HttpFetch for RevalidatedInstance {
+        fn fetch(&self, request: HttpRequest) -> BoxFuture<'_, Result<HttpResponse, NitterError>> {
+            let etag = ("etag".to_owned(), "\"abc\"".to_owned());
+            let revalidating = request.headers.iter().any(|(name, value)| {
+                name.eq_ignore_ascii_case("if-none-match") && *value == etag.1
+            });
+            let response = match revalidating {
+                true => HttpResponse::new(304, vec![etag], request.url, String::new()),
+                false => HttpResponse::new(200, vec![etag], request.url, TIMELINE_PAGE.to_owned()),
+            };
+            Box::pin(async { response })
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_watch_not_modified() {
+        let cache = ResponseCache::in_memory();
+        let checks = std::sync::Mutex::new(0);
+        let scraper = NitterScraper::builder()
+            .client(HttpClient::Custom(&RevalidatedInstance))
+            .instance("https://nitter.net")
+            .query(NitterQuery::User {
+                user: "user".into(),
+            })
+            .cache(Some(&cache))
+            .on_check(|_| *checks.lock().unwrap() += 1)
+            .try_build()
+            .unwrap();
+        let watch = scraper.watch(Duration::from_secs(60)).await;
+        futures_util::pin_mut!(watch);
+        let watching = tokio::time::timeout(Duration::from_secs(150), watch.next());
+        assert!(watching.await.is_err());
+
+        // Only the first check parsed the page, the later ones found it unchanged
+        assert_eq!(*checks.lock().unwrap(), 3);
+        assert_eq!(scraper.stats().requests, 3);
+        assert_eq!(scraper.stats().items_parsed, 1);
+    }
+
+    #[tokio::test]
+    async fn test_max_buffered_items() {
+        // Each page has one tweet, the bounded search only fetches the page it's returning
+        for (max_buffered_items, requests) in [(None, 4), (Some(1), 2)] {
+            let scraper = NitterScraper::builder()
+                .client(HttpClient::Custom(&EndlessInstance))
+                .instance("https://nitter.net")
+                .query(NitterQuery::User {
+                    user: "user".into(),
+                })
+                .prefetch(3)
+                .max_buffered_items(max_buffered_items)
+                .try_build()
+                .unwrap();
+            let search = scraper.search().await;
+            futures_util::pin_mut!(search);
+            search.next().await.unwrap().unwrap();
+            search.next().await.unwrap().unwrap();
+            assert_eq!(scraper.stats().requests, requests);
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_shutdown() {
+        let fetch = FakeInstance(TIMELINE_PAGE.to_owned());
+        let scraper = NitterScraper::builder()
+            .client(HttpClient::Custom(&fetch))
+            .instance("https://nitter.net")
+            .query(NitterQuery::User {
+                user: "user".into(),
+            })
+            .try_build()
+            .unwrap();
+
+        // A search that's still held keeps the shutdown from ending in time
+        let search = scraper.search().await;
+        assert!(!scraper.shutdown(Duration::from_secs(1)).await);
+        drop(search);
+        assert!(scraper.shutdown(Duration::from_secs(1)).await);
+
+        // Later searches end right away
+        assert!(scraper.fetch_all().await.unwrap().is_empty());
+        assert_eq!(scraper.finish_reason(), Some(FinishReason::Cancelled));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_shared_rate_limit() {
+        let fetch = FakeInstance(TIMELINE_PAGE.to_owned());
+        let rate_limit = Arc::new(RateLimiter::per_minute(30));
+        let scraper = |user: &str| {
+            NitterScraper::builder()
+                .client(HttpClient::Custom(&fetch))
+                .instance("https://nitter.net")
+                .query(NitterQuery::User { user: user.into() })
+                .shared_rate_limit(rate_limit.clone())
+                .try_build()
+                .unwrap()
+        };
+        let (a, b) = (scraper("a"), scraper("b"));
+        let start = tokio::time::Instant::now();
+        a.fetch_all().await.unwrap();
+        b.fetch_all().await.unwrap();
+        a.fetch_all().await.unwrap();
+        assert_eq!(start.elapsed(), Duration::from_secs(4));
+    }
+
+    #[tokio::test]
+    async fn test_max_response_size() {
+        let fetch = FakeInstance(TIMELINE_PAGE.to_owned());
+        let scraper = |max_response_size| {
+            NitterScraper::builder()
+                .client(HttpClient::Custom(&fetch))
+                .instance("https://nitter.net")
+                .query(NitterQuery::User {
+                    user: "user".into(),
+                })
+                .max_response_size(max_response_size)
+                .try_build()
+                .unwrap()
+        };
+        assert!(matches!(
+            scraper(Some(100)).fetch_all().await,
+            Err(NitterError::ResponseTooLarge(100))
+        ));
+        assert!(scraper(Some(TIMELINE_PAGE.len())).fetch_all().await.is_ok());
+        assert!(scraper(None).fetch_all().await.is_ok());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_page_delay() {
+        let page = TIMELINE_PAGE.replace(
+            "</div></body>",
+            r#"<div class="show-more"><a href="?cursor=abc">Load more</a></div></div></body>"#,
+        );
+        let fetch = FakeInstance(page);
+        let (warnings, _warnings) = tokio::sync::mpsc::unbounded_channel();
+        let requested = std::sync::Mutex::new(vec![]);
+        let scraper = NitterScraper::builder()
+            .client(HttpClient::Custom(&fetch))
+            .instance("https://nitter.net")
+            .query(NitterQuery::User {
+                user: "user".into(),
+            })
+            .page_delay(Duration::from_secs(5))
+            .jitter(Duration::from_secs(2))
+            .on_page(|_, _, _| requested.lock().unwrap().push(Instant::now()))
+            .warnings(warnings)
+            .try_build()
+            .unwrap();
+        let start = Instant::now();
+        scraper.fetch_all().await.unwrap();
+        scraper.fetch_all().await.unwrap();
+        drop(scraper);
+
+        let requested = requested.into_inner().unwrap();
+        assert_eq!(requested.len(), 4);
+        // The first page of each search isn't delayed
+        assert_eq!(requested[0], start);
+        assert_eq!(requested[2], requested[1]);
+        for delay in [requested[1] - requested[0], requested[3] - requested[2]] {
+            assert!(delay >= Duration::from_secs(5) && delay <= Duration::from_secs(7));
+        }
+    }
+
+    /// Responds to every request after a while
+    struct SlowInstance(Duration);
+
+    impl HttpFetch for SlowInstance {
+        fn fetch(&self, request: HttpRequest) -> BoxFuture<'_, Result<HttpResponse, NitterError>> {
+            Box::pin(async move {
+                tokio::time::sleep(self.0).await;
+                FakeInstance(TIMELINE_PAGE.to_owned()).fetch(request).await
+            })
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_page_timeout() {
+        let fetch = SlowInstance(Duration::from_secs(10));
+        let scraper = |page_timeout| {
+            NitterScraper::builder()
+                .client(HttpClient::Custom(&fetch))
+                .instance("https://nitter.net")
+                .query(NitterQuery::User {
+                    user: "user".into(),
+                })
+                .page_timeout(page_timeout)
+                .try_build()
+                .unwrap()
+        };
+        let start = Instant::now();
+        let result = scraper(Duration::from_secs(5)).fetch_all().await;
+        assert!(matches!(result, Err(NitterError::Timeout)));
+        assert_eq!(start.elapsed(), Duration::from_secs(5));
+        assert!(scraper(Duration::from_secs(15)).fetch_all().await.is_ok());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_deadline() {
+        let page = TIMELINE_PAGE.replace(
+            "</div></body>",
+            r#"<div class="show-more"><a href="?cursor=abc">Load more</a></div></div></body>"#,
+        );
+        let fetch = FakeInstance(page);
+        let (warnings, _warnings) = tokio::sync::mpsc::unbounded_channel();
+        let deadline = std::time::Instant::now() + Duration::from_secs(3);
+        let scraper = NitterScraper::builder()
+            .client(HttpClient::Custom(&fetch))
+            .instance("https://nitter.net")
+            .query(NitterQuery::User {
+                user: "user".into(),
+            })
+            .page_delay(Duration::from_secs(5))
+            .deadline(deadline)
+            .warnings(warnings)
+            .try_build()
+            .unwrap();
+        let start = Instant::now();
+        let results: Vec<_> = scraper.search().await.collect().await;
+        // The second page would be requested after the deadline
+        assert_eq!(results.len(), 2);
+        assert!(results[0].is_ok());
+        assert!(matches!(results[1], Err(NitterError::DeadlineExceeded)));
+        assert!(start.elapsed() < Duration::from_secs(4));
+        assert_eq!(scraper.finish_reason(), Some(FinishReason::Error));
+
+        // Watching ends at the deadline
+        let results: Vec<_> = scraper.watch(Duration::from_secs(60)).await.collect().await;
+        assert!(matches!(results[..], [Err(NitterError::DeadlineExceeded)]));
+    }
+
+    #[tokio::test]
+    async fn test_hooks() {
+        let fetch = FakeInstance(TIMELINE_PAGE.to_owned());
+        let pages = std::sync::Mutex::new(vec![]);
+        let tweets = std::sync::Mutex::new(vec![]);
+        let scraper = NitterScraper::builder()
+            .client(HttpClient::Custom(&fetch))
+            .instance("https://nitter.net")
+            .query(NitterQuery::User {
+                user: "user".into(),
+            })
+            .on_page(|page, url, count| pages.lock().unwrap().push((page, url.to_owned(), count)))
+            .on_tweet(|tweet| tweets.lock().unwrap().push(tweet.id))
+            .try_build()
+            .unwrap();
+        scraper.fetch_all().await.unwrap();
+        drop(scraper);
+        assert_eq!(
+            pages.into_inner().unwrap(),
+            [(0, "https://nitter.net/user".to_owned(), 1)]
+        );
+        assert_eq!(tweets.into_inner().unwrap(), [100]);
+    }
+
+    #[test]
+    fn test_proxy_rotation() {
+        let proxies = [FakeInstance(String::new()), FakeInstance(String::new())];
+        let scraper = NitterScraper::builder()
+            .client(HttpClient::Custom(&OFFLINE))
+            .proxies(proxies.iter().map(|proxy| HttpClient::Custom(proxy)))
+            .instance("https://nitter.net")
+            .query(NitterQuery::User {
+                user: "jack".into(),
+            })
+            .try_build()
+            .unwrap();
+        let is_proxy = |client, i| matches!(client, HttpClient::Custom(fetch) if std::ptr::addr_eq(fetch, &proxies[i]));
+        assert!(is_proxy(scraper.next_proxy().unwrap(), 0));
+        assert!(is_proxy(scraper.next_proxy().unwrap(), 1));
+        assert!(is_proxy(scraper.next_proxy().unwrap(), 0));
+    }
+
+    #[test]
+    fn test_user_agent_rotation() {
+        let scraper = NitterScraper::builder()
+            .client(HttpClient::Custom(&OFFLINE))
+            .user_agents(["first", "invalid\n", "second"])
+            .instance("https://nitter.net")
+            .query(NitterQuery::User {
+                user: "jack".into(),
+            })
+            .try_build()
+            .unwrap();
+        let user_agents: Vec<_> = (0..3).map(|_| scraper.next_user_agent().unwrap()).collect();
+        assert_eq!(user_agents, ["first", "second", "first"]);
+    }
+
+    #[test]
+    fn test_instance_headers() {
+        let scraper = NitterScraper::builder()
+            .client(HttpClient::Custom(&OFFLINE))
+            .instance("https://nitter.net")
+            .header(
+                HeaderName::from_static("authorization"),
+                HeaderValue::from_static("Basic dXNlcjpwYXNz"),
+            )
+            .cookie("auth", "token")
+            .query(NitterQuery::User {
+                user: "jack".into(),
+            })
+            .try_build()
+            .unwrap();
+        let headers = scraper.instance_headers();
+        assert_eq!(headers["authorization"], "Basic dXNlcjpwYXNz");
+        assert_eq!(
+            headers[COOKIE],
+            format!("{}; auth=token", PREFERENCES_COOKIE)
+        );
+        assert!(!headers.contains_key(USER_AGENT));
+
+        let scraper = NitterScraper::builder()
+            .client(HttpClient::Custom(&OFFLINE))
+            .instance("https://nitter.net")
+            .cookie("auth;", "token")
+            .query(NitterQuery::User {
+                user: "jack".into(),
+            })
+            .try_build();
+        assert!(matches!(scraper, Err(NitterError::InvalidCookie(_))));
+    }
+
+    #[test]
+    fn test_challenge_provider() {
+        let response = |status, headers: &[(&'static str, &'static str)], body: &str| {
+            let headers = headers
+                .iter()
+                .map(|(name, value)| {
+                    (
+                        HeaderName::from_static(name),
+                        HeaderValue::from_static(value),
+                    )
+                })
+                .collect();
+            HttpResponse::from_parts(status, headers, "https://nitter.net".into(), body.into())
+        };
+        let challenge = r#"<html><head><title>Just a moment...</title></head><body>
+            <script>(function(){window._cf_chl_opt={cvId: '3'};})();</script></body></html>"#;
+        assert_eq!(
+            challenge_provider(&response(StatusCode::FORBIDDEN, &[], challenge)),
+            Some("Cloudflare")
+        );
+        assert_eq!(
+            challenge_provider(&response(
+                StatusCode::FORBIDDEN,
+                &[("cf-mitigated", "challenge")],
+                ""
+            )),
+            Some("Cloudflare")
+        );
+        let anubis = r#"<script id="anubis_challenge" type="application/json">{}</script>"#;
+        assert_eq!(
+            challenge_provider(&response(StatusCode::OK, &[], anubis)),
+            Some("Anubis")
+        );
+        // Timeline pages with Cloudflare's scripts injected aren't challenges
+        let timeline = format!(
+            r#"{}<script src="/cdn-cgi/challenge-platform/h/b/scripts/jsd/main.js"></script>"#,
+            TIMELINE_PAGE
+        );
+        assert_eq!(
+            challenge_provider(&response(StatusCode::OK, &[], &timeline)),
+            None
+        );
+    }
+
+    #[test]
+    fn test_parse_retry_after() {
+        assert_eq!(parse_retry_after("120"), Some(Duration::from_secs(120)));
+        assert_eq!(
+            parse_retry_after("Wed, 21 Oct 2015 07:28:00 GMT"),
+            Some(Duration::ZERO)
+        );
+        let later = OffsetDateTime::now_utc() + Duration::from_secs(3600);
+        let delay = parse_retry_after(&later.format(&Rfc2822).unwrap()).unwrap();
+        assert!(delay > Duration::from_secs(3500) && delay <= Duration::from_secs(3600));
+        assert_eq!(parse_retry_after("soon"), None);
+    }
+
+    #[test]
+    fn test_list_url() {
+        let by_id = NitterQuery::List {
+            list: "1234".into(),
+        };
+        assert_eq!(by_id.url_path(), "/i/lists/1234");
+        let by_slug = NitterQuery::List {
+            list: "jack/friends".into(),
+        };
+        assert_eq!(by_slug.url_path(), "/jack/lists/friends");
+    }
+
+    #[test]
+    fn test_user_with_replies_url() {
+        let query = NitterQuery::UserWithReplies {
+            user: "jack".into(),
+        };
+        assert_eq!(query.url_path(), "/jack/with_replies");
+        assert_eq!(query.encode_get_params(), "");
+    }
+
+    #[test]
+    fn test_user_media_url() {
+        let query = NitterQuery::UserMedia {
+            user: "jack".into(),
+        };
+        assert_eq!(query.url_path(), "/jack/media");
+        assert_eq!(query.encode_get_params(), "");
+    }
+
+    #[test]
+    fn test_query_round_trip() {
+        let queries = [
+            NitterQuery::Search {
+                query: "from:jack since:2020-01-01".into(),
+            },
+            NitterQuery::User {
+                user: "jack".into(),
+            },
+            NitterQuery::UserWithReplies {
+                user: "jack".into(),
+            },
+            NitterQuery::UserMedia {
+                user: "jack".into(),
+            },
+            NitterQuery::UserSearch {
+                user: "jack".into(),
+                query: "a:b c".into(),
+            },
+            NitterQuery::Status { id: 20 },
+            NitterQuery::RepliesTo { tweet: 20 },
+            NitterQuery::QuotesOf { tweet: 20 },
+            NitterQuery::List {
+                list: "jack/friends".into(),
+            },
+            NitterQuery::Users {
+                query: "rust lang".into(),
+            },
+        ];
+        for query in queries {
+            assert_eq!(query.to_string().parse::<NitterQuery>().unwrap(), query);
+        }
+        assert!("user".parse::<NitterQuery>().is_err());
+        assert!("status:abc".parse::<NitterQuery>().is_err());
+    }
+
+    #[test]
+    fn test_retry_delay() {
+        let delays = |seed| {
+            let mut rng = StdRng::seed_from_u64(seed);
+            (1..=10)
+                .map(|i| retry_delay(i, &mut rng))
+                .collect::<Vec<_>>()
+        };
+        let first = delays(1);
+        for (i, delay) in (1..=10).zip(&first) {
+            let base = Duration::from_secs(1 << std::cmp::min(i, 8));
+            assert!(*delay >= base && *delay < base.mul_f64(1.5));
+        }
+        // The same seed gives the same delays
+        assert_eq!(first, delays(1));
+        assert_ne!(first, delays(2));
+    }
+
+    /// Serves a timeline with a single tweet whose status page was deleted
+    struct DeletedTweetInstance;
+
+    impl HttpFetch for DeletedTweetInstance {
+        fn fetch(&self, request: HttpRequest) -> BoxFuture<'_, Result<HttpResponse, NitterError>> {
+            let response = match request.url.contains("/status/") {
+                true => HttpResponse::new(404, vec![], request.url, String::new()),
+                false => HttpResponse::new(200, vec![], request.url, TIMELINE_PAGE.to_owned()),
+            };
+            Box::pin(async { response })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_watch_disappeared() {
+        let checks = std::sync::Mutex::new(vec![]);
+        let scraper = NitterScraper::builder()
+            .client(HttpClient::Custom(&DeletedTweetInstance))
+            .instance("https://nitter.net")
+            .query(NitterQuery::User {
+                user: "user".into(),
+            })
+            .min_id(Some(1))
+            .recheck_window(10)
+            .on_check(|check| checks.lock().unwrap().push(check.ok()))
+            .try_build()
+            .unwrap();
+        let items: Vec<_> = scraper
+            .watch_items(Duration::from_millis(10))
+            .await
+            .take(2)
+            .map(Result::unwrap)
+            .collect()
+            .await;
+
+        // The tweet is yielded when it's new, and found deleted at the next check
+        assert!(matches!(&items[0], TimelineItem::Tweet(tweet) if tweet.id == 100));
+        assert!(matches!(&items[1], TimelineItem::Disappeared(tweet) if tweet.id == 100));
+        drop(scraper);
+        assert_eq!(*checks.lock().unwrap(), [Some(1), Some(0)]);
     }
 }
@@ -1,22 +1,196 @@
+use std::collections::HashMap;
+
+use ego_tree::iter::Edge;
 use once_cell::sync::Lazy;
+use quick_xml::events::Event;
+use quick_xml::Reader;
 use regex::Regex;
-use scraper::{ElementRef, Html, Selector};
+use scraper::{ElementRef, Html, Node, Selector};
 use time::format_description::well_known::Rfc2822;
 use time::format_description::FormatItem;
 use time::macros::format_description;
-use time::PrimitiveDateTime;
+use time::{OffsetDateTime, PrimitiveDateTime};
 
+use crate::coverage::FieldCoverage;
 use crate::error::NitterError;
-use crate::nitter_scraper::NitterCursor;
-use crate::tweet::{Stats, Tweet, User, Video};
+use crate::id_time::{snowflake_time_ms, tweet_time};
+use crate::nitter_scraper::{ImageQuality, MediaUrls, NitterCursor};
+use crate::tweet::{
+    Attachment, Profile, ProfileStats, Stats, TimelineItem, Tweet, UnavailableTweet, User,
+    UserSummary, VerifiedType, Video,
+};
 
-pub fn parse_nitter_html(html: String) -> Result<(Vec<Tweet>, NitterCursor), NitterError> {
-    static TWEET_SELECTOR: Lazy<Selector> = Lazy::new(|| {
-        Selector::parse(".timeline-item:not(.show-more):not(.unavailable):not(.threadunavailable)")
-            .unwrap()
-    });
+#[derive(Debug, Default)]
+pub struct ParseOptions {
+    pub include_profile: bool,
+    pub include_raw_html: bool,
+    pub include_replies: bool,
+    pub image_quality: ImageQuality,
+    pub media_urls: MediaUrls,
+    /// Base URL that proxied media URLs are relative to
+    pub instance: String,
+    pub selectors: SelectorProfile,
+}
+
+/// Overrides for the CSS selectors tweets are parsed with, for Nitter forks that rename classes
+///
+/// Selectors that aren't overridden are chosen by the markup detected on each page.
+#[derive(Debug, Clone, Default)]
+pub struct SelectorProfile {
+    item: Option<Selector>,
+    body: Option<Selector>,
+    date: Option<Selector>,
+    stats: Option<Selector>,
+}
+
+impl SelectorProfile {
+    /// Timeline items containing a tweet each, e.g. `.timeline-item`
+    pub fn item(mut self, selector: &str) -> Result<Self, NitterError> {
+        self.item = Some(parse_selector(selector)?);
+        Ok(self)
+    }
+
+    /// Tweet text within an item, e.g. `.tweet-content`
+    pub fn body(mut self, selector: &str) -> Result<Self, NitterError> {
+        self.body = Some(parse_selector(selector)?);
+        Ok(self)
+    }
+
+    /// Link to the tweet within an item, with the full date as its title, e.g. `.tweet-date > a`
+    pub fn date(mut self, selector: &str) -> Result<Self, NitterError> {
+        self.date = Some(parse_selector(selector)?);
+        Ok(self)
+    }
+
+    /// Stat counts within an item, each containing an icon and a number, e.g.
+    /// `.tweet-stat > .icon-container`
+    pub fn stats(mut self, selector: &str) -> Result<Self, NitterError> {
+        self.stats = Some(parse_selector(selector)?);
+        Ok(self)
+    }
+}
+
+fn parse_selector(selector: &str) -> Result<Selector, NitterError> {
+    Selector::parse(selector)
+        .map_err(|e| NitterError::InvalidSelector(format!("{:?}: {}", selector, e)))
+}
+
+/// Selectors used for a page, from the user's overrides or else the page's layout
+struct Selectors<'a> {
+    item: &'a Selector,
+    main_tweet: &'a Selector,
+    body: &'a Selector,
+    date: &'a Selector,
+    stats: &'a Selector,
+}
+
+impl<'a> Selectors<'a> {
+    fn new(layout: Layout, profile: &'a SelectorProfile) -> Self {
+        static STATS_SELECTOR: Lazy<Selector> =
+            Lazy::new(|| Selector::parse(".tweet-stat > .icon-container").unwrap());
+
+        Self {
+            item: profile.item.as_ref().unwrap_or(layout.item_selector()),
+            main_tweet: layout.main_tweet_selector(),
+            body: profile.body.as_ref().unwrap_or(layout.body_selector()),
+            date: profile.date.as_ref().unwrap_or(layout.date_selector()),
+            stats: profile.stats.as_ref().unwrap_or(&STATS_SELECTOR),
+        }
+    }
+}
+
+/// Markup generations of Nitter, which rename some of the classes tweets are parsed from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Layout {
+    /// Markup used by current instances
+    Current,
+    /// Markup used by older instances and the forks based on them, e.g. `.timeline-tweet`
+    /// items and `.status-content` bodies
+    Legacy,
+}
+
+impl Layout {
+    /// Detect the layout from markup that is only present in one of them
+    fn detect(document: &Html) -> Self {
+        static LEGACY_SELECTOR: Lazy<Selector> =
+            Lazy::new(|| Selector::parse(".timeline-tweet, .status-content").unwrap());
+        static CURRENT_SELECTOR: Lazy<Selector> =
+            Lazy::new(|| Selector::parse(".timeline-item, .tweet-content").unwrap());
+
+        let root = document.root_element();
+        if root.select(&CURRENT_SELECTOR).next().is_none()
+            && root.select(&LEGACY_SELECTOR).next().is_some()
+        {
+            Self::Legacy
+        } else {
+            Self::Current
+        }
+    }
+
+    fn item_selector(&self) -> &'static Lazy<Selector> {
+        static CURRENT_SELECTOR: Lazy<Selector> = Lazy::new(|| {
+            Selector::parse(".timeline-item:not(.show-more):not(.more-replies-thread)").unwrap()
+        });
+        static LEGACY_SELECTOR: Lazy<Selector> =
+            Lazy::new(|| Selector::parse(".timeline-tweet:not(.show-more)").unwrap());
+        match self {
+            Self::Current => &CURRENT_SELECTOR,
+            Self::Legacy => &LEGACY_SELECTOR,
+        }
+    }
+
+    fn main_tweet_selector(&self) -> &'static Lazy<Selector> {
+        static CURRENT_SELECTOR: Lazy<Selector> =
+            Lazy::new(|| Selector::parse("div.main-tweet > .timeline-item").unwrap());
+        static LEGACY_SELECTOR: Lazy<Selector> =
+            Lazy::new(|| Selector::parse("div.main-tweet > .timeline-tweet").unwrap());
+        match self {
+            Self::Current => &CURRENT_SELECTOR,
+            Self::Legacy => &LEGACY_SELECTOR,
+        }
+    }
+
+    fn body_selector(&self) -> &'static Lazy<Selector> {
+        static CURRENT_SELECTOR: Lazy<Selector> =
+            Lazy::new(|| Selector::parse(".tweet-content").unwrap());
+        static LEGACY_SELECTOR: Lazy<Selector> =
+            Lazy::new(|| Selector::parse(".status-content").unwrap());
+        match self {
+            Self::Current => &CURRENT_SELECTOR,
+            Self::Legacy => &LEGACY_SELECTOR,
+        }
+    }
+
+    /// Selects the link to the tweet, which has the full date as its title
+    fn date_selector(&self) -> &'static Lazy<Selector> {
+        static CURRENT_SELECTOR: Lazy<Selector> =
+            Lazy::new(|| Selector::parse(".tweet-date > a").unwrap());
+        static LEGACY_SELECTOR: Lazy<Selector> =
+            Lazy::new(|| Selector::parse(".tweet-date > a, .status-date > a").unwrap());
+        match self {
+            Self::Current => &CURRENT_SELECTOR,
+            Self::Legacy => &LEGACY_SELECTOR,
+        }
+    }
+}
 
+pub fn parse_nitter_html(
+    html: String,
+    options: &ParseOptions,
+    coverage: &mut FieldCoverage,
+) -> Result<(Vec<TimelineItem>, NitterCursor), NitterError> {
+    let (items, cursor) = parse_nitter_html_items(html, options, coverage)?;
+    Ok((items.into_iter().collect::<Result<_, _>>()?, cursor))
+}
+
+/// Like [`parse_nitter_html`], but an item that can't be parsed doesn't fail the whole page
+pub fn parse_nitter_html_items(
+    html: String,
+    options: &ParseOptions,
+    coverage: &mut FieldCoverage,
+) -> Result<(Vec<Result<TimelineItem, NitterError>>, NitterCursor), NitterError> {
     let mut document = Html::parse_document(&html);
+    let selectors = Selectors::new(Layout::detect(&document), &options.selectors);
 
     // Check if user is protected
     if parse_protected(document.root_element()) {
@@ -33,6 +207,20 @@ pub fn parse_nitter_html(html: String) -> Result<(Vec<Tweet>, NitterCursor), Nit
         return Err(NitterError::NotFound);
     }
 
+    if parse_rate_limited(document.root_element()) {
+        return Err(rate_limited_page());
+    }
+
+    // Save original html before quotes are removed
+    let mut raw_html: HashMap<_, _> = if options.include_raw_html {
+        document
+            .select(selectors.item)
+            .map(|element| (element.id(), element.html()))
+            .collect()
+    } else {
+        HashMap::new()
+    };
+
     // Remove all quotes
     static QUOTE_SELECTOR: Lazy<Selector> =
         Lazy::new(|| Selector::parse(".quote > *:not(.quote-link)").unwrap());
@@ -44,19 +232,76 @@ pub fn parse_nitter_html(html: String) -> Result<(Vec<Tweet>, NitterCursor), Nit
         document.tree.get_mut(id).unwrap().detach();
     }
 
-    let mut tweets = vec![];
-    for element in document.select(&TWEET_SELECTOR) {
-        tweets.push(parse_tweet(element)?);
+    let mut items = document
+        .select(selectors.item)
+        .enumerate()
+        .map(|(position, element)| {
+            let raw_html = raw_html.remove(&element.id());
+            parse_item(element, position, raw_html, &selectors, options, coverage)
+        })
+        .collect::<Vec<_>>();
+
+    // Only the profile owner's retweets are on their timeline
+    if let Some(screen_name) = parse_profile_screen_name(document.root_element()) {
+        for item in items.iter_mut() {
+            if let Ok(TimelineItem::Tweet(tweet)) = item {
+                if tweet.retweet {
+                    tweet.retweeted_by = Some(screen_name.clone());
+                }
+            }
+        }
+    }
+
+    // Attach profile counts to the profile owner's tweets
+    if options.include_profile {
+        if let Some((screen_name, profile_stats)) = parse_profile_stats(document.root_element()) {
+            for item in items.iter_mut() {
+                if let Ok(TimelineItem::Tweet(tweet)) = item {
+                    if tweet.user.screen_name.eq_ignore_ascii_case(&screen_name) {
+                        tweet.author_profile = Some(profile_stats.clone());
+                    }
+                }
+            }
+        }
     }
 
     // Parse pagination cursor
     let cursor = parse_cursor(document.root_element());
 
-    Ok((tweets, cursor))
+    Ok((items, cursor))
 }
 
-pub fn parse_nitter_single(html: String) -> Result<(Tweet, NitterCursor), NitterError> {
+/// Parse a status page, which contains the main tweet and optionally its replies
+pub fn parse_nitter_single(
+    html: String,
+    options: &ParseOptions,
+    coverage: &mut FieldCoverage,
+) -> Result<(Vec<TimelineItem>, NitterCursor), NitterError> {
+    static REPLY_SELECTOR: Lazy<Selector> = Lazy::new(|| {
+        Selector::parse(
+            ".after-tweet > .timeline-item, \
+             .replies .timeline-item:not(.show-more):not(.more-replies):not(.more-replies-thread)",
+        )
+        .unwrap()
+    });
+
     let mut document = Html::parse_document(&html);
+    let selectors = Selectors::new(Layout::detect(&document), &options.selectors);
+
+    if parse_rate_limited(document.root_element()) {
+        return Err(rate_limited_page());
+    }
+
+    // Save original html before quotes are removed
+    let mut raw_html: HashMap<_, _> = if options.include_raw_html {
+        let main_tweet = main_tweet(document.root_element(), &selectors)?;
+        std::iter::once(main_tweet)
+            .chain(document.select(&REPLY_SELECTOR))
+            .map(|element| (element.id(), element.html()))
+            .collect()
+    } else {
+        HashMap::new()
+    };
 
     // Remove all quotes
     static QUOTE_SELECTOR: Lazy<Selector> =
@@ -69,33 +314,116 @@ pub fn parse_nitter_single(html: String) -> Result<(Tweet, NitterCursor), Nitter
         document.tree.get_mut(id).unwrap().detach();
     }
 
-    let main_tweet = main_tweet(document.root_element());
+    let main_tweet = main_tweet(document.root_element(), &selectors)?;
+    let mut tweet = parse_tweet(main_tweet, &selectors, options)?;
+    record_coverage(main_tweet, &selectors, &tweet, coverage);
+    tweet.raw_html = raw_html.remove(&main_tweet.id());
+    let mut items = vec![TimelineItem::Tweet(tweet)];
+
+    if !options.include_replies {
+        return Ok((items, NitterCursor::End));
+    }
 
-    Ok((parse_tweet(main_tweet)?, NitterCursor::End))
+    // Replies are positioned after the main tweet
+    for (position, element) in document.select(&REPLY_SELECTOR).enumerate() {
+        let raw_html = raw_html.remove(&element.id());
+        let item = parse_item(
+            element,
+            position + 1,
+            raw_html,
+            &selectors,
+            options,
+            coverage,
+        )?;
+        items.push(item);
+    }
+    let cursor = parse_cursor(document.root_element());
+
+    Ok((items, cursor))
+}
+
+fn parse_item(
+    element: ElementRef,
+    position: usize,
+    raw_html: Option<String>,
+    selectors: &Selectors,
+    options: &ParseOptions,
+    coverage: &mut FieldCoverage,
+) -> Result<TimelineItem, NitterError> {
+    let position = Some(position as u32);
+    if let Some(reason) = parse_unavailable(element) {
+        return Ok(TimelineItem::Unavailable(UnavailableTweet {
+            page: None,
+            position,
+            reason,
+        }));
+    }
+    if let Some(mut user) = parse_user_result(element, selectors)? {
+        user.position = position;
+        return Ok(TimelineItem::User(user));
+    }
+    let mut tweet = parse_tweet(element, selectors, options)?;
+    record_coverage(element, selectors, &tweet, coverage);
+    tweet.position = position;
+    tweet.raw_html = raw_html;
+    Ok(TimelineItem::Tweet(tweet))
 }
 
-fn parse_tweet(element: ElementRef) -> Result<Tweet, NitterError> {
+fn parse_tweet(
+    element: ElementRef,
+    selectors: &Selectors,
+    options: &ParseOptions,
+) -> Result<Tweet, NitterError> {
     // Parse individual tweets
     let full_name = parse_tweet_full_name(element)?;
-    let screen_name = parse_tweet_screen_name(element)?;
-    let id_str = parse_tweet_id_str(element)?;
+    let screen_name = parse_tweet_screen_name(element, selectors)?;
+    let verified_type = parse_tweet_verified(element);
+    let id_str = parse_tweet_id_str(element, selectors)?;
     let id = id_str
         .parse()
         .map_err(|_| NitterError::Parse(format!("invalid id {:?}", id_str)))?;
-    let full_text = parse_tweet_body(element)?;
-    let links = parse_links(element)?;
-    let images = parse_tweet_images(element);
-    let video = parse_video(element);
-    let (created_at, created_at_ts) = parse_tweet_time(element)?;
+    let full_text = parse_tweet_body(element, selectors)?;
+    let full_text_markdown = parse_tweet_body_markdown(element, selectors)?;
+    let links = parse_links(element, selectors)?;
+    let images = parse_tweet_images(element, options, options.media_urls);
+    let video = parse_video(element, options);
+    let video_thumbnail = parse_video_thumbnail(element, options, options.media_urls);
+    let attachments = parse_attachments(element, options);
+    let (proxied_images, proxied_video_thumbnail) = match options.media_urls {
+        MediaUrls::Both => (
+            Some(parse_tweet_images(element, options, MediaUrls::Proxied)),
+            parse_video_thumbnail(element, options, MediaUrls::Proxied),
+        ),
+        _ => (None, None),
+    };
+    let (created_at, created_at_ts, created_at_ms) = match snowflake_time_ms(id) {
+        // The displayed time is only minute-granular, prefer the time encoded in the ID
+        Some(created_at_ms) => {
+            let time = tweet_time(id);
+            (
+                time.format(&Rfc2822).unwrap(),
+                time.unix_timestamp(),
+                created_at_ms,
+            )
+        }
+        None => {
+            let (created_at, created_at_ts) = parse_tweet_time(element, selectors)?;
+            (created_at, created_at_ts, created_at_ts * 1000)
+        }
+    };
     let retweet = parse_tweet_retweet(element);
     let reply = parse_tweet_reply(element);
     let quote = parse_tweet_quote(element);
+    let quoted_id = parse_tweet_quoted_id(element);
     let pinned = parse_tweet_pinned(element);
+    let possibly_sensitive = parse_tweet_sensitive(element);
+    let (is_thread_start, conversation_id) = parse_tweet_thread(element, selectors);
     let stats = Stats {
-        comment: parse_tweet_stat(element, TweetStat::Comment),
-        retweet: parse_tweet_stat(element, TweetStat::Retweet),
-        quote: parse_tweet_stat(element, TweetStat::Quote),
-        heart: parse_tweet_stat(element, TweetStat::Heart),
+        comment: parse_tweet_stat(element, selectors, TweetStat::Comment),
+        retweet: parse_tweet_stat(element, selectors, TweetStat::Retweet),
+        quote: parse_tweet_stat(element, selectors, TweetStat::Quote),
+        heart: parse_tweet_stat(element, selectors, TweetStat::Heart),
+        views: find_tweet_stat(element, selectors, TweetStat::Views),
     };
 
     Ok(Tweet {
@@ -103,26 +431,486 @@ fn parse_tweet(element: ElementRef) -> Result<Tweet, NitterError> {
         id_str,
         created_at,
         created_at_ts,
+        created_at_ms,
         full_text,
+        full_text_markdown,
         links,
+        expanded_links: None,
         images,
         video,
+        video_thumbnail,
+        proxied_images,
+        proxied_video_thumbnail,
+        attachments,
         retweet,
+        // Filled in from the page, the header only shows the retweeter's full name
+        retweeted_by: None,
         reply,
         quote,
+        quoted_id,
+        quoted_tweet: None,
         pinned,
+        possibly_sensitive,
+        is_thread_start,
+        conversation_id,
         user: User {
             screen_name,
             full_name,
+            verified: verified_type.is_some(),
+            verified_type,
         },
         stats,
+        author_profile: None,
+        page: None,
+        position: None,
+        raw_html: None,
     })
 }
 
-fn main_tweet(element: ElementRef) -> ElementRef {
-    static MAIN_TWEET_SELECTOR: Lazy<Selector> =
-        Lazy::new(|| Selector::parse("div.main-tweet > .timeline-item").unwrap());
-    element.select(&MAIN_TWEET_SELECTOR).next().unwrap()
+/// Parse the profile header of a user's timeline page
+pub fn parse_nitter_profile(html: &str) -> Result<Profile, NitterError> {
+    static FULLNAME_SELECTOR: Lazy<Selector> =
+        Lazy::new(|| Selector::parse(".profile-card .profile-card-fullname").unwrap());
+    static VERIFIED_SELECTOR: Lazy<Selector> = Lazy::new(|| {
+        Selector::parse(".profile-card .profile-card-fullname .verified-icon").unwrap()
+    });
+    static BIO_SELECTOR: Lazy<Selector> =
+        Lazy::new(|| Selector::parse(".profile-card .profile-bio").unwrap());
+    static LOCATION_SELECTOR: Lazy<Selector> =
+        Lazy::new(|| Selector::parse(".profile-card .profile-location").unwrap());
+    static WEBSITE_SELECTOR: Lazy<Selector> =
+        Lazy::new(|| Selector::parse(".profile-card .profile-website a").unwrap());
+    static JOINDATE_SELECTOR: Lazy<Selector> =
+        Lazy::new(|| Selector::parse(".profile-card .profile-joindate > span").unwrap());
+    static JOINDATE_FORMAT_DESCRIPTION: &[FormatItem<'_>] = format_description!(
+        "[hour repr:12 padding:none]:[minute] [period] - [day padding:none] [month repr:short] [year]"
+    );
+
+    let document = Html::parse_document(html);
+    let root = document.root_element();
+    if parse_suspended(root) {
+        return Err(NitterError::SuspendedAccount);
+    }
+    if parse_not_found(root) {
+        return Err(NitterError::NotFound);
+    }
+    if parse_rate_limited(root) {
+        return Err(rate_limited_page());
+    }
+
+    let (screen_name, stats) =
+        parse_profile_stats(root).ok_or_else(|| NitterError::Parse("missing profile".into()))?;
+    let full_name = root
+        .select(&FULLNAME_SELECTOR)
+        .next()
+        .map(|e| {
+            e.value()
+                .attr("title")
+                .map(|title| title.to_owned())
+                .unwrap_or_else(|| e.text().collect::<String>().trim().to_owned())
+        })
+        .ok_or_else(|| NitterError::Parse("missing full_name".into()))?;
+    let verified_type = root.select(&VERIFIED_SELECTOR).next().map(verified_type);
+    let text = |selector: &Selector| {
+        root.select(selector)
+            .next()
+            .map(|e| e.text().collect::<String>().trim().to_owned())
+            .filter(|text| !text.is_empty())
+    };
+    let website = root
+        .select(&WEBSITE_SELECTOR)
+        .next()
+        .and_then(|e| e.value().attr("href"))
+        .map(|href| href.to_owned());
+    let joined_at = root
+        .select(&JOINDATE_SELECTOR)
+        .next()
+        .and_then(|e| e.value().attr("title"))
+        .and_then(|title| PrimitiveDateTime::parse(title, JOINDATE_FORMAT_DESCRIPTION).ok())
+        .map(|time| time.assume_utc());
+
+    Ok(Profile {
+        screen_name,
+        full_name,
+        verified_type,
+        bio: text(&BIO_SELECTOR),
+        location: text(&LOCATION_SELECTOR),
+        website,
+        joined_at: joined_at.map(|t| t.format(&Rfc2822).unwrap()),
+        joined_at_ts: joined_at.map(|t| t.unix_timestamp()),
+        protected: parse_protected(root),
+        stats,
+    })
+}
+
+/// Parse a timeline item of a user search, if it is one
+fn parse_user_result(
+    element: ElementRef,
+    selectors: &Selectors,
+) -> Result<Option<UserSummary>, NitterError> {
+    static PROFILE_RESULT_SELECTOR: Lazy<Selector> =
+        Lazy::new(|| Selector::parse(".profile-result").unwrap());
+    static USERNAME_SELECTOR: Lazy<Selector> = Lazy::new(|| Selector::parse("a.username").unwrap());
+    static FOLLOWERS_SELECTOR: Lazy<Selector> =
+        Lazy::new(|| Selector::parse(".followers .profile-stat-num").unwrap());
+
+    if element.select(&PROFILE_RESULT_SELECTOR).next().is_none() {
+        return Ok(None);
+    }
+
+    let screen_name = element
+        .select(&USERNAME_SELECTOR)
+        .next()
+        .and_then(|e| e.value().attr("href"))
+        .map(|href| href.trim_start_matches('/').to_owned())
+        .ok_or_else(|| NitterError::Parse("missing screen_name".into()))?;
+    let bio = parse_tweet_body(element, selectors)
+        .ok()
+        .filter(|bio| !bio.is_empty());
+    let followers = element
+        .select(&FOLLOWERS_SELECTOR)
+        .next()
+        .and_then(|e| e.text().next())
+        .and_then(parse_stat_count);
+
+    Ok(Some(UserSummary {
+        screen_name,
+        full_name: parse_tweet_full_name(element)?,
+        verified_type: parse_tweet_verified(element),
+        bio,
+        followers,
+        page: None,
+        position: None,
+    }))
+}
+
+/// Returns the reason a timeline item is unavailable, if it is
+fn parse_unavailable(element: ElementRef) -> Option<String> {
+    if !element
+        .value()
+        .classes()
+        .any(|c| c == "unavailable" || c == "threadunavailable")
+    {
+        return None;
+    }
+
+    let text: String = element.text().collect();
+    let reason = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    Some(reason)
+}
+
+/// Parse an RSS feed, which only contains the text, images, and author of each tweet
+///
+/// An item that can't be parsed is returned as its error, the feed only fails as a whole.
+pub fn parse_nitter_rss(
+    xml: &str,
+    options: &ParseOptions,
+) -> Result<Vec<Result<TimelineItem, NitterError>>, NitterError> {
+    let mut reader = Reader::from_str(xml);
+    reader.trim_text(true);
+
+    // Text of the elements of each <item>, keyed by tag name
+    let mut channel_title = None;
+    let mut rss_items: Vec<HashMap<String, String>> = Vec::new();
+    let mut path: Vec<String> = Vec::new();
+    loop {
+        let event = reader
+            .read_event()
+            .map_err(|e| NitterError::Parse(format!("invalid RSS: {}", e)))?;
+        let text = match event {
+            Event::Start(e) => {
+                let name = String::from_utf8_lossy(e.name().as_ref()).into_owned();
+                if name == "item" && path.last().is_some_and(|p| p == "channel") {
+                    rss_items.push(HashMap::new());
+                }
+                path.push(name);
+                continue;
+            }
+            Event::End(_) => {
+                path.pop();
+                continue;
+            }
+            Event::Text(e) => e
+                .unescape()
+                .map_err(|e| NitterError::Parse(format!("invalid RSS: {}", e)))?
+                .into_owned(),
+            Event::CData(e) => String::from_utf8_lossy(&e.into_inner()).into_owned(),
+            Event::Eof => break,
+            _ => continue,
+        };
+        match path.iter().map(|p| p.as_str()).collect::<Vec<_>>()[..] {
+            ["rss", "channel", "title"] => channel_title = Some(text),
+            ["rss", "channel", "item", tag] => {
+                if let Some(item) = rss_items.last_mut() {
+                    item.entry(tag.to_owned()).or_default().push_str(&text);
+                }
+            }
+            _ => {}
+        }
+    }
+    if channel_title.is_none() {
+        return Err(NitterError::Parse("missing RSS channel".into()));
+    }
+
+    // Feeds of a user are titled e.g. "Full Name / @user"
+    let channel_user = channel_title.as_deref().and_then(|title| {
+        let (full_name, screen_name) = title.rsplit_once(" / @")?;
+        Some((full_name.to_owned(), screen_name.to_owned()))
+    });
+    let full_name_of = |screen_name: &str| match &channel_user {
+        Some((full_name, channel_screen_name))
+            if channel_screen_name.eq_ignore_ascii_case(screen_name) =>
+        {
+            full_name.clone()
+        }
+        _ => screen_name.to_owned(),
+    };
+
+    Ok(rss_items
+        .iter()
+        .enumerate()
+        .map(|(position, item)| {
+            let mut tweet = parse_rss_item(item, options, full_name_of)?;
+            tweet.position = Some(position as u32);
+            Ok(TimelineItem::Tweet(tweet))
+        })
+        .collect())
+}
+
+fn parse_rss_item(
+    item: &HashMap<String, String>,
+    options: &ParseOptions,
+    full_name_of: impl Fn(&str) -> String,
+) -> Result<Tweet, NitterError> {
+    static IMAGES_SELECTOR: Lazy<Selector> = Lazy::new(|| Selector::parse("img").unwrap());
+    static LINK_SELECTOR: Lazy<Selector> = Lazy::new(|| Selector::parse("a").unwrap());
+
+    let field = |tag: &str| item.get(tag).map(|s| s.as_str()).unwrap_or_default();
+
+    // The link points to the original tweet, even for retweets
+    let link = item
+        .get("link")
+        .or_else(|| item.get("guid"))
+        .ok_or_else(|| NitterError::Parse("missing RSS link".into()))?;
+    let path = link.split_once("://").map_or(link.as_str(), |(_, rest)| {
+        rest.find('/').map_or("", |i| &rest[i..])
+    });
+    let caps = TWEET_LINK_RE
+        .captures(path)
+        .ok_or_else(|| NitterError::Parse(format!("invalid RSS link {:?}", link)))?;
+    let screen_name = caps.name("screen_name").unwrap().as_str().to_owned();
+    let id_str = caps.name("id").unwrap().as_str().to_owned();
+    let id = id_str
+        .parse()
+        .map_err(|_| NitterError::Parse(format!("invalid id {:?}", id_str)))?;
+
+    let description = Html::parse_fragment(field("description"));
+    let body = description.root_element();
+    let full_text = body_text(body);
+    let mut full_text_markdown = String::new();
+    push_markdown(body, &mut full_text_markdown);
+    let links = body
+        .select(&LINK_SELECTOR)
+        .filter_map(|l| l.value().attr("href"))
+        .filter(|l| !l.contains("/pic/"))
+        .map(|l| l.to_owned())
+        .collect();
+
+    // Images are absolute URLs on the instance
+    let pics: Vec<_> = body
+        .select(&IMAGES_SELECTOR)
+        .filter_map(|img| img.value().attr("src"))
+        .filter_map(|src| src.find("/pic/").map(|i| &src[i..]))
+        .collect();
+    let images: Vec<_> = pics
+        .iter()
+        .filter_map(|pic| media_url(pic, options, options.media_urls))
+        .collect();
+    let proxied_images = match options.media_urls {
+        MediaUrls::Both => Some(pics.iter().map(|pic| proxied_url(pic, options)).collect()),
+        _ => None,
+    };
+    let attachments = images
+        .iter()
+        .map(|url| Attachment::Image { url: url.clone() })
+        .collect();
+
+    let (created_at, created_at_ts, created_at_ms) = match snowflake_time_ms(id) {
+        Some(created_at_ms) => {
+            let time = tweet_time(id);
+            (
+                time.format(&Rfc2822).unwrap(),
+                time.unix_timestamp(),
+                created_at_ms,
+            )
+        }
+        None => {
+            let time = OffsetDateTime::parse(field("pubDate"), &Rfc2822)
+                .map_err(|e| NitterError::Parse(format!("invalid RSS date: {}", e)))?;
+            (
+                time.format(&Rfc2822).unwrap(),
+                time.unix_timestamp(),
+                time.unix_timestamp() * 1000,
+            )
+        }
+    };
+
+    // Titles are prefixed with e.g. "RT by @user: " and "R to @user: "
+    let title = field("title");
+    let retweeted_by = title
+        .strip_prefix("RT by @")
+        .and_then(|rest| rest.split_once(':'))
+        .map(|(retweeter, _)| retweeter.to_owned());
+    let reply = title.starts_with("R to @");
+
+    Ok(Tweet {
+        id,
+        id_str,
+        created_at,
+        created_at_ts,
+        created_at_ms,
+        full_text,
+        full_text_markdown,
+        links,
+        expanded_links: None,
+        images,
+        video: None,
+        video_thumbnail: None,
+        proxied_images,
+        proxied_video_thumbnail: None,
+        attachments,
+        retweet: retweeted_by.is_some(),
+        retweeted_by,
+        reply,
+        quote: false,
+        quoted_id: None,
+        quoted_tweet: None,
+        pinned: false,
+        possibly_sensitive: false,
+        is_thread_start: false,
+        conversation_id: None,
+        user: User {
+            full_name: full_name_of(&screen_name),
+            screen_name,
+            verified: false,
+            verified_type: None,
+        },
+        stats: Stats {
+            comment: 0,
+            retweet: 0,
+            quote: 0,
+            heart: 0,
+            views: None,
+        },
+        author_profile: None,
+        page: None,
+        position: None,
+        raw_html: None,
+    })
+}
+
+/// Parse the version from an instance's about page, e.g. "2023.08.24-3f2d2bb"
+pub fn parse_nitter_version(html: &str) -> Option<String> {
+    static PARAGRAPH_SELECTOR: Lazy<Selector> = Lazy::new(|| Selector::parse("p").unwrap());
+    static VERSION_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"^\s*Version\s+(\S+)").unwrap());
+
+    let document = Html::parse_document(html);
+    document.select(&PARAGRAPH_SELECTOR).find_map(|p| {
+        let text: String = p.text().collect();
+        VERSION_RE
+            .captures(&text)
+            .map(|caps| caps.get(1).unwrap().as_str().to_owned())
+    })
+}
+
+/// Record which optional fields were extracted when their markup was present
+fn record_coverage(
+    element: ElementRef,
+    selectors: &Selectors,
+    tweet: &Tweet,
+    coverage: &mut FieldCoverage,
+) {
+    static IMAGE_SELECTOR: Lazy<Selector> =
+        Lazy::new(|| Selector::parse(".attachment.image").unwrap());
+    static VIDEO_SELECTOR: Lazy<Selector> =
+        Lazy::new(|| Selector::parse(".attachment.video-container").unwrap());
+    static ATTACHMENT_SELECTOR: Lazy<Selector> =
+        Lazy::new(|| Selector::parse(".attachment").unwrap());
+    static STATS_SELECTOR: Lazy<Selector> = Lazy::new(|| Selector::parse(".tweet-stats").unwrap());
+
+    let count = |selector: &Selector| element.select(selector).count();
+
+    let images = count(&IMAGE_SELECTOR);
+    if images > 0 {
+        coverage.record("images", tweet.images.len() == images);
+    }
+    if count(&VIDEO_SELECTOR) > 0 {
+        coverage.record("video", tweet.video.is_some());
+        coverage.record("video_thumbnail", tweet.video_thumbnail.is_some());
+    }
+    let attachments = count(&ATTACHMENT_SELECTOR);
+    if attachments > 0 {
+        coverage.record("attachments", tweet.attachments.len() == attachments);
+    }
+    if count(&STATS_SELECTOR) > 0 {
+        let found = [TweetStat::Comment, TweetStat::Retweet, TweetStat::Heart]
+            .into_iter()
+            .all(|stat| find_tweet_stat(element, selectors, stat).is_some());
+        coverage.record("stats", found);
+    }
+}
+
+/// The tweet of a status page, which is missing if it was deleted or the markup changed
+fn main_tweet<'a>(
+    element: ElementRef<'a>,
+    selectors: &Selectors,
+) -> Result<ElementRef<'a>, NitterError> {
+    element
+        .select(selectors.main_tweet)
+        .next()
+        .ok_or_else(|| NitterError::Parse("status page has no main tweet".into()))
+}
+
+/// Screen name of the user whose profile card is on the page
+fn parse_profile_screen_name(element: ElementRef) -> Option<String> {
+    static USERNAME_SELECTOR: Lazy<Selector> =
+        Lazy::new(|| Selector::parse(".profile-card .profile-card-username").unwrap());
+
+    element
+        .select(&USERNAME_SELECTOR)
+        .next()
+        .and_then(|username_element| username_element.value().attr("href"))
+        .map(|href| href.trim_start_matches('/').to_owned())
+}
+
+fn parse_profile_stats(element: ElementRef) -> Option<(String, ProfileStats)> {
+    static STAT_SELECTOR: Lazy<Selector> =
+        Lazy::new(|| Selector::parse(".profile-card .profile-statlist > li").unwrap());
+    static STAT_NUM_SELECTOR: Lazy<Selector> =
+        Lazy::new(|| Selector::parse(".profile-stat-num").unwrap());
+
+    let screen_name = parse_profile_screen_name(element)?;
+
+    let mut profile_stats = ProfileStats::default();
+    for stat_element in element.select(&STAT_SELECTOR) {
+        let num = stat_element
+            .select(&STAT_NUM_SELECTOR)
+            .next()
+            .and_then(|num_element| num_element.text().next())
+            .and_then(parse_stat_count)
+            .unwrap_or(0);
+        for class in stat_element.value().classes() {
+            match class {
+                "posts" => profile_stats.tweets = num,
+                "following" => profile_stats.following = num,
+                "followers" => profile_stats.followers = num,
+                "likes" => profile_stats.likes = num,
+                _ => {}
+            }
+        }
+    }
+
+    Some((screen_name, profile_stats))
 }
 
 fn parse_protected(element: ElementRef) -> bool {
@@ -152,8 +940,23 @@ fn parse_not_found(element: ElementRef) -> bool {
         .eq(&Some(true))
 }
 
-static TWEET_LINK_SELECTOR: Lazy<Selector> =
-    Lazy::new(|| Selector::parse(".tweet-date > a").unwrap());
+/// Error for Nitter's rate limit error page, which is served with a success status
+fn rate_limited_page() -> NitterError {
+    NitterError::RateLimited {
+        status: 200,
+        retry_after: None,
+    }
+}
+
+fn parse_rate_limited(element: ElementRef) -> bool {
+    element
+        .select(&ERROR_SELECTOR)
+        .next()
+        .and_then(|element| element.text().next())
+        .map(|text| text.contains("rate limited"))
+        .eq(&Some(true))
+}
+
 static TWEET_LINK_RE: Lazy<Regex> =
     Lazy::new(|| Regex::new(r"^/(?P<screen_name>\w+)/status/(?P<id>\d+)").unwrap());
 
@@ -167,9 +970,34 @@ fn parse_tweet_full_name(element: ElementRef) -> Result<String, NitterError> {
         .ok_or_else(|| NitterError::Parse("missing full_name".into()))
 }
 
-fn parse_tweet_screen_name(element: ElementRef) -> Result<String, NitterError> {
+fn parse_tweet_verified(element: ElementRef) -> Option<VerifiedType> {
+    static VERIFIED_SELECTOR: Lazy<Selector> =
+        Lazy::new(|| Selector::parse("a.fullname .verified-icon").unwrap());
+
+    let verified_element = element.select(&VERIFIED_SELECTOR).next()?;
+    Some(verified_type(verified_element))
+}
+
+/// Kind of verification shown by a verified icon
+fn verified_type(verified_element: ElementRef) -> VerifiedType {
+    verified_element
+        .value()
+        .classes()
+        .find_map(|class| match class {
+            "blue" => Some(VerifiedType::Blue),
+            "business" => Some(VerifiedType::Business),
+            "government" => Some(VerifiedType::Government),
+            _ => None,
+        })
+        .unwrap_or(VerifiedType::Legacy)
+}
+
+fn parse_tweet_screen_name(
+    element: ElementRef,
+    selectors: &Selectors,
+) -> Result<String, NitterError> {
     element
-        .select(&TWEET_LINK_SELECTOR)
+        .select(selectors.date)
         .next()
         .and_then(|tweet_link_element| tweet_link_element.value().attr("href"))
         .and_then(|tweet_link| TWEET_LINK_RE.captures(tweet_link))
@@ -178,9 +1006,9 @@ fn parse_tweet_screen_name(element: ElementRef) -> Result<String, NitterError> {
         .ok_or_else(|| NitterError::Parse("missing screen_name".into()))
 }
 
-fn parse_tweet_id_str(element: ElementRef) -> Result<String, NitterError> {
+fn parse_tweet_id_str(element: ElementRef, selectors: &Selectors) -> Result<String, NitterError> {
     element
-        .select(&TWEET_LINK_SELECTOR)
+        .select(selectors.date)
         .next()
         .and_then(|tweet_link_element| tweet_link_element.value().attr("href"))
         .and_then(|tweet_link| TWEET_LINK_RE.captures(tweet_link))
@@ -189,24 +1017,106 @@ fn parse_tweet_id_str(element: ElementRef) -> Result<String, NitterError> {
         .ok_or_else(|| NitterError::Parse("missing id".into()))
 }
 
-static TWEET_BODY_SELECTOR: Lazy<Selector> =
-    Lazy::new(|| Selector::parse(".tweet-content").unwrap());
+fn parse_tweet_body(element: ElementRef, selectors: &Selectors) -> Result<String, NitterError> {
+    let body = element
+        .select(selectors.body)
+        .next()
+        .ok_or_else(|| NitterError::Parse("missing body".into()))?;
+    Ok(body_text(body))
+}
+
+/// Plain text of a tweet body, with emoji restored and blocks separated by line breaks
+fn body_text(body: ElementRef) -> String {
+    let mut full_text = String::new();
+    for edge in body.traverse() {
+        match edge {
+            Edge::Open(node) => match node.value() {
+                Node::Text(text) => full_text.push_str(text),
+                // Nitter replaces emoji with images, restore the original characters
+                Node::Element(e) if e.name() == "img" && e.classes().any(|c| c == "emoji") => {
+                    full_text.push_str(e.attr("alt").unwrap_or_default())
+                }
+                Node::Element(e) if e.name() == "br" => full_text.push('\n'),
+                _ => {}
+            },
+            // Separate paragraphs and other block elements by a line break
+            Edge::Close(node) if node.id() != body.id() => match node.value() {
+                Node::Element(e)
+                    if matches!(e.name(), "p" | "div" | "blockquote" | "li")
+                        && !full_text.is_empty()
+                        && !full_text.ends_with('\n') =>
+                {
+                    full_text.push('\n')
+                }
+                _ => {}
+            },
+            _ => {}
+        }
+    }
+    full_text.trim_end_matches('\n').to_owned()
+}
 
-fn parse_tweet_body(element: ElementRef) -> Result<String, NitterError> {
-    let full_text = element
-        .select(&TWEET_BODY_SELECTOR)
+fn parse_tweet_body_markdown(
+    element: ElementRef,
+    selectors: &Selectors,
+) -> Result<String, NitterError> {
+    let body = element
+        .select(selectors.body)
         .next()
-        .ok_or_else(|| NitterError::Parse("missing body".into()))?
-        .text()
-        .collect();
-    Ok(full_text)
+        .ok_or_else(|| NitterError::Parse("missing body".into()))?;
+
+    let mut markdown = String::new();
+    push_markdown(body, &mut markdown);
+    Ok(markdown)
 }
 
-fn parse_links(element: ElementRef) -> Result<Vec<String>, NitterError> {
+fn push_markdown(element: ElementRef, markdown: &mut String) {
+    for node in element.children() {
+        match node.value() {
+            Node::Text(text) => {
+                for c in text.chars() {
+                    if matches!(c, '\\' | '*' | '_' | '`' | '[' | ']' | '<' | '>' | '#') {
+                        markdown.push('\\');
+                    }
+                    markdown.push(c);
+                }
+            }
+            Node::Element(e) if e.name() == "img" && e.classes().any(|c| c == "emoji") => {
+                markdown.push_str(e.attr("alt").unwrap_or_default())
+            }
+            Node::Element(e) if e.name() == "br" => markdown.push_str("  \n"),
+            Node::Element(e) if e.name() == "a" => {
+                let child = ElementRef::wrap(node).unwrap();
+                let text: String = child.text().collect();
+                let href = e.attr("href").unwrap_or_default();
+                // Mentions and hashtags link to the Nitter instance, point them at Twitter instead
+                let href = if let Some(tag) = href.strip_prefix("/search?q=%23") {
+                    format!("https://twitter.com/hashtag/{}", tag)
+                } else if href.starts_with('/') {
+                    format!("https://twitter.com{}", href)
+                } else {
+                    href.to_owned()
+                };
+                markdown.push('[');
+                push_markdown(child, markdown);
+                if text.is_empty() {
+                    markdown.push_str(&href);
+                }
+                markdown.push_str("](");
+                markdown.push_str(&href);
+                markdown.push(')');
+            }
+            Node::Element(_) => push_markdown(ElementRef::wrap(node).unwrap(), markdown),
+            _ => {}
+        }
+    }
+}
+
+fn parse_links(element: ElementRef, selectors: &Selectors) -> Result<Vec<String>, NitterError> {
     static LINK_SELECTOR: Lazy<Selector> = Lazy::new(|| Selector::parse("a").unwrap());
 
     let links = element
-        .select(&TWEET_BODY_SELECTOR)
+        .select(selectors.body)
         .next()
         .ok_or_else(|| NitterError::Parse("missing body".into()))?
         .select(&LINK_SELECTOR)
@@ -217,29 +1127,87 @@ fn parse_links(element: ElementRef) -> Result<Vec<String>, NitterError> {
     Ok(links)
 }
 
-fn parse_tweet_images(element: ElementRef) -> Vec<String> {
+fn parse_tweet_images(
+    element: ElementRef,
+    options: &ParseOptions,
+    media_urls: MediaUrls,
+) -> Vec<String> {
     static IMAGES_SELECTOR: Lazy<Selector> =
         Lazy::new(|| Selector::parse(".attachment.image a.still-image").unwrap());
-    static IMAGE_ID_RE: Lazy<Regex> =
-        Lazy::new(|| Regex::new(r"^/pic/\w+/media%2F(?P<url>[\w\-]+\.\w+)$").unwrap());
 
     let images: Vec<_> = element
         .select(&IMAGES_SELECTOR)
-        .filter_map(|e| {
-            let link = e.value().attr("href")?;
-            match IMAGE_ID_RE.captures(link) {
-                Some(caps) => Some(format!(
-                    "https://pbs.twimg.com/media/{}",
-                    caps.name("url")?.as_str()
-                )),
-                None => None,
-            }
-        })
+        .filter_map(|e| image_url(e, options, media_urls))
         .collect();
     images
 }
 
-fn parse_video(element: ElementRef) -> Option<Video> {
+fn image_url(
+    still_image: ElementRef,
+    options: &ParseOptions,
+    media_urls: MediaUrls,
+) -> Option<String> {
+    static IMAGE_ID_RE: Lazy<Regex> =
+        Lazy::new(|| Regex::new(r"^/pic/\w+/media%2F(?P<url>[\w\-]+\.\w+)$").unwrap());
+
+    let link = still_image.value().attr("href")?;
+    let caps = IMAGE_ID_RE.captures(link)?;
+    if media_urls == MediaUrls::Proxied {
+        return Some(proxied_url(link, options));
+    }
+    let image_quality = options.image_quality;
+    Some(format!(
+        "https://pbs.twimg.com/media/{}{}",
+        caps.name("url")?.as_str(),
+        image_quality.url_suffix()
+    ))
+}
+
+fn parse_attachments(element: ElementRef, options: &ParseOptions) -> Vec<Attachment> {
+    static ATTACHMENT_SELECTOR: Lazy<Selector> =
+        Lazy::new(|| Selector::parse(".attachment").unwrap());
+    static STILL_IMAGE_SELECTOR: Lazy<Selector> =
+        Lazy::new(|| Selector::parse("a.still-image").unwrap());
+    static VIDEO_SELECTOR: Lazy<Selector> = Lazy::new(|| Selector::parse("video").unwrap());
+    static VIDEO_SOURCE_SELECTOR: Lazy<Selector> =
+        Lazy::new(|| Selector::parse("video > source").unwrap());
+
+    element
+        .select(&ATTACHMENT_SELECTOR)
+        .filter_map(|attachment| {
+            let classes: Vec<_> = attachment.value().classes().collect();
+            if classes.contains(&"image") {
+                let still_image = attachment.select(&STILL_IMAGE_SELECTOR).next()?;
+                return Some(Attachment::Image {
+                    url: image_url(still_image, options, options.media_urls)?,
+                });
+            }
+
+            let url = attachment
+                .select(&VIDEO_SOURCE_SELECTOR)
+                .next()
+                .and_then(|source_element| source_element.value().attr("src"))
+                .map(|src| src.to_owned());
+            let poster =
+                parse_video_thumbnail(attachment, options, options.media_urls).or_else(|| {
+                    attachment
+                        .select(&VIDEO_SELECTOR)
+                        .next()
+                        .and_then(|video_element| video_element.value().attr("poster"))
+                        .and_then(|pic| media_url(pic, options, options.media_urls))
+                });
+            if classes.contains(&"media-gif") {
+                Some(Attachment::Gif { url, poster })
+            } else if classes.contains(&"video-container") {
+                Some(Attachment::Video { url, poster })
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+fn parse_video(element: ElementRef, options: &ParseOptions) -> Option<Video> {
     static VIDEO_SELECTOR: Lazy<Selector> = Lazy::new(|| Selector::parse("video").unwrap());
     static VIDEO_SOURCE_SELECTOR: Lazy<Selector> =
         Lazy::new(|| Selector::parse("video > source").unwrap());
@@ -253,30 +1221,66 @@ fn parse_video(element: ElementRef) -> Option<Video> {
         .select(&VIDEO_SELECTOR)
         .next()
         .and_then(|source_element| source_element.value().attr("poster"))
-        .and_then(|poster| urlencoding::decode(poster).ok())
-        .map(|poster| {
-            let poster = poster.to_string();
-            let poster = if let Some(x) = poster.rsplit_once('?') {
-                x.0
-            } else {
-                poster.as_str()
-            };
-            let poster = poster.trim_start_matches("/pic/");
-            format!("https://pbs.twimg.com/{}", poster)
-        })?;
+        .and_then(|pic| media_url(pic, options, options.media_urls))?;
 
     Some(Video { poster, url })
 }
 
-fn parse_tweet_time(element: ElementRef) -> Result<(String, i64), NitterError> {
-    static TWEET_DATE_SELECTOR: Lazy<Selector> =
-        Lazy::new(|| Selector::parse("span.tweet-date a").unwrap());
+fn parse_video_thumbnail(
+    element: ElementRef,
+    options: &ParseOptions,
+    media_urls: MediaUrls,
+) -> Option<String> {
+    static THUMBNAIL_SELECTOR: Lazy<Selector> = Lazy::new(|| {
+        Selector::parse(".attachment.video-container video, .attachment.video-container img")
+            .unwrap()
+    });
+
+    // Videos that can't be played inline are rendered as a still image instead of a <video>
+    element
+        .select(&THUMBNAIL_SELECTOR)
+        .find_map(|e| match e.value().name() {
+            "video" => e.value().attr("poster"),
+            _ => e.value().attr("src"),
+        })
+        .and_then(|pic| media_url(pic, options, media_urls))
+}
+
+/// Convert an instance proxied /pic/ URL to the kind of URL requested
+fn media_url(pic: &str, options: &ParseOptions, media_urls: MediaUrls) -> Option<String> {
+    match media_urls {
+        MediaUrls::Proxied => Some(proxied_url(pic, options)),
+        MediaUrls::Direct | MediaUrls::Both => pic_to_twimg(pic),
+    }
+}
+
+/// Make a /pic/ URL absolute
+fn proxied_url(pic: &str, options: &ParseOptions) -> String {
+    format!("{}{}", options.instance.trim_end_matches('/'), pic)
+}
+
+/// Convert an instance proxied /pic/ URL to a pbs.twimg.com URL
+fn pic_to_twimg(pic: &str) -> Option<String> {
+    let pic = urlencoding::decode(pic).ok()?.to_string();
+    let pic = if let Some(x) = pic.rsplit_once('?') {
+        x.0
+    } else {
+        pic.as_str()
+    };
+    let pic = pic.trim_start_matches("/pic/");
+    Some(format!("https://pbs.twimg.com/{}", pic))
+}
+
+fn parse_tweet_time(
+    element: ElementRef,
+    selectors: &Selectors,
+) -> Result<(String, i64), NitterError> {
     static TIME_FORMAT_DESCRIPTION: &[FormatItem<'_>] = format_description!(
         "[month repr:short] [day padding:none], [year] · [hour repr:12 padding:none]:[minute] [period] UTC"
     );
 
     let time = element
-        .select(&TWEET_DATE_SELECTOR)
+        .select(selectors.date)
         .next()
         .and_then(|tweet_date_element| tweet_date_element.value().attr("title"))
         .and_then(|time_str| PrimitiveDateTime::parse(time_str, TIME_FORMAT_DESCRIPTION).ok())
@@ -302,6 +1306,18 @@ fn parse_tweet_pinned(element: ElementRef) -> bool {
     element.select(&PINNED_SELECTOR).next().is_some()
 }
 
+fn parse_tweet_sensitive(element: ElementRef) -> bool {
+    // Sensitive media is rendered behind a blurred overlay
+    static SENSITIVE_SELECTOR: Lazy<Selector> = Lazy::new(|| {
+        Selector::parse(
+            r#".attachments .sensitive, .attachments.sensitive, [data-sensitive="true"]"#,
+        )
+        .unwrap()
+    });
+
+    element.select(&SENSITIVE_SELECTOR).next().is_some()
+}
+
 fn parse_tweet_reply(element: ElementRef) -> bool {
     static REPLY_SELECTOR: Lazy<Selector> = Lazy::new(|| Selector::parse(".replying-to").unwrap());
 
@@ -314,11 +1330,65 @@ fn parse_tweet_quote(element: ElementRef) -> bool {
     element.select(&QUOTE_SELECTOR).next().is_some()
 }
 
+fn parse_tweet_quoted_id(element: ElementRef) -> Option<u128> {
+    static QUOTE_LINK_SELECTOR: Lazy<Selector> =
+        Lazy::new(|| Selector::parse(".quote .quote-link").unwrap());
+
+    let link = element
+        .select(&QUOTE_LINK_SELECTOR)
+        .next()?
+        .value()
+        .attr("href")?;
+    TWEET_LINK_RE
+        .captures(link)?
+        .name("id")?
+        .as_str()
+        .parse()
+        .ok()
+}
+
+fn parse_tweet_thread(element: ElementRef, selectors: &Selectors) -> (bool, Option<u128>) {
+    static SHOW_THREAD_SELECTOR: Lazy<Selector> =
+        Lazy::new(|| Selector::parse("a.show-thread").unwrap());
+    static SHOW_THREAD_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"/status/(?P<id>\d+)").unwrap());
+    static THREAD_ITEM_SELECTOR: Lazy<Selector> = Lazy::new(|| {
+        Selector::parse(".thread-line > .timeline-item:not(.more-replies-thread)").unwrap()
+    });
+
+    // "Show this thread" links point to the first tweet of the conversation
+    let show_thread_id = element
+        .select(&SHOW_THREAD_SELECTOR)
+        .next()
+        .and_then(|show_thread_element| show_thread_element.value().attr("href"))
+        .and_then(|link| SHOW_THREAD_RE.captures(link))
+        .and_then(|caps| caps.name("id"))
+        .and_then(|cap| cap.as_str().parse().ok());
+
+    // Self-threads are grouped together inside a thread-line container
+    let thread_line = element
+        .parent()
+        .and_then(ElementRef::wrap)
+        .filter(|parent| parent.value().classes().any(|c| c == "thread-line"));
+    let Some(thread_line) = thread_line else {
+        return (false, show_thread_id);
+    };
+    let first = thread_line.select(&THREAD_ITEM_SELECTOR).next();
+    let is_thread_start = first
+        .map(|first| first.id() == element.id())
+        .unwrap_or(false);
+    let first_id = first
+        .and_then(|first| parse_tweet_id_str(first, selectors).ok())
+        .and_then(|id_str| id_str.parse().ok());
+
+    (is_thread_start, show_thread_id.or(first_id))
+}
+
 enum TweetStat {
     Comment,
     Retweet,
     Quote,
     Heart,
+    Views,
 }
 
 impl TweetStat {
@@ -331,28 +1401,51 @@ impl TweetStat {
             Lazy::new(|| Selector::parse(".icon-quote").unwrap());
         static HEART_SELECTOR: Lazy<Selector> =
             Lazy::new(|| Selector::parse(".icon-heart").unwrap());
+        static VIEWS_SELECTOR: Lazy<Selector> =
+            Lazy::new(|| Selector::parse(".icon-views, .icon-play").unwrap());
         match self {
             Self::Comment => &COMMENT_SELECTOR,
             Self::Retweet => &RETWEET_SELECTOR,
             Self::Quote => &QUOTE_SELECTOR,
             Self::Heart => &HEART_SELECTOR,
+            Self::Views => &VIEWS_SELECTOR,
         }
     }
 }
 
-fn parse_tweet_stat(element: ElementRef, stat: TweetStat) -> u64 {
-    static TWEET_STAT_SELECTOR: Lazy<Selector> =
-        Lazy::new(|| Selector::parse(".tweet-stat > .icon-container").unwrap());
-    for e in element.select(&TWEET_STAT_SELECTOR) {
+fn parse_tweet_stat(element: ElementRef, selectors: &Selectors, stat: TweetStat) -> u64 {
+    find_tweet_stat(element, selectors, stat).unwrap_or(0)
+}
+
+/// Find a stat, returning `None` if the instance doesn't display it
+fn find_tweet_stat(element: ElementRef, selectors: &Selectors, stat: TweetStat) -> Option<u64> {
+    for e in element.select(selectors.stats) {
         if e.select(stat.selector()).next().is_some() {
-            return e
-                .text()
-                .next()
-                .and_then(|t| t.trim().replace(',', "").parse().ok())
-                .unwrap_or(0);
+            return Some(e.text().next().and_then(parse_stat_count).unwrap_or(0));
         }
     }
-    0
+    None
+}
+
+/// Parse a displayed stat count, e.g. "1,234", "1.234", "1.2K", or "3,4M"
+fn parse_stat_count(text: &str) -> Option<u64> {
+    let text = text.trim();
+    let (number, multiplier) = match text.chars().last()? {
+        'K' | 'k' => (&text[..text.len() - 1], 1_000),
+        'M' | 'm' => (&text[..text.len() - 1], 1_000_000),
+        'B' | 'b' => (&text[..text.len() - 1], 1_000_000_000),
+        _ => (text, 1),
+    };
+    let number: String = number.chars().filter(|c| !c.is_whitespace()).collect();
+
+    if multiplier == 1 {
+        // Without a suffix, separators can only be thousands separators
+        number.replace([',', '.', '\''], "").parse().ok()
+    } else {
+        // With a suffix, a separator is the decimal point
+        let number: f64 = number.replace(',', ".").parse().ok()?;
+        Some((number * multiplier as f64).round() as u64)
+    }
 }
 
 fn parse_cursor(element: ElementRef) -> NitterCursor {
@@ -369,3 +1462,628 @@ fn parse_cursor(element: ElementRef) -> NitterCursor {
         None => NitterCursor::End,
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // The pages in these tests are synthetic, with only the markup the parser reads, not captured
+    // from an instance
+
+    fn timeline_item(id: u128, extra: &str) -> String {
+        format!(
+            r#"<div class="timeline-item">
+                <a class="fullname" href="/user" title="Full Name">Full Name</a>
+                <span class="tweet-date"><a href="/user/status/{id}#m" title="Jan 1, 2023 · 1:00 PM UTC">1 Jan</a></span>
+                <div class="tweet-content media-body">text</div>
+                {extra}
+            </div>"#
+        )
+    }
+
+    fn parse_tweets(html: String, options: &ParseOptions) -> Vec<Tweet> {
+        let (items, _) = parse_nitter_html(html, options, &mut FieldCoverage::default()).unwrap();
+        items
+            .into_iter()
+            .filter_map(|item| match item {
+                TimelineItem::Tweet(tweet) => Some(tweet),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn timeline(items: &str) -> String {
+        format!(r#"<html><body><div class="timeline">{items}</div></body></html>"#)
+    }
+
+    #[test]
+    fn test_thread() {
+        let html = timeline(&format!(
+            r#"<div class="thread-line">{}{}</div>{}"#,
+            timeline_item(100, ""),
+            timeline_item(101, ""),
+            timeline_item(
+                200,
+                r#"<a class="show-thread" href="/i/status/150">Show this thread</a>"#
+            ),
+        ));
+        let tweets = parse_tweets(html, &ParseOptions::default());
+        assert_eq!(tweets.len(), 3);
+        assert!(tweets[0].is_thread_start);
+        assert_eq!(tweets[0].conversation_id, Some(100));
+        assert!(!tweets[1].is_thread_start);
+        assert_eq!(tweets[1].conversation_id, Some(100));
+        assert!(!tweets[2].is_thread_start);
+        assert_eq!(tweets[2].conversation_id, Some(150));
+    }
+
+    #[test]
+    fn test_emoji() {
+        let html = timeline(&timeline_item(100, "")).replace(
+            ">text<",
+            r#">hi <img class="emoji" src="/emoji/1f600.svg" alt="😀"> there<"#,
+        );
+        let tweets = parse_tweets(html, &ParseOptions::default());
+        assert_eq!(tweets[0].full_text, "hi 😀 there");
+    }
+
+    #[test]
+    fn test_profile_stats() {
+        let profile = r#"<div class="profile-card">
+                <a class="profile-card-username" href="/User">@User</a>
+                <ul class="profile-statlist">
+                    <li class="posts"><span class="profile-stat-num">1,234</span></li>
+                    <li class="following"><span class="profile-stat-num">56</span></li>
+                    <li class="followers"><span class="profile-stat-num">7,890</span></li>
+                    <li class="likes"><span class="profile-stat-num">12</span></li>
+                </ul>
+            </div>"#;
+        let html = timeline(&format!("{}{}", profile, timeline_item(100, "")));
+        let options = ParseOptions {
+            include_profile: true,
+            ..Default::default()
+        };
+        let tweets = parse_tweets(html, &options);
+        let profile_stats = tweets[0].author_profile.as_ref().unwrap();
+        assert_eq!(profile_stats.tweets, 1234);
+        assert_eq!(profile_stats.following, 56);
+        assert_eq!(profile_stats.followers, 7890);
+        assert_eq!(profile_stats.likes, 12);
+    }
+
+    #[test]
+    fn test_verified() {
+        let html = timeline(&timeline_item(100, "")).replace(
+            "Full Name</a>",
+            r#"Full Name<div class="icon-container"><span class="icon-ok verified-icon business"></span></div></a>"#,
+        );
+        let tweets = parse_tweets(html, &ParseOptions::default());
+        assert!(tweets[0].user.verified);
+        assert_eq!(tweets[0].user.verified_type, Some(VerifiedType::Business));
+    }
+
+    #[test]
+    fn test_retweeted_by() {
+        let header = r#"<div class="retweet-header"><span><div class="icon-container"><span class="icon-retweet"></span> Some One retweeted</div></span></div>"#;
+        let html = timeline(&timeline_item(100, header));
+        let tweets = parse_tweets(html.clone(), &ParseOptions::default());
+        assert!(tweets[0].retweet);
+        // The header only has the retweeter's full name
+        assert_eq!(tweets[0].retweeted_by, None);
+
+        // On a user's timeline, it's the user whose profile card is on the page
+        let profile = r#"<div class="profile-card"><a class="profile-card-username" href="/someone">@someone</a></div>"#;
+        let html = html.replace("<body>", &format!("<body>{profile}"));
+        let tweets = parse_tweets(html, &ParseOptions::default());
+        assert!(tweets[0].retweet);
+        assert_eq!(tweets[0].retweeted_by.as_deref(), Some("someone"));
+    }
+
+    #[test]
+    fn test_quoted_id() {
+        let html = timeline(&format!(
+            "{}{}",
+            timeline_item(
+                100,
+                r#"<div class="quote quote-big"><a class="quote-link" href="/other/status/50#m"></a><div class="tweet-name-row"><a class="fullname" href="/other" title="Other">Other</a></div><div class="quote-text">quoted</div></div>"#,
+            ),
+            timeline_item(101, ""),
+        ));
+        let tweets = parse_tweets(html, &ParseOptions::default());
+        assert!(tweets[0].quote);
+        assert_eq!(tweets[0].quoted_id, Some(50));
+        assert_eq!(tweets[0].full_text, "text");
+        assert_eq!(tweets[1].quoted_id, None);
+    }
+
+    #[test]
+    fn test_stats() {
+        let stat = |icon: &str, num: &str| {
+            format!(
+                r#"<span class="tweet-stat"><div class="icon-container"><span class="icon-{icon}"></span> {num}</div></span>"#
+            )
+        };
+        let stats = format!(
+            r#"<div class="tweet-stats">{}{}{}{}</div>"#,
+            stat("comment", "1"),
+            stat("retweet", "2"),
+            stat("heart", "3.4K"),
+            stat("views", "12,345"),
+        );
+        let html = timeline(&timeline_item(100, &stats));
+        let tweets = parse_tweets(html, &ParseOptions::default());
+        let stats = &tweets[0].stats;
+        assert_eq!(stats.comment, 1);
+        assert_eq!(stats.retweet, 2);
+        assert_eq!(stats.quote, 0);
+        assert_eq!(stats.heart, 3400);
+        assert_eq!(stats.views, Some(12345));
+    }
+
+    #[test]
+    fn test_markdown() {
+        let html = timeline(&timeline_item(100, "")).replace(
+            ">text<",
+            r#">*hi* <a href="/jack">@jack</a><br><a href="/search?q=%23tag">#tag</a> <a href="https://example.com/">example.com</a><"#,
+        );
+        let tweets = parse_tweets(html, &ParseOptions::default());
+        assert_eq!(
+            tweets[0].full_text_markdown,
+            "\\*hi\\* [@jack](https://twitter.com/jack)  \n[\\#tag](https://twitter.com/hashtag/tag) [example.com](https://example.com/)"
+        );
+    }
+
+    #[test]
+    fn test_image_quality() {
+        let html = timeline(&timeline_item(
+            100,
+            r#"<div class="attachment image"><a class="still-image" href="/pic/orig/media%2FAbC-1.jpg"></a></div>"#,
+        ));
+        let tweets = parse_tweets(html.clone(), &ParseOptions::default());
+        assert_eq!(tweets[0].images, ["https://pbs.twimg.com/media/AbC-1.jpg"]);
+        let options = ParseOptions {
+            image_quality: ImageQuality::Orig,
+            ..Default::default()
+        };
+        let tweets = parse_tweets(html, &options);
+        assert_eq!(
+            tweets[0].images,
+            ["https://pbs.twimg.com/media/AbC-1.jpg?name=orig"]
+        );
+    }
+
+    #[test]
+    fn test_video_thumbnail() {
+        let html = timeline(&timeline_item(
+            100,
+            r#"<div class="attachment video-container"><img src="/pic/amplify_video_thumb%2F123%2Fimg%2Fabc.jpg%3Fname%3Dsmall" alt=""></div>"#,
+        ));
+        let tweets = parse_tweets(html, &ParseOptions::default());
+        assert!(tweets[0].video.is_none());
+        assert_eq!(
+            tweets[0].video_thumbnail.as_deref(),
+            Some("https://pbs.twimg.com/amplify_video_thumb/123/img/abc.jpg")
+        );
+    }
+
+    #[test]
+    fn test_attachments() {
+        let html = timeline(&timeline_item(
+            100,
+            r#"<div class="attachments">
+                <div class="attachment video-container"><video poster="/pic/ext_tw_video_thumb%2F1%2Fimg%2Fa.jpg"><source src="https://video.twimg.com/a.mp4"></video></div>
+                <div class="attachment image"><a class="still-image" href="/pic/orig/media%2Fb.jpg"></a></div>
+                <div class="attachment media-gif"><video class="gif" poster="/pic/tweet_video_thumb%2Fc.jpg"><source src="https://video.twimg.com/tweet_video/c.mp4"></video></div>
+            </div>"#,
+        ));
+        let tweets = parse_tweets(html, &ParseOptions::default());
+        assert_eq!(
+            tweets[0].attachments,
+            [
+                Attachment::Video {
+                    url: Some("https://video.twimg.com/a.mp4".into()),
+                    poster: Some("https://pbs.twimg.com/ext_tw_video_thumb/1/img/a.jpg".into()),
+                },
+                Attachment::Image {
+                    url: "https://pbs.twimg.com/media/b.jpg".into(),
+                },
+                Attachment::Gif {
+                    url: Some("https://video.twimg.com/tweet_video/c.mp4".into()),
+                    poster: Some("https://pbs.twimg.com/tweet_video_thumb/c.jpg".into()),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_sensitive() {
+        let html = timeline(&format!(
+            "{}{}{}",
+            timeline_item(100, r#"<div class="attachments sensitive"></div>"#),
+            timeline_item(101, r#"<div class="card" data-sensitive="true"></div>"#),
+            timeline_item(102, r#"<div class="card" data-sensitive="false"></div>"#),
+        ));
+        let tweets = parse_tweets(html, &ParseOptions::default());
+        let sensitive: Vec<_> = tweets.iter().map(|t| t.possibly_sensitive).collect();
+        assert_eq!(sensitive, [true, true, false]);
+    }
+
+    #[test]
+    fn test_unavailable() {
+        let html = timeline(&format!(
+            r#"{}<div class="timeline-item unavailable"><div class="unavailable-box">This tweet is
+                unavailable</div></div>{}"#,
+            timeline_item(100, ""),
+            timeline_item(99, ""),
+        ));
+        let (items, _) = parse_nitter_html(
+            html,
+            &ParseOptions::default(),
+            &mut FieldCoverage::default(),
+        )
+        .unwrap();
+        assert_eq!(items.len(), 3);
+        match &items[1] {
+            TimelineItem::Unavailable(u) => {
+                assert_eq!(u.position, Some(1));
+                assert_eq!(u.reason, "This tweet is unavailable");
+            }
+            _ => panic!("expected unavailable tweet"),
+        }
+        match &items[2] {
+            TimelineItem::Tweet(t) => assert_eq!(t.position, Some(2)),
+            _ => panic!("expected tweet"),
+        }
+    }
+
+    #[test]
+    fn test_malformed_item() {
+        let html = timeline(&format!(
+            "{}{}{}",
+            timeline_item(100, ""),
+            timeline_item(100, "").replace("/status/100", "/status/abc"),
+            timeline_item(99, ""),
+        ));
+        let options = ParseOptions::default();
+        assert!(matches!(
+            parse_nitter_html(html.clone(), &options, &mut FieldCoverage::default()),
+            Err(NitterError::Parse(_))
+        ));
+
+        // Only the malformed item fails
+        let (items, _) =
+            parse_nitter_html_items(html, &options, &mut FieldCoverage::default()).unwrap();
+        assert_eq!(items.len(), 3);
+        assert!(matches!(&items[0], Ok(TimelineItem::Tweet(t)) if t.id == 100));
+        assert!(matches!(&items[1], Err(NitterError::Parse(_))));
+        assert!(matches!(&items[2], Ok(TimelineItem::Tweet(t)) if t.position == Some(2)));
+    }
+
+    #[test]
+    fn test_rate_limited() {
+        let html = r#"<html><body><div class="error-panel"><span>Instance has been rate limited.<br>Use another instance or try again later.</span></div></body></html>"#;
+        assert!(matches!(
+            parse_nitter_html(
+                html.into(),
+                &ParseOptions::default(),
+                &mut FieldCoverage::default()
+            ),
+            Err(NitterError::RateLimited {
+                retry_after: None,
+                ..
+            })
+        ));
+        assert!(matches!(
+            parse_nitter_profile(html),
+            Err(NitterError::RateLimited {
+                retry_after: None,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_coverage() {
+        let html = timeline(&format!(
+            "{}{}",
+            timeline_item(
+                100,
+                r#"<div class="attachment image"><a class="still-image" href="/pic/orig/media%2Fb.jpg"></a></div>"#
+            ),
+            timeline_item(
+                99,
+                r#"<div class="attachment image"><a class="still-image" href="/unknown/b.jpg"></a></div>"#
+            ),
+        ));
+        let mut coverage = FieldCoverage::default();
+        parse_nitter_html(html, &ParseOptions::default(), &mut coverage).unwrap();
+        assert!(coverage.has_missing());
+        assert_eq!(
+            coverage.to_string(),
+            "attachments: 1/2 extracted, images: 1/2 extracted"
+        );
+    }
+
+    #[test]
+    fn test_created_at() {
+        let html = timeline(&format!(
+            "{}{}",
+            timeline_item(1612439052567916544, ""),
+            timeline_item(100, "")
+        ));
+        let tweets = parse_tweets(html, &ParseOptions::default());
+        assert_eq!(tweets[0].created_at_ms, 1673270402458);
+        assert_eq!(tweets[0].created_at_ts, 1673270402);
+        assert_eq!(tweets[0].created_at, "Mon, 09 Jan 2023 13:20:02 +0000");
+        assert_eq!(tweets[1].created_at_ts, 1672578000);
+        assert_eq!(tweets[1].created_at_ms, 1672578000000);
+    }
+
+    #[test]
+    fn test_version() {
+        let html = r#"<html><body><div class="about-page">
+            <h2>Instance info</h2>
+            <p>Version <a href="https://github.com/zedeus/nitter/commit/3f2d2bb">2023.08.24-3f2d2bb</a></p>
+            </div></body></html>"#;
+        assert_eq!(
+            parse_nitter_version(html).as_deref(),
+            Some("2023.08.24-3f2d2bb")
+        );
+        assert_eq!(parse_nitter_version(&timeline("")), None);
+    }
+
+    #[test]
+    fn test_line_breaks() {
+        let html = timeline(&timeline_item(100, "")).replace(
+            ">text<",
+            r#">line 1<br>line 2<br><br><p>paragraph</p>after<"#,
+        );
+        let tweets = parse_tweets(html, &ParseOptions::default());
+        assert_eq!(tweets[0].full_text, "line 1\nline 2\n\nparagraph\nafter");
+    }
+
+    #[test]
+    fn test_stat_count() {
+        assert_eq!(parse_stat_count(""), None);
+        assert_eq!(parse_stat_count("12"), Some(12));
+        assert_eq!(parse_stat_count(" 1,234 "), Some(1234));
+        assert_eq!(parse_stat_count("1.234.567"), Some(1234567));
+        assert_eq!(parse_stat_count("1 234"), Some(1234));
+        assert_eq!(parse_stat_count("1.2K"), Some(1200));
+        assert_eq!(parse_stat_count("15K"), Some(15000));
+        assert_eq!(parse_stat_count("3,4M"), Some(3400000));
+    }
+
+    #[test]
+    fn test_legacy_layout() {
+        let html = timeline(&timeline_item(100, ""))
+            .replace("timeline-item", "timeline-tweet")
+            .replace("tweet-content", "status-content");
+        let document = Html::parse_document(&html);
+        assert_eq!(Layout::detect(&document), Layout::Legacy);
+        let tweets = parse_tweets(html, &ParseOptions::default());
+        assert_eq!(tweets.len(), 1);
+        assert_eq!(tweets[0].id, 100);
+        assert_eq!(tweets[0].full_text, "text");
+
+        let document = Html::parse_document(&timeline(&timeline_item(100, "")));
+        assert_eq!(Layout::detect(&document), Layout::Current);
+    }
+
+    #[test]
+    fn test_selector_profile() {
+        let html = timeline(&timeline_item(100, ""))
+            .replace("timeline-item", "fork-item")
+            .replace("tweet-content", "fork-body");
+        let options = ParseOptions {
+            selectors: SelectorProfile::default()
+                .item(".fork-item")
+                .unwrap()
+                .body(".fork-body")
+                .unwrap(),
+            ..Default::default()
+        };
+        let tweets = parse_tweets(html, &options);
+        assert_eq!(tweets.len(), 1);
+        assert_eq!(tweets[0].full_text, "text");
+
+        assert!(matches!(
+            SelectorProfile::default().item("<>"),
+            Err(NitterError::InvalidSelector(_))
+        ));
+    }
+
+    #[test]
+    fn test_media_urls() {
+        let html = timeline(&timeline_item(
+            100,
+            r#"<div class="attachment image"><a class="still-image" href="/pic/orig/media%2FAbC-1.jpg"></a></div>"#,
+        ));
+        let options = ParseOptions {
+            media_urls: MediaUrls::Proxied,
+            instance: "https://nitter.example/".into(),
+            ..Default::default()
+        };
+        let tweets = parse_tweets(html.clone(), &options);
+        assert_eq!(
+            tweets[0].images,
+            ["https://nitter.example/pic/orig/media%2FAbC-1.jpg"]
+        );
+        assert_eq!(tweets[0].proxied_images, None);
+
+        let options = ParseOptions {
+            media_urls: MediaUrls::Both,
+            ..options
+        };
+        let tweets = parse_tweets(html, &options);
+        assert_eq!(tweets[0].images, ["https://pbs.twimg.com/media/AbC-1.jpg"]);
+        assert_eq!(
+            tweets[0].proxied_images.as_deref(),
+            Some(&["https://nitter.example/pic/orig/media%2FAbC-1.jpg".to_owned()][..])
+        );
+    }
+
+    #[test]
+    fn test_profile() {
+        let html = r#"<html><body><div class="profile-card">
+                <a class="profile-card-fullname" href="/User" title="Full Name">Full Name<span class="icon-ok verified-icon blue"></span></a>
+                <a class="profile-card-username" href="/User" title="@User">@User</a>
+                <div class="profile-card-extra">
+                    <div class="profile-bio"><p>Hello <a href="/search?q=%23bio">#bio</a></p></div>
+                    <div class="profile-location"><span class="icon-location"></span> <span>Earth</span></div>
+                    <div class="profile-website"><span><span class="icon-link"></span> <a href="https://example.com">example.com</a></span></div>
+                    <div class="profile-joindate"><span title="10:40 PM - 21 Mar 2006"><span class="icon-calendar"></span> Joined March 2006</span></div>
+                </div>
+                <ul class="profile-statlist">
+                    <li class="posts"><span class="profile-stat-num">1,234</span></li>
+                    <li class="followers"><span class="profile-stat-num">7,890</span></li>
+                </ul>
+            </div></body></html>"#;
+        let profile = parse_nitter_profile(html).unwrap();
+        assert_eq!(profile.screen_name, "User");
+        assert_eq!(profile.full_name, "Full Name");
+        assert_eq!(profile.verified_type, Some(VerifiedType::Blue));
+        assert_eq!(profile.bio.as_deref(), Some("Hello #bio"));
+        assert_eq!(profile.location.as_deref(), Some("Earth"));
+        assert_eq!(profile.website.as_deref(), Some("https://example.com"));
+        assert_eq!(profile.joined_at_ts, Some(1142980800));
+        assert!(!profile.protected);
+        assert_eq!(profile.stats.tweets, 1234);
+        assert_eq!(profile.stats.followers, 7890);
+
+        assert!(parse_nitter_profile(&timeline("")).is_err());
+    }
+
+    #[test]
+    fn test_replies() {
+        let html = format!(
+            r#"<html><body><div class="conversation">
+                <div class="main-thread"><div class="main-tweet">{}</div></div>
+                <div class="replies">
+                    <div class="reply thread thread-line">{}<div class="timeline-item more-replies"><a class="more-replies-text" href="/user/status/201#m">more replies</a></div></div>
+                    <div class="reply thread thread-line">{}</div>
+                    <div class="show-more"><a href="?cursor=abc">Load more</a></div>
+                </div>
+            </div></body></html>"#,
+            timeline_item(100, ""),
+            timeline_item(201, ""),
+            timeline_item(202, ""),
+        );
+
+        let (items, cursor) = parse_nitter_single(
+            html.clone(),
+            &ParseOptions::default(),
+            &mut FieldCoverage::default(),
+        )
+        .unwrap();
+        assert_eq!(items.len(), 1);
+        assert!(matches!(cursor, NitterCursor::End));
+
+        let options = ParseOptions {
+            include_replies: true,
+            ..Default::default()
+        };
+        let (items, cursor) =
+            parse_nitter_single(html, &options, &mut FieldCoverage::default()).unwrap();
+        let ids: Vec<_> = items
+            .iter()
+            .map(|item| match item {
+                TimelineItem::Tweet(t) => t.id,
+                _ => 0,
+            })
+            .collect();
+        assert_eq!(ids, [100, 201, 202]);
+        assert!(matches!(cursor, NitterCursor::More(c) if c == "?cursor=abc"));
+
+        // Deleted tweets have no main tweet
+        let result = parse_nitter_single(timeline(""), &options, &mut FieldCoverage::default());
+        assert!(matches!(result, Err(NitterError::Parse(_))));
+    }
+
+    #[test]
+    fn test_user_results() {
+        let html = timeline(
+            r#"<div class="timeline-item">
+                <a class="tweet-link" href="/jack"></a>
+                <div class="tweet-body profile-result">
+                    <div class="tweet-header">
+                        <a class="fullname" href="/jack" title="jack">jack<span class="icon-ok verified-icon"></span></a>
+                        <a class="username" href="/jack" title="@jack">@jack</a>
+                    </div>
+                    <div class="tweet-content media-body">no state is the best state</div>
+                </div>
+            </div>"#,
+        );
+        let (items, _) = parse_nitter_html(
+            html,
+            &ParseOptions::default(),
+            &mut FieldCoverage::default(),
+        )
+        .unwrap();
+        let TimelineItem::User(user) = &items[0] else {
+            panic!("expected a user, got {:?}", items[0]);
+        };
+        assert_eq!(user.screen_name, "jack");
+        assert_eq!(user.full_name, "jack");
+        assert_eq!(user.verified_type, Some(VerifiedType::Legacy));
+        assert_eq!(user.bio.as_deref(), Some("no state is the best state"));
+        assert_eq!(user.followers, None);
+        assert_eq!(user.position, Some(0));
+    }
+
+    #[test]
+    fn test_rss() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<rss xmlns:atom="http://www.w3.org/2005/Atom" xmlns:dc="http://purl.org/dc/elements/1.1/" version="2.0">
+  <channel>
+    <title>Full Name / @user</title>
+    <link>https://nitter.example/user</link>
+    <item>
+      <title>RT by @user: hello &amp; bye</title>
+      <dc:creator>@other</dc:creator>
+      <description><![CDATA[<p>hello &amp; bye</p>
+<img src="https://nitter.example/pic/media%2Fabc.jpg" style="max-width:250px;" />]]></description>
+      <pubDate>Sun, 01 Jan 2023 13:00:00 GMT</pubDate>
+      <guid>https://nitter.example/other/status/1609578040000000000#m</guid>
+      <link>https://nitter.example/other/status/1609578040000000000#m</link>
+    </item>
+    <item>
+      <title>R to @other: reply</title>
+      <dc:creator>@user</dc:creator>
+      <description><![CDATA[<p>reply</p>]]></description>
+      <pubDate>Sun, 01 Jan 2023 12:00:00 GMT</pubDate>
+      <guid>https://nitter.example/user/status/1609560000000000000#m</guid>
+      <link>https://nitter.example/user/status/1609560000000000000#m</link>
+    </item>
+  </channel>
+</rss>"#;
+        let items = parse_nitter_rss(xml, &ParseOptions::default()).unwrap();
+        let tweets: Vec<_> = items
+            .into_iter()
+            .filter_map(|item| match item {
+                Ok(TimelineItem::Tweet(tweet)) => Some(tweet),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(tweets.len(), 2);
+
+        assert_eq!(tweets[0].id, 1609578040000000000);
+        assert_eq!(tweets[0].user.screen_name, "other");
+        assert_eq!(tweets[0].user.full_name, "other");
+        assert_eq!(tweets[0].full_text, "hello & bye");
+        assert_eq!(tweets[0].images, ["https://pbs.twimg.com/media/abc.jpg"]);
+        assert!(tweets[0].retweet);
+        assert_eq!(tweets[0].retweeted_by.as_deref(), Some("user"));
+        assert!(!tweets[0].reply);
+
+        assert_eq!(tweets[1].user.screen_name, "user");
+        assert_eq!(tweets[1].user.full_name, "Full Name");
+        assert!(tweets[1].reply);
+        assert!(!tweets[1].retweet);
+        assert_eq!(tweets[1].position, Some(1));
+
+        assert!(
+            parse_nitter_rss("<html><body>Error</body></html>", &ParseOptions::default()).is_err()
+        );
+    }
+}
@@ -0,0 +1,238 @@
+use std::pin::Pin;
+use std::sync::Arc;
+
+use futures_util::{Stream, StreamExt};
+use typed_builder::TypedBuilder;
+
+use crate::error::NitterError;
+use crate::http_client::HttpClient;
+use crate::multi_scraper::take_turns;
+use crate::nitter_scraper::{NitterQuery, NitterScraper};
+use crate::rate_limit::RateLimiter;
+use crate::tweet::Tweet;
+
+type TweetResult = Result<Tweet, NitterError>;
+
+/// Scrapes the timelines of many accounts, spread over several instances
+///
+/// Accounts are spread evenly over the instances and take turns like those of a
+/// [`MultiScraper`](crate::MultiScraper): their pages are requested round robin, a limited number
+/// at once, so an account with many tweets can't hold up the others. Tweets are yielded a page at
+/// a time as pages arrive, not in any order across accounts.
+#[derive(TypedBuilder)]
+#[builder(build_method(vis = "pub", name = try_build, into = Result<NitterScraperPool<'a>, NitterError>))]
+pub struct NitterScraperPool<'a> {
+    #[builder(setter(into))]
+    client: HttpClient<'a>,
+
+    #[builder(setter(transform = |instances: impl IntoIterator<Item = impl Into<String>>| {
+        instances.into_iter().map(Into::into).collect()
+    }))]
+    instances: Vec<String>,
+
+    #[builder(setter(transform = |users: impl IntoIterator<Item = impl Into<String>>| {
+        users.into_iter().map(Into::into).collect()
+    }))]
+    users: Vec<String>,
+
+    /// Number of pages to request at once, across all accounts
+    #[builder(default = 4)]
+    concurrency: usize,
+
+    /// Number of pages of each account that can be requested or waiting to be yielded at once
+    #[builder(default = 1)]
+    max_in_flight_per_account: usize,
+
+    /// Most tweets of the pages that arrived to hold until they're yielded, across all accounts
+    #[builder(default)]
+    max_buffered_items: Option<usize>,
+
+    #[builder(default)]
+    limit_per_user: Option<usize>,
+
+    /// Maximum number of requests per minute to send to each instance, across all accounts
+    #[builder(default)]
+    rate_limit_per_instance: Option<u32>,
+
+    /// Scraper of each user, on the instance it was assigned
+    #[builder(setter(skip), default)]
+    scrapers: Vec<NitterScraper<'a>>,
+}
+
+impl<'a> From<NitterScraperPool<'a>> for Result<NitterScraperPool<'a>, NitterError> {
+    /// Build the scraper of every user, assigning users to instances round robin
+    fn from(mut pool: NitterScraperPool<'a>) -> Self {
+        if pool.instances.is_empty() {
+            return Err(NitterError::InvalidInstance(
+                "no instances to scrape".into(),
+            ));
+        }
+        let rate_limits: Vec<_> = pool
+            .instances
+            .iter()
+            .map(|_| {
+                pool.rate_limit_per_instance
+                    .map(|rpm| Arc::new(RateLimiter::per_minute(rpm)))
+            })
+            .collect();
+        pool.scrapers = pool
+            .users
+            .iter()
+            .zip(pool.instances.iter().zip(&rate_limits).cycle())
+            .map(|(user, (instance, rate_limit))| {
+                let builder = NitterScraper::builder()
+                    .client(pool.client)
+                    .instance(instance.as_str())
+                    .query(NitterQuery::User { user: user.clone() })
+                    .reorder_pinned(true)
+                    .limit(pool.limit_per_user);
+                match rate_limit {
+                    Some(rate_limit) => builder.shared_rate_limit(rate_limit.clone()).try_build(),
+                    None => builder.try_build(),
+                }
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(pool)
+    }
+}
+
+impl<'a> NitterScraperPool<'a> {
+    /// Scrape every account, yielding their tweets tagged with the username
+    ///
+    /// An error only ends the scrape of the account it happened in.
+    pub async fn search<'s>(&'s self) -> impl Stream<Item = (String, TweetResult)> + use<'s, 'a> {
+        let mut streams = vec![];
+        for scraper in self.scrapers.iter() {
+            let tweets: Pin<Box<dyn Stream<Item = TweetResult> + 's>> =
+                Box::pin(scraper.search().await);
+            streams.push(tweets);
+        }
+        take_turns(
+            streams,
+            Some(self.concurrency),
+            self.max_in_flight_per_account,
+            self.max_buffered_items,
+        )
+        .map(|(user, result)| (self.users[user].clone(), result))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
+    use super::*;
+    use crate::http_client::{HttpFetch, HttpRequest, HttpResponse};
+    use futures_util::future::BoxFuture;
+
+    /// Serves a timeline with one tweet per page, counting the requests to each instance
+    ///
+    /// Users named "busy" have three pages, the others one. The pages are synthetic, with only the
+    /// markup the parser reads.
+    #[derive(Default)]
+    struct FakeInstances(Mutex<HashMap<String, usize>>);
+
+    impl HttpFetch for FakeInstances {
+        fn fetch(&self, request: HttpRequest) -> BoxFuture<'_, Result<HttpResponse, NitterError>> {
+            let (instance, path) = request.url.rsplit_once('/').unwrap();
+            *self
+                .0
+                .lock()
+                .unwrap()
+                .entry(instance.to_owned())
+                .or_default() += 1;
+            let (user, page) = match path.split_once("?cursor=") {
+                Some((user, page)) => (user, page.parse::<u128>().unwrap()),
+                None => (path, 0),
+            };
+            let more = match user == "busy" && page < 2 {
+                true => format!(
+                    r#"<div class="show-more"><a href="?cursor={}">Load more</a></div>"#,
+                    page + 1
+                ),
+                false => String::new(),
+            };
+            let body = format!(
+                r#"<html><body><div class="timeline"><div class="timeline-item">
+                    <a class="fullname" href="/{user}" title="User">User</a>
+                    <span class="tweet-date"><a href="/{user}/status/{id}#m" title="Jan 1, 2023 · 1:00 PM UTC">1 Jan</a></span>
+                    <div class="tweet-content media-body">text</div>
+                </div>{more}</div></body></html>"#,
+                id = 100 - page
+            );
+            let response = HttpResponse::new(200, vec![], request.url, body);
+            Box::pin(async { response })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_pool() {
+        let fetch = FakeInstances::default();
+        let pool = NitterScraperPool::builder()
+            .client(HttpClient::Custom(&fetch))
+            .instances(["https://a.example", "https://b.example"])
+            .users(["u1", "u2", "u3", "u4"])
+            .concurrency(2)
+            .try_build()
+            .unwrap();
+        let mut users: Vec<_> = pool
+            .search()
+            .await
+            .map(|(user, tweet)| (user, tweet.unwrap().id))
+            .collect()
+            .await;
+        users.sort();
+        assert_eq!(
+            users,
+            [
+                ("u1".to_owned(), 100),
+                ("u2".to_owned(), 100),
+                ("u3".to_owned(), 100),
+                ("u4".to_owned(), 100)
+            ]
+        );
+        // Accounts are spread evenly over the instances
+        let requests = fetch.0.lock().unwrap().clone();
+        assert_eq!(requests["https://a.example"], 2);
+        assert_eq!(requests["https://b.example"], 2);
+
+        let pool = NitterScraperPool::builder()
+            .client(HttpClient::Custom(&fetch))
+            .instances(Vec::<String>::new())
+            .users(["u1"])
+            .try_build();
+        assert!(matches!(pool, Err(NitterError::InvalidInstance(_))));
+    }
+
+    #[tokio::test]
+    async fn test_pool_turns() {
+        let fetch = FakeInstances::default();
+        let pool = NitterScraperPool::builder()
+            .client(HttpClient::Custom(&fetch))
+            .instances(["https://a.example"])
+            .users(["busy", "u2", "u3"])
+            .concurrency(1)
+            .try_build()
+            .unwrap();
+        let tweets: Vec<_> = pool
+            .search()
+            .await
+            .map(|(user, tweet)| (user, tweet.unwrap().id))
+            .collect()
+            .await;
+        // Every account gets its first page before the busy one gets its next
+        let expected = [
+            ("busy", 100),
+            ("u2", 100),
+            ("u3", 100),
+            ("busy", 99),
+            ("busy", 98),
+        ];
+        let expected: Vec<_> = expected
+            .iter()
+            .map(|&(user, id)| (user.to_owned(), id))
+            .collect();
+        assert_eq!(tweets, expected);
+    }
+}
@@ -0,0 +1,59 @@
+use std::sync::Mutex;
+use std::time::Duration;
+
+use crate::timer::{self, Instant};
+
+/// Spaces requests evenly so no more than a set number are sent per minute
+///
+/// Wrapped in an `Arc`, one limiter can be shared by several scrapers with their
+/// `shared_rate_limit` option.
+#[derive(Debug)]
+pub struct RateLimiter {
+    interval: Duration,
+    /// Earliest time the next request may be sent
+    next: Mutex<Option<Instant>>,
+}
+
+impl RateLimiter {
+    pub fn per_minute(requests_per_minute: u32) -> Self {
+        Self {
+            interval: Duration::from_secs(60) / requests_per_minute.max(1),
+            next: Mutex::new(None),
+        }
+    }
+
+    /// Wait for the next free slot, reserving it
+    pub(crate) async fn wait(&self) {
+        let slot = {
+            let mut next = self.next.lock().unwrap();
+            let now = Instant::now();
+            let slot = next.map_or(now, |next| next.max(now));
+            *next = Some(slot + self.interval);
+            slot
+        };
+        timer::sleep_until(slot).await;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[tokio::test(start_paused = true)]
+    async fn test_rate_limiter() {
+        let limiter = RateLimiter::per_minute(30);
+        let start = Instant::now();
+        limiter.wait().await;
+        assert_eq!(start.elapsed(), Duration::ZERO);
+        limiter.wait().await;
+        limiter.wait().await;
+        assert_eq!(start.elapsed(), Duration::from_secs(4));
+
+        // Idle time isn't saved up for bursts
+        tokio::time::sleep(Duration::from_secs(60)).await;
+        let start = Instant::now();
+        limiter.wait().await;
+        limiter.wait().await;
+        assert_eq!(start.elapsed(), Duration::from_secs(2));
+    }
+}
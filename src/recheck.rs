@@ -0,0 +1,147 @@
+use std::collections::VecDeque;
+
+use crate::tweet::Tweet;
+
+/// The newest tweets seen while following a timeline, re-checked in turn by
+/// [`NitterScraper::recheck`](crate::NitterScraper::recheck) to notice the ones that disappear,
+/// e.g. because they were deleted
+///
+/// Push each new tweet after a poll of the timeline, the oldest ones beyond the window size are
+/// forgotten.
+#[derive(Debug)]
+pub struct RecheckWindow {
+    size: usize,
+    /// IDs and authors of the tweets, oldest first
+    tweets: VecDeque<(u128, String)>,
+    /// Index of the tweet to re-check next
+    next: usize,
+}
+
+impl RecheckWindow {
+    pub fn new(size: usize) -> Self {
+        Self {
+            size,
+            tweets: VecDeque::new(),
+            next: 0,
+        }
+    }
+
+    /// Add a new tweet, forgetting the oldest ones beyond the window size
+    pub fn push(&mut self, tweet: &Tweet) {
+        self.tweets
+            .push_back((tweet.id, tweet.user.screen_name.clone()));
+        while self.tweets.len() > self.size {
+            self.tweets.pop_front();
+            self.next = self.next.saturating_sub(1);
+        }
+    }
+
+    /// The next `count` tweets to re-check, continuing from where the last ones left off
+    pub(crate) fn due(&mut self, count: usize) -> Vec<(u128, String)> {
+        let count = count.min(self.tweets.len());
+        let due = (0..count)
+            .map(|i| self.tweets[(self.next + i) % self.tweets.len()].clone())
+            .collect();
+        self.next = match self.tweets.len() {
+            0 => 0,
+            len => (self.next + count) % len,
+        };
+        due
+    }
+
+    pub(crate) fn remove(&mut self, id: u128) {
+        if let Some(i) = self.tweets.iter().position(|(recent, _)| *recent == id) {
+            self.tweets.remove(i);
+            if i < self.next {
+                self.next -= 1;
+            }
+        }
+    }
+}
+
+#[cfg(all(test, feature = "reqwest"))]
+mod test {
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+
+    use reqwest::Client;
+
+    use super::*;
+    use crate::nitter_scraper::{NitterQuery, NitterScraper};
+
+    /// Serve status pages, with a 404 for tweets with odd IDs as if they were deleted
+    fn serve_statuses() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let instance = format!("http://{}", listener.local_addr().unwrap());
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut lines = BufReader::new(&stream).lines();
+                let request = lines.next().unwrap().unwrap();
+                for line in lines {
+                    if line.unwrap().is_empty() {
+                        break;
+                    }
+                }
+                let id: u128 = request.split(['/', ' ']).nth(4).unwrap().parse().unwrap();
+                let status = match id % 2 {
+                    1 => "404 Not Found",
+                    _ => "200 OK",
+                };
+                write!(
+                    stream,
+                    "HTTP/1.1 {status}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+                )
+                .unwrap();
+            }
+        });
+        instance
+    }
+
+    fn ids(due: Vec<(u128, String)>) -> Vec<u128> {
+        due.into_iter().map(|(id, _)| id).collect()
+    }
+
+    #[test]
+    fn test_recheck_window() {
+        let mut window = RecheckWindow::new(3);
+        window.tweets = (2..=4).map(|id| (id, "user".to_owned())).collect();
+
+        // The tweets take turns, wrapping around
+        assert_eq!(ids(window.due(2)), [2, 3]);
+        assert_eq!(ids(window.due(2)), [4, 2]);
+
+        // Removing a tweet that was already re-checked keeps the turn
+        window.remove(2);
+        assert_eq!(ids(window.due(1)), [3]);
+        assert_eq!(ids(window.due(5)), [4, 3]);
+    }
+
+    #[tokio::test]
+    async fn test_recheck() {
+        let instance = serve_statuses();
+        let client = Client::new();
+        let scraper = NitterScraper::builder()
+            .client(&client)
+            .instance(instance)
+            .query(NitterQuery::User {
+                user: "user".into(),
+            })
+            .try_build()
+            .unwrap();
+        let mut window = RecheckWindow::new(3);
+        window.tweets = (1..=3).map(|id| (id, "user".to_owned())).collect();
+
+        // Only the first two tweets are re-checked within the budget
+        let disappeared = scraper.recheck(&mut window, 2).await;
+        let disappeared_ids: Vec<_> = disappeared.iter().map(|tweet| tweet.id).collect();
+        assert_eq!(disappeared_ids, [1]);
+        assert_eq!(disappeared[0].screen_name, "user");
+
+        // The disappeared tweet isn't re-checked again
+        let disappeared = scraper.recheck(&mut window, 2).await;
+        let disappeared_ids: Vec<_> = disappeared.iter().map(|tweet| tweet.id).collect();
+        assert_eq!(disappeared_ids, [3]);
+        assert_eq!(ids(window.due(5)), [2]);
+    }
+}
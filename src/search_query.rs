@@ -0,0 +1,172 @@
+use time::macros::format_description;
+use time::Date;
+
+use crate::nitter_scraper::NitterQuery;
+
+/// Builder for search queries using Twitter's advanced search operators
+///
+/// ```
+/// use nitter_scraper::{NitterQuery, SearchQuery};
+///
+/// let query: NitterQuery = SearchQuery::new("rust")
+///     .from("jack")
+///     .filter_media()
+///     .min_faves(10)
+///     .into();
+/// assert_eq!(query.to_string(), "search:rust from:jack filter:media min_faves:10");
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct SearchQuery {
+    terms: Vec<String>,
+}
+
+impl SearchQuery {
+    /// Search for tweets containing all words of `text`, which may also contain operators
+    pub fn new(text: impl Into<String>) -> Self {
+        let text = text.into();
+        let mut query = Self::default();
+        if !text.trim().is_empty() {
+            query.terms.push(text.trim().to_owned());
+        }
+        query
+    }
+
+    fn push(mut self, term: String) -> Self {
+        self.terms.push(term);
+        self
+    }
+
+    /// Tweets containing this exact phrase
+    pub fn phrase(self, phrase: &str) -> Self {
+        self.push(format!("\"{}\"", phrase.replace('"', "")))
+    }
+
+    /// Tweets not containing this word
+    pub fn exclude(self, word: &str) -> Self {
+        self.push(format!("-{}", word))
+    }
+
+    /// Tweets sent by this user
+    pub fn from(self, user: &str) -> Self {
+        self.push(format!("from:{}", user.trim_start_matches('@')))
+    }
+
+    /// Tweets replying to this user
+    pub fn to(self, user: &str) -> Self {
+        self.push(format!("to:{}", user.trim_start_matches('@')))
+    }
+
+    /// Tweets mentioning this user
+    pub fn mentioning(self, user: &str) -> Self {
+        self.push(format!("@{}", user.trim_start_matches('@')))
+    }
+
+    /// Tweets sent on or after this day, in UTC
+    pub fn since(self, date: Date) -> Self {
+        self.push(format!("since:{}", format_date(date)))
+    }
+
+    /// Tweets sent before this day, in UTC
+    pub fn until(self, date: Date) -> Self {
+        self.push(format!("until:{}", format_date(date)))
+    }
+
+    /// Tweets in this language, e.g. "en"
+    pub fn lang(self, lang: &str) -> Self {
+        self.push(format!("lang:{}", lang))
+    }
+
+    /// Tweets with images or videos
+    pub fn filter_media(self) -> Self {
+        self.push("filter:media".into())
+    }
+
+    /// Tweets with images
+    pub fn filter_images(self) -> Self {
+        self.push("filter:images".into())
+    }
+
+    /// Tweets with videos
+    pub fn filter_videos(self) -> Self {
+        self.push("filter:videos".into())
+    }
+
+    /// Tweets with links
+    pub fn filter_links(self) -> Self {
+        self.push("filter:links".into())
+    }
+
+    /// Only replies
+    pub fn filter_replies(self) -> Self {
+        self.push("filter:replies".into())
+    }
+
+    /// Tweets that aren't replies
+    pub fn exclude_replies(self) -> Self {
+        self.push("-filter:replies".into())
+    }
+
+    /// Tweets that aren't retweets
+    pub fn exclude_retweets(self) -> Self {
+        self.push("-filter:nativeretweets".into())
+    }
+
+    /// Tweets with at least this many likes
+    pub fn min_faves(self, count: u64) -> Self {
+        self.push(format!("min_faves:{}", count))
+    }
+
+    /// Tweets with at least this many retweets
+    pub fn min_retweets(self, count: u64) -> Self {
+        self.push(format!("min_retweets:{}", count))
+    }
+
+    /// Tweets with at least this many replies
+    pub fn min_replies(self, count: u64) -> Self {
+        self.push(format!("min_replies:{}", count))
+    }
+}
+
+fn format_date(date: Date) -> String {
+    date.format(format_description!("[year]-[month]-[day]"))
+        .unwrap()
+}
+
+impl std::fmt::Display for SearchQuery {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.terms.join(" "))
+    }
+}
+
+impl From<SearchQuery> for NitterQuery {
+    fn from(query: SearchQuery) -> Self {
+        Self::Search {
+            query: query.to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use time::macros::date;
+
+    use super::*;
+
+    #[test]
+    fn test_search_query() {
+        let query = SearchQuery::new(" cats ")
+            .phrase("hello \"world\"")
+            .exclude("dogs")
+            .from("@jack")
+            .to("elon")
+            .since(date!(2023 - 01 - 05))
+            .until(date!(2023 - 02 - 01))
+            .exclude_retweets()
+            .min_retweets(5);
+        assert_eq!(
+            query.to_string(),
+            "cats \"hello world\" -dogs from:jack to:elon since:2023-01-05 until:2023-02-01 -filter:nativeretweets min_retweets:5"
+        );
+        assert_eq!(SearchQuery::new("").lang("en").to_string(), "lang:en");
+    }
+}
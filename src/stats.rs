@@ -0,0 +1,137 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use web_time::Instant;
+
+/// Counts of the work done by the last search, for capacity planning
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ScrapeStats {
+    /// Requests sent, including retries, expanded links and quoted tweets
+    pub requests: u64,
+    /// Timeline pages fetched
+    pub pages_fetched: u64,
+    /// Size of the response bodies downloaded, cached pages that weren't modified don't count
+    pub bytes_downloaded: u64,
+    /// Timeline items parsed, including the ones that failed to parse
+    pub items_parsed: u64,
+    /// Parsed items that weren't returned, e.g. retweets with `skip_retweets` or the items
+    /// returned before resuming
+    pub items_skipped: u64,
+    /// Requests retried after being rate limited or receiving an empty page
+    pub retries: u64,
+    /// Time from the start of the search until it ended, or until now if it hasn't
+    pub duration: Duration,
+}
+
+impl std::fmt::Display for ScrapeStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} requests, {} pages, {} bytes, {} items parsed, {} skipped, {} retries in {}s",
+            self.requests,
+            self.pages_fetched,
+            self.bytes_downloaded,
+            self.items_parsed,
+            self.items_skipped,
+            self.retries,
+            self.duration.as_secs()
+        )
+    }
+}
+
+/// Reads the stats of a scraper while its search stream borrows it
+#[derive(Debug, Clone)]
+pub struct StatsHandle(pub(crate) Arc<StatsCounters>);
+
+impl StatsHandle {
+    pub fn get(&self) -> ScrapeStats {
+        let counters = &self.0;
+        let (started, finished) = *counters.times.lock().unwrap();
+        let duration = match (started, finished) {
+            (Some(started), Some(finished)) => finished - started,
+            (Some(started), None) => started.elapsed(),
+            _ => Duration::ZERO,
+        };
+        ScrapeStats {
+            requests: counters.requests.load(Ordering::Relaxed),
+            pages_fetched: counters.pages_fetched.load(Ordering::Relaxed),
+            bytes_downloaded: counters.bytes_downloaded.load(Ordering::Relaxed),
+            items_parsed: counters.items_parsed.load(Ordering::Relaxed),
+            items_skipped: counters.items_skipped.load(Ordering::Relaxed),
+            retries: counters.retries.load(Ordering::Relaxed),
+            duration,
+        }
+    }
+}
+
+/// Counters shared with the requests of a search, which may outlive the borrow of the scraper
+/// when prefetched
+#[derive(Debug, Default)]
+pub(crate) struct StatsCounters {
+    pub requests: AtomicU64,
+    pub pages_fetched: AtomicU64,
+    pub bytes_downloaded: AtomicU64,
+    pub items_parsed: AtomicU64,
+    pub items_skipped: AtomicU64,
+    pub retries: AtomicU64,
+    /// When the search started and ended
+    times: Mutex<(Option<Instant>, Option<Instant>)>,
+}
+
+impl StatsCounters {
+    /// Clear the counters for a new search
+    pub fn start(&self) {
+        for counter in [
+            &self.requests,
+            &self.pages_fetched,
+            &self.bytes_downloaded,
+            &self.items_parsed,
+            &self.items_skipped,
+            &self.retries,
+        ] {
+            counter.store(0, Ordering::Relaxed);
+        }
+        *self.times.lock().unwrap() = (Some(Instant::now()), None);
+    }
+
+    pub fn finish(&self) {
+        let mut times = self.times.lock().unwrap();
+        if times.1.is_none() {
+            times.1 = Some(Instant::now());
+        }
+    }
+
+    /// The search continues after ending, e.g. a watch checking the timeline again
+    pub fn resume(&self) {
+        self.times.lock().unwrap().1 = None;
+    }
+
+    pub fn add(counter: &AtomicU64, n: u64) {
+        counter.fetch_add(n, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_stats() {
+        let handle = StatsHandle(Arc::default());
+        assert_eq!(handle.get(), ScrapeStats::default());
+
+        let counters = &handle.0;
+        StatsCounters::add(&counters.requests, 3);
+        counters.start();
+        StatsCounters::add(&counters.requests, 2);
+        StatsCounters::add(&counters.bytes_downloaded, 1024);
+        counters.finish();
+        let stats = handle.get();
+        // Counts from before the search started are cleared
+        assert_eq!(stats.requests, 2);
+        assert_eq!(stats.bytes_downloaded, 1024);
+        assert_eq!(handle.get().duration, stats.duration);
+    }
+}
@@ -0,0 +1,118 @@
+use futures_util::{Stream, StreamExt};
+use serde::Serialize;
+
+use crate::error::NitterError;
+use crate::tweet::Tweet;
+
+/// An author's consecutive tweets in one conversation, usually a self-thread
+#[derive(Debug, Serialize)]
+#[non_exhaustive]
+pub struct Thread {
+    /// ID of the first tweet of the conversation, which may not be in `tweets`
+    pub root: u128,
+    /// Tweets of the thread, oldest first
+    pub tweets: Vec<Tweet>,
+}
+
+/// Group consecutive tweets of the same author and conversation in a stream into threads
+///
+/// Tweets that aren't part of a thread are yielded as threads of one tweet, in stream order.
+pub fn threads<S>(tweets: S) -> impl Stream<Item = Result<Thread, NitterError>>
+where
+    S: Stream<Item = Result<Tweet, NitterError>>,
+{
+    futures_util::stream::unfold(Box::pin(tweets.peekable()), |mut tweets| async move {
+        let first = match tweets.next().await? {
+            Ok(tweet) => tweet,
+            Err(e) => return Some((Err(e), tweets)),
+        };
+
+        let mut thread = vec![first];
+        while let Some(Ok(next)) = tweets.as_mut().peek().await {
+            if !same_thread(&thread[0], next) {
+                break;
+            }
+            if let Some(Ok(next)) = tweets.next().await {
+                thread.push(next);
+            }
+        }
+
+        thread.sort_by_key(|t| t.id);
+        let root = thread[0].conversation_id.unwrap_or(thread[0].id);
+        Some((
+            Ok(Thread {
+                root,
+                tweets: thread,
+            }),
+            tweets,
+        ))
+    })
+}
+
+fn same_thread(a: &Tweet, b: &Tweet) -> bool {
+    // Retweets show someone else's tweet, they never continue a thread
+    !a.retweet
+        && !b.retweet
+        && a.conversation_id.is_some()
+        && a.conversation_id == b.conversation_id
+        && a.user.screen_name.eq_ignore_ascii_case(&b.user.screen_name)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::coverage::FieldCoverage;
+    use crate::parse::{parse_nitter_html, ParseOptions};
+    use crate::tweet::TimelineItem;
+
+    /// Synthetic timeline item with only the markup the parser reads
+    fn timeline_item(user: &str, id: u128, extra: &str) -> String {
+        format!(
+            r#"<div class="timeline-item">
+                <a class="fullname" href="/{user}" title="{user}">{user}</a>
+                <span class="tweet-date"><a href="/{user}/status/{id}#m" title="Jan 1, 2023 · 1:00 PM UTC">1 Jan</a></span>
+                <div class="tweet-content media-body">text</div>
+                {extra}
+            </div>"#
+        )
+    }
+
+    #[tokio::test]
+    async fn test_threads() {
+        let html = format!(
+            r#"<html><body><div class="timeline">{}<div class="thread-line">{}{}{}</div>{}</div></body></html>"#,
+            timeline_item("user", 300, ""),
+            timeline_item("user", 200, ""),
+            timeline_item("user", 201, ""),
+            timeline_item("user", 202, ""),
+            timeline_item(
+                "user",
+                150,
+                r#"<a class="show-thread" href="/i/status/100">Show this thread</a>"#
+            ),
+        );
+        let (items, _) = parse_nitter_html(
+            html,
+            &ParseOptions::default(),
+            &mut FieldCoverage::default(),
+        )
+        .unwrap();
+        let tweets = items.into_iter().filter_map(|item| match item {
+            TimelineItem::Tweet(tweet) => Some(Ok(tweet)),
+            _ => None,
+        });
+
+        let threads: Vec<_> = threads(futures_util::stream::iter(tweets))
+            .map(|thread| thread.unwrap())
+            .collect()
+            .await;
+        let ids: Vec<Vec<_>> = threads
+            .iter()
+            .map(|thread| thread.tweets.iter().map(|t| t.id).collect())
+            .collect();
+        assert_eq!(ids, [vec![300], vec![200, 201, 202], vec![150]]);
+        assert_eq!(threads[0].root, 300);
+        assert_eq!(threads[1].root, 200);
+        assert_eq!(threads[2].root, 100);
+    }
+}
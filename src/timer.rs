@@ -0,0 +1,58 @@
+//! Timers of the scraper, tokio's, or the browser's on wasm32 where there's no tokio runtime to
+//! drive them
+
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) use tokio::time::{sleep, sleep_until, timeout, timeout_at, Instant};
+#[cfg(target_arch = "wasm32")]
+pub(crate) use wasm::{sleep, sleep_until, timeout, timeout_at, Instant};
+
+/// The timer's instant for an instant of the public API
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn instant(instant: web_time::Instant) -> Instant {
+    Instant::from_std(instant)
+}
+
+#[cfg(target_arch = "wasm32")]
+pub(crate) fn instant(instant: web_time::Instant) -> Instant {
+    instant
+}
+
+#[cfg(target_arch = "wasm32")]
+mod wasm {
+    use std::future::Future;
+    use std::pin::pin;
+    use std::time::Duration;
+
+    use futures_timer::Delay;
+    use futures_util::future::{select, Either};
+    pub(crate) use web_time::Instant;
+
+    /// Error of a future that didn't complete in time
+    #[derive(Debug)]
+    pub(crate) struct Elapsed;
+
+    pub(crate) async fn sleep(duration: Duration) {
+        Delay::new(duration).await
+    }
+
+    pub(crate) async fn sleep_until(deadline: Instant) {
+        sleep(deadline.saturating_duration_since(Instant::now())).await
+    }
+
+    pub(crate) async fn timeout<F: Future>(
+        duration: Duration,
+        future: F,
+    ) -> Result<F::Output, Elapsed> {
+        match select(pin!(future), pin!(sleep(duration))).await {
+            Either::Left((output, _)) => Ok(output),
+            Either::Right(_) => Err(Elapsed),
+        }
+    }
+
+    pub(crate) async fn timeout_at<F: Future>(
+        deadline: Instant,
+        future: F,
+    ) -> Result<F::Output, Elapsed> {
+        timeout(deadline.saturating_duration_since(Instant::now()), future).await
+    }
+}
@@ -1,39 +1,168 @@
 use serde::Serialize;
 
 #[derive(Debug, Serialize)]
+#[non_exhaustive]
 pub struct Tweet {
     pub id: u128,
     pub id_str: String,
     pub created_at: String,
     pub created_at_ts: i64,
+    pub created_at_ms: i64,
     pub user: User,
     pub full_text: String,
+    pub full_text_markdown: String,
     pub images: Vec<String>,
     pub video: Option<Video>,
+    pub video_thumbnail: Option<String>,
+    /// Instance proxied URLs of `images`, only with [`MediaUrls::Both`](crate::MediaUrls::Both)
+    pub proxied_images: Option<Vec<String>>,
+    /// Instance proxied URL of `video_thumbnail`, only with
+    /// [`MediaUrls::Both`](crate::MediaUrls::Both)
+    pub proxied_video_thumbnail: Option<String>,
+    pub attachments: Vec<Attachment>,
     pub links: Vec<String>,
+    pub expanded_links: Option<Vec<String>>,
     pub retweet: bool,
+    /// Screen name of the account that retweeted it, known on user timelines and RSS feeds
+    pub retweeted_by: Option<String>,
     pub reply: bool,
     pub quote: bool,
+    /// ID of the quoted tweet
+    pub quoted_id: Option<u128>,
+    /// The quoted tweet itself, only fetched with `resolve_quotes`
+    pub quoted_tweet: Option<Box<Tweet>>,
     pub pinned: bool,
+    pub possibly_sensitive: bool,
+    pub is_thread_start: bool,
+    pub conversation_id: Option<u128>,
     pub stats: Stats,
+    pub author_profile: Option<ProfileStats>,
+    pub page: Option<u32>,
+    pub position: Option<u32>,
+    pub raw_html: Option<String>,
 }
 
+/// An item of a timeline, in the order Nitter displays them
+// Almost all items are tweets, boxing them would only add allocations
+#[allow(clippy::large_enum_variant)]
 #[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+#[non_exhaustive]
+pub enum TimelineItem {
+    Tweet(Tweet),
+    Unavailable(UnavailableTweet),
+    User(UserSummary),
+    Disappeared(DisappearedTweet),
+}
+
+/// Placeholder for a deleted, withheld, or otherwise unavailable tweet
+#[derive(Debug, Serialize)]
+#[non_exhaustive]
+pub struct UnavailableTweet {
+    pub page: Option<u32>,
+    pub position: Option<u32>,
+    pub reason: String,
+}
+
+/// A tweet seen while following a timeline that no longer resolves, e.g. because it was deleted
+#[derive(Debug, Serialize)]
+#[non_exhaustive]
+pub struct DisappearedTweet {
+    pub id: u128,
+    pub id_str: String,
+    /// Screen name of the tweet's author
+    pub screen_name: String,
+}
+
+/// A user search result
+#[derive(Debug, Serialize)]
+#[non_exhaustive]
+pub struct UserSummary {
+    pub screen_name: String,
+    pub full_name: String,
+    pub verified_type: Option<VerifiedType>,
+    pub bio: Option<String>,
+    /// Only shown by some instances
+    pub followers: Option<u64>,
+    pub page: Option<u32>,
+    pub position: Option<u32>,
+}
+
+#[derive(Debug, Serialize)]
+#[non_exhaustive]
 pub struct User {
     pub full_name: String,
     pub screen_name: String,
+    pub verified: bool,
+    pub verified_type: Option<VerifiedType>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+#[non_exhaustive]
+pub enum VerifiedType {
+    Blue,
+    Business,
+    Government,
+    Legacy,
 }
 
 #[derive(Debug, Serialize)]
+#[non_exhaustive]
 pub struct Video {
     pub poster: String,
     pub url: String,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+#[non_exhaustive]
+pub enum Attachment {
+    #[non_exhaustive]
+    Image { url: String },
+    #[non_exhaustive]
+    Gif {
+        url: Option<String>,
+        poster: Option<String>,
+    },
+    #[non_exhaustive]
+    Video {
+        url: Option<String>,
+        poster: Option<String>,
+    },
+}
+
 #[derive(Debug, Serialize)]
+#[non_exhaustive]
 pub struct Stats {
     pub comment: u64,
     pub retweet: u64,
     pub quote: u64,
     pub heart: u64,
+    pub views: Option<u64>,
+}
+
+/// A user's profile header
+#[derive(Debug, Serialize)]
+#[non_exhaustive]
+pub struct Profile {
+    pub screen_name: String,
+    pub full_name: String,
+    pub verified_type: Option<VerifiedType>,
+    pub bio: Option<String>,
+    pub location: Option<String>,
+    pub website: Option<String>,
+    pub joined_at: Option<String>,
+    pub joined_at_ts: Option<i64>,
+    pub protected: bool,
+    pub stats: ProfileStats,
+}
+
+#[derive(Debug, Default, Clone, Serialize)]
+#[non_exhaustive]
+pub struct ProfileStats {
+    pub tweets: u64,
+    pub following: u64,
+    pub followers: u64,
+    pub likes: u64,
 }
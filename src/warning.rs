@@ -0,0 +1,46 @@
+use std::time::Duration;
+
+/// A non-fatal issue encountered while scraping
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Warning {
+    /// The instance rate limited a request, which is retried after `delay`
+    RateLimited { status: u16, delay: Duration },
+    /// The instance returned a page without tweets, which is fetched again
+    EmptyPage { page: u32, attempt: u32 },
+    /// The instance returned the cursor of the current page again, pagination ends early
+    RepeatedCursor { cursor: String },
+    /// The RSS feed couldn't be read, HTML pages are scraped instead
+    RssUnavailable { reason: String },
+    /// A quoted tweet couldn't be fetched, its `quoted_tweet` is left empty
+    QuoteUnavailable { id: u128, reason: String },
+}
+
+impl std::fmt::Display for Warning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::RateLimited { status, delay } => write!(
+                f,
+                "Received status code {}, sleeping for {} seconds",
+                status,
+                delay.as_secs()
+            ),
+            Self::EmptyPage { page, attempt } => {
+                write!(f, "Page {} was empty, retrying (attempt {})", page, attempt)
+            }
+            Self::RepeatedCursor { cursor } => {
+                write!(f, "Cursor {:?} was repeated, stopping pagination", cursor)
+            }
+            Self::RssUnavailable { reason } => {
+                write!(
+                    f,
+                    "RSS feed unavailable ({}), scraping HTML instead",
+                    reason
+                )
+            }
+            Self::QuoteUnavailable { id, reason } => {
+                write!(f, "Could not fetch quoted tweet {} ({})", id, reason)
+            }
+        }
+    }
+}